@@ -54,6 +54,10 @@ pub const PATHOLOGICAL_DEFEAT_SIMPLE_VECTOR_FREQ: &'static str =
     include_str!("../data/pathological/defeat-simple-vector-freq.txt");
 pub const PATHOLOGICAL_DEFEAT_SIMPLE_VECTOR_REPEATED: &'static str =
     include_str!("../data/pathological/defeat-simple-vector-repeated.txt");
+pub const PATHOLOGICAL_DEFEAT_SIMPLE_VECTOR_FREQ_REV: &'static str =
+    include_str!("../data/pathological/defeat-simple-vector-freq-rev.txt");
+pub const PATHOLOGICAL_DEFEAT_SIMPLE_VECTOR_REPEATED_REV: &'static str =
+    include_str!("../data/pathological/defeat-simple-vector-repeated-rev.txt");
 
 pub const SLICESLICE_I386: &'static str =
     include_str!("../data/sliceslice/i386.txt");
@@ -124,3 +124,33 @@ pub fn memrchr3_count(b1: u8, b2: u8, b3: u8, haystack: &[u8]) -> usize {
     }
     count
 }
+
+pub fn naive_rev1_count(b1: u8, haystack: &[u8]) -> usize {
+    let mut count = 0;
+    let mut end = haystack.len();
+    while let Some(i) = naive::memrchr(b1, &haystack[..end]) {
+        count += 1;
+        end = i;
+    }
+    count
+}
+
+pub fn naive_rev2_count(b1: u8, b2: u8, haystack: &[u8]) -> usize {
+    let mut count = 0;
+    let mut end = haystack.len();
+    while let Some(i) = naive::memrchr2(b1, b2, &haystack[..end]) {
+        count += 1;
+        end = i;
+    }
+    count
+}
+
+pub fn naive_rev3_count(b1: u8, b2: u8, b3: u8, haystack: &[u8]) -> usize {
+    let mut count = 0;
+    let mut end = haystack.len();
+    while let Some(i) = naive::memrchr3(b1, b2, b3, &haystack[..end]) {
+        count += 1;
+        end = i;
+    }
+    count
+}
@@ -7,6 +7,7 @@ use crate::{
             fallback1_count, fallback2_count, fallback3_count, memchr1_count,
             memchr2_count, memchr3_count, memrchr1_count, memrchr2_count,
             memrchr3_count, naive1_count, naive2_count, naive3_count,
+            naive_rev1_count, naive_rev2_count, naive_rev3_count,
         },
         inputs::{Input, Search1, Search2, Search3, EMPTY, HUGE, SMALL, TINY},
     },
@@ -640,6 +641,49 @@ pub fn all(c: &mut Criterion) {
         );
     }
 
+    define_memchr_input1(c, "memrchr1/naive/huge", HUGE, move |search, b| {
+        b.iter(|| {
+            assert_eq!(
+                search.byte1.count,
+                naive_rev1_count(search.byte1.byte, search.corpus)
+            );
+        });
+    });
+    define_memchr_input1(
+        c,
+        "memrchr1/naive/small",
+        SMALL,
+        move |search, b| {
+            b.iter(|| {
+                assert_eq!(
+                    search.byte1.count,
+                    naive_rev1_count(search.byte1.byte, search.corpus)
+                );
+            });
+        },
+    );
+    define_memchr_input1(c, "memrchr1/naive/tiny", TINY, move |search, b| {
+        b.iter(|| {
+            assert_eq!(
+                search.byte1.count,
+                naive_rev1_count(search.byte1.byte, search.corpus)
+            );
+        });
+    });
+    define_memchr_input1(
+        c,
+        "memrchr1/naive/empty",
+        EMPTY,
+        move |search, b| {
+            b.iter(|| {
+                assert_eq!(
+                    search.byte1.count,
+                    naive_rev1_count(search.byte1.byte, search.corpus)
+                );
+            });
+        },
+    );
+
     define_memchr_input2(c, "memrchr2/krate/huge", HUGE, move |search, b| {
         b.iter(|| {
             assert_eq!(
@@ -699,6 +743,65 @@ pub fn all(c: &mut Criterion) {
         },
     );
 
+    define_memchr_input2(c, "memrchr2/naive/huge", HUGE, move |search, b| {
+        b.iter(|| {
+            assert_eq!(
+                search.byte1.count + search.byte2.count,
+                naive_rev2_count(
+                    search.byte1.byte,
+                    search.byte2.byte,
+                    search.corpus,
+                )
+            );
+        });
+    });
+    define_memchr_input2(
+        c,
+        "memrchr2/naive/small",
+        SMALL,
+        move |search, b| {
+            b.iter(|| {
+                assert_eq!(
+                    search.byte1.count + search.byte2.count,
+                    naive_rev2_count(
+                        search.byte1.byte,
+                        search.byte2.byte,
+                        search.corpus,
+                    )
+                );
+            });
+        },
+    );
+    define_memchr_input2(c, "memrchr2/naive/tiny", TINY, move |search, b| {
+        b.iter(|| {
+            assert_eq!(
+                search.byte1.count + search.byte2.count,
+                naive_rev2_count(
+                    search.byte1.byte,
+                    search.byte2.byte,
+                    search.corpus,
+                )
+            );
+        });
+    });
+    define_memchr_input2(
+        c,
+        "memrchr2/naive/empty",
+        EMPTY,
+        move |search, b| {
+            b.iter(|| {
+                assert_eq!(
+                    search.byte1.count + search.byte2.count,
+                    naive_rev2_count(
+                        search.byte1.byte,
+                        search.byte2.byte,
+                        search.corpus,
+                    )
+                );
+            });
+        },
+    );
+
     define_memchr_input3(c, "memrchr3/krate/huge", HUGE, move |search, b| {
         b.iter(|| {
             assert_eq!(
@@ -765,6 +868,73 @@ pub fn all(c: &mut Criterion) {
             });
         },
     );
+
+    define_memchr_input3(c, "memrchr3/naive/huge", HUGE, move |search, b| {
+        b.iter(|| {
+            assert_eq!(
+                search.byte1.count + search.byte2.count + search.byte3.count,
+                naive_rev3_count(
+                    search.byte1.byte,
+                    search.byte2.byte,
+                    search.byte3.byte,
+                    search.corpus,
+                )
+            );
+        });
+    });
+    define_memchr_input3(
+        c,
+        "memrchr3/naive/small",
+        SMALL,
+        move |search, b| {
+            b.iter(|| {
+                assert_eq!(
+                    search.byte1.count
+                        + search.byte2.count
+                        + search.byte3.count,
+                    naive_rev3_count(
+                        search.byte1.byte,
+                        search.byte2.byte,
+                        search.byte3.byte,
+                        search.corpus,
+                    )
+                );
+            });
+        },
+    );
+    define_memchr_input3(c, "memrchr3/naive/tiny", TINY, move |search, b| {
+        b.iter(|| {
+            assert_eq!(
+                search.byte1.count + search.byte2.count + search.byte3.count,
+                naive_rev3_count(
+                    search.byte1.byte,
+                    search.byte2.byte,
+                    search.byte3.byte,
+                    search.corpus,
+                )
+            );
+        });
+    });
+    define_memchr_input3(
+        c,
+        "memrchr3/naive/empty",
+        EMPTY,
+        move |search, b| {
+            b.iter(|| {
+                assert_eq!(
+                    search.byte1.count
+                        + search.byte2.count
+                        + search.byte3.count,
+                    naive_rev3_count(
+                        search.byte1.byte,
+                        search.byte2.byte,
+                        search.byte3.byte,
+                        search.corpus,
+                    )
+                );
+            });
+        },
+    );
 }
 
 fn define_memchr_input1<'i>(
@@ -254,4 +254,31 @@ pub const INPUTS: &'static [Input] = &[
         }],
         common: &[],
     },
+    // The inputs below are the reverse of some of the inputs above, such that
+    // the only match is at the beginning of the haystack. This makes them
+    // useful for measuring reverse search (and the reverse prefilter in
+    // particular), since a reverse search on the inputs above finds its match
+    // immediately.
+    Input {
+        name: "pathological-defeat-simple-vector-freq-rev",
+        corpus: data::PATHOLOGICAL_DEFEAT_SIMPLE_VECTOR_FREQ_REV,
+        never: &[],
+        rare: &[Query {
+            name: "alphabet",
+            needle: "zaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaajq",
+            count: 1,
+        }],
+        common: &[],
+    },
+    Input {
+        name: "pathological-defeat-simple-vector-repeated-rev",
+        corpus: data::PATHOLOGICAL_DEFEAT_SIMPLE_VECTOR_REPEATED_REV,
+        never: &[],
+        rare: &[Query {
+            name: "alphabet",
+            needle: "zazzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz",
+            count: 1,
+        }],
+        common: &[],
+    },
 ];
@@ -64,6 +64,10 @@ follows:
     iter-prebuilt
       Counts the total number of matches. This does not measure the time it
       takes to build the searcher.
+    count-prebuilt
+      Counts the total number of matches with `Finder::count`, which doesn't
+      suspend the search between matches. This does not measure the time it
+      takes to build the searcher. Only this crate is benchmarked here.
 
   corpus
     A brief name describing the corpus or haystack used in the benchmark. In
@@ -105,6 +109,7 @@ pub fn all(c: &mut Criterion) {
     prebuilt(c);
     oneshot_iter(c);
     prebuilt_iter(c);
+    prebuilt_count(c);
     sliceslice::all(c);
 }
 
@@ -381,3 +386,41 @@ fn prebuilt_iter(c: &mut Criterion) {
         }
     }
 }
+
+fn prebuilt_count(c: &mut Criterion) {
+    // Like prebuilt_iter, this is only interesting when there is more than
+    // one match. It's particularly meant to highlight dense matches, such as
+    // the one-space queries, where the cost of restarting the search after
+    // every match dominates when iterating.
+    let config = "prebuiltcount";
+    for inp in INPUTS {
+        let queries = inp.rare.iter().map(|q| ("rare", q));
+        let queries = queries.chain(inp.common.iter().map(|q| ("common", q)));
+        for (freq, q) in queries {
+            if q.count <= 1 {
+                continue;
+            }
+            let name = format!(
+                "memmem/krate/{config}/{inp}/{freq}-{q}",
+                config = config,
+                inp = inp.name,
+                freq = freq,
+                q = q.name,
+            );
+            define(
+                c,
+                &name,
+                inp.corpus.as_bytes(),
+                Box::new(move |b| {
+                    let finder = memchr::memmem::Finder::new(q.needle);
+                    b.iter(|| {
+                        assert_eq!(
+                            q.count,
+                            finder.count(inp.corpus.as_bytes())
+                        );
+                    });
+                }),
+            );
+        }
+    }
+}
@@ -8,7 +8,8 @@ fn main() {
 // This adds various simd cfgs if this compiler and target support it.
 //
 // This can be disabled with RUSTFLAGS="--cfg memchr_disable_auto_simd", but
-// this is generally only intended for testing.
+// this is generally only intended for testing. The 'minimal' feature disables
+// it too, for when code size matters more than speed.
 //
 // On targets which don't feature SSE2, this is disabled, as LLVM wouln't know
 // how to work with SSE2 operands. Enabling SSE4.2 and AVX on SSE2-only targets
@@ -16,6 +17,7 @@ fn main() {
 // runtime.
 fn enable_simd_optimizations() {
     if is_env_set("CARGO_CFG_MEMCHR_DISABLE_AUTO_SIMD")
+        || is_feature_set("MINIMAL")
         || !target_has_feature("sse2")
     {
         return;
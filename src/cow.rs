@@ -9,14 +9,15 @@ use core::ops;
 #[derive(Clone, Debug)]
 pub struct CowBytes<'a>(Imp<'a>);
 
-// N.B. We don't use std::borrow::Cow here since we can get away with a
-// Box<[u8]> for our use case, which is 1/3 smaller than the Vec<u8> that
-// a Cow<[u8]> would use.
+// N.B. We don't use std::borrow::Cow here since Cow is not in core, and we
+// need the no-std variant below to have the same interface. The owned variant
+// is a Vec<u8> rather than a Box<[u8]> so that its allocation can be reused
+// by `set_owned` for needles of different lengths.
 #[cfg(feature = "std")]
 #[derive(Clone, Debug)]
 enum Imp<'a> {
     Borrowed(&'a [u8]),
-    Owned(Box<[u8]>),
+    Owned(Vec<u8>),
 }
 
 #[cfg(not(feature = "std"))]
@@ -39,13 +40,54 @@ impl<'a> CowBytes<'a> {
         CowBytes(Imp::new(bytes.as_ref()))
     }
 
+    /// Create a new borrowed CowBytes for the empty byte string.
+    ///
+    /// Unlike `new`, this can be used in a constant expression.
+    #[inline(always)]
+    pub const fn empty() -> CowBytes<'static> {
+        // Older compilers reject an unsizing cast in a const fn, so give the
+        // empty slice its type up front.
+        const EMPTY: &[u8] = &[];
+        CowBytes(Imp::new(EMPTY))
+    }
+
     /// Create a new owned CowBytes.
     #[cfg(feature = "std")]
     #[inline(always)]
-    pub fn new_owned(bytes: Box<[u8]>) -> CowBytes<'static> {
+    pub fn new_owned(bytes: Vec<u8>) -> CowBytes<'static> {
         CowBytes(Imp::Owned(bytes))
     }
 
+    /// Create a new CowBytes from a standard library `Cow`, preserving
+    /// whether it is borrowed or owned.
+    ///
+    /// An owned vector is moved in as is, without copying it.
+    #[cfg(feature = "std")]
+    #[inline(always)]
+    pub fn from_cow(cow: std::borrow::Cow<'a, [u8]>) -> CowBytes<'a> {
+        match cow {
+            std::borrow::Cow::Borrowed(b) => CowBytes(Imp::Borrowed(b)),
+            std::borrow::Cow::Owned(v) => CowBytes(Imp::Owned(v)),
+        }
+    }
+
+    /// Replace the contents of this byte string with an owned copy of the
+    /// given bytes.
+    ///
+    /// If this is already an owned byte string, then its allocation is
+    /// reused, and only grows when `bytes` doesn't fit in its capacity.
+    /// Otherwise, a new one is made.
+    #[cfg(feature = "std")]
+    #[inline(always)]
+    pub fn set_owned(&mut self, bytes: &[u8]) {
+        if let Imp::Owned(ref mut v) = self.0 {
+            v.clear();
+            v.extend_from_slice(bytes);
+            return;
+        }
+        *self = CowBytes::new_owned(bytes.to_vec());
+    }
+
     /// Return a borrowed byte string, regardless of whether this is an owned
     /// or borrowed byte string internally.
     #[inline(always)]
@@ -61,7 +103,7 @@ impl<'a> CowBytes<'a> {
     #[inline(always)]
     pub fn into_owned(self) -> CowBytes<'static> {
         match self.0 {
-            Imp::Borrowed(b) => CowBytes::new_owned(Box::from(b)),
+            Imp::Borrowed(b) => CowBytes::new_owned(b.to_vec()),
             Imp::Owned(b) => CowBytes::new_owned(b),
         }
     }
@@ -70,13 +112,13 @@ impl<'a> CowBytes<'a> {
 impl<'a> Imp<'a> {
     #[cfg(feature = "std")]
     #[inline(always)]
-    pub fn new(bytes: &'a [u8]) -> Imp<'a> {
+    pub const fn new(bytes: &'a [u8]) -> Imp<'a> {
         Imp::Borrowed(bytes)
     }
 
     #[cfg(not(feature = "std"))]
     #[inline(always)]
-    pub fn new(bytes: &'a [u8]) -> Imp<'a> {
+    pub const fn new(bytes: &'a [u8]) -> Imp<'a> {
         Imp(bytes)
     }
 
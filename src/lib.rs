@@ -142,15 +142,30 @@ standard library exposes a platform independent SIMD API.
 * **std** - When enabled (the default), this will permit this crate to use
   features specific to the standard library. Currently, the only thing used
   from the standard library is runtime SIMD CPU feature detection. This means
-  that this feature must be enabled to get AVX accelerated routines. When
-  `std` is not enabled, this crate will still attempt to use SSE2 accelerated
-  routines on `x86_64`.
+  that this feature must be enabled to get AVX accelerated routines, unless
+  AVX2 is enabled at compile time (e.g., with `-C target-feature=+avx2`).
+  When `std` is not enabled, this crate will still attempt to use SSE2
+  accelerated routines on `x86_64`.
 * **libc** - When enabled (**not** the default), this library will use your
   platform's libc implementation of `memchr` (and `memrchr` on Linux). This
   can be useful on non-`x86_64` targets where the fallback implementation in
   this crate is not as good as the one found in your libc. All other routines
   (e.g., `memchr[23]` and substring search) unconditionally use the
   implementation in this crate.
+* **instrumentation** - When enabled (**not** the default), this exposes
+  `memmem::Finder::find_instrumented`, which reports statistics about how
+  effective a prefilter was during a search. This is only useful for
+  performance analysis.
+* **no-rabinkarp** - When enabled (**not** the default), the Rabin-Karp
+  substring search routines are compiled out, and Two-Way is used for short
+  haystacks instead. This reduces code size at the cost of slower searches
+  of short haystacks.
+* **minimal** - When enabled (**not** the default), all vector routines are
+  compiled out, and only the portable scalar implementations are used. The
+  public API is unchanged. This reduces code size at the cost of much slower
+  searches, and has no effect on targets other than `x86_64`, which have no
+  vector routines to begin with. Combine it with **no-rabinkarp** for the
+  smallest build.
 */
 
 #![deny(missing_docs)]
@@ -168,10 +183,21 @@ standard library exposes a platform independent SIMD API.
 )))]
 compile_error!("memchr currently not supported on non-{16,32,64}");
 
+#[cfg(feature = "std")]
+pub use crate::memchr::LineStreamer;
 pub use crate::memchr::{
-    memchr, memchr2, memchr2_iter, memchr3, memchr3_iter, memchr_iter,
-    memrchr, memrchr2, memrchr2_iter, memrchr3, memrchr3_iter, memrchr_iter,
-    Memchr, Memchr2, Memchr3,
+    find_byte_in, find_u16_be, find_u16_le, find_u32_be, find_u32_le, is_all,
+    memchr, memchr16, memchr2, memchr2_count, memchr2_iter, memchr2_which,
+    memchr3, memchr3_count, memchr3_iter, memchr3_which, memchr_byteset,
+    memchr_cancellable, memchr_classify, memchr_dual, memchr_grid,
+    memchr_high_bit, memchr_iter, memchr_mask, memchr_masks, memchr_not_range,
+    memchr_nth, memchr_nz, memchr_range, memchr_ring, memchr_runs,
+    memchr_split, memchr_split_indices, memchr_summary, memchr_unescaped,
+    memcmp_idx, memcmp_ridx, memrchr, memrchr2, memrchr2_iter, memrchr3,
+    memrchr3_iter, memrchr_batch, memrchr_byteset, memrchr_iter, memrchr_nz,
+    prefer_avoid_avx, rfind_byte_in, simd_available, ByteSet, Cancelled,
+    Memchr, Memchr2, Memchr3, MemchrRuns, MemchrSplit, MemchrSplitIndices,
+    Side, Summary,
 };
 
 mod cow;
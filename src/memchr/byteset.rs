@@ -0,0 +1,72 @@
+/// A set of bytes, represented as a 256-bit bitset.
+///
+/// This is used for searching for any of an arbitrary number of bytes with
+/// [`memchr_byteset`](crate::memchr_byteset) and
+/// [`memrchr_byteset`](crate::memrchr_byteset). Unlike the byte slices given
+/// to [`find_byte_in`](crate::find_byte_in), a set can be built once and
+/// reused for many searches.
+///
+/// # Example
+///
+/// ```
+/// use memchr::ByteSet;
+///
+/// let mut set = ByteSet::new(b" \t");
+/// set.add(b'\n');
+/// assert!(set.contains(b'\n'));
+/// assert!(!set.contains(b'x'));
+/// assert_eq!(3, set.len());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ByteSet {
+    bits: [u64; 4],
+}
+
+impl ByteSet {
+    /// Create a new set containing each of the given bytes.
+    pub fn new(bytes: &[u8]) -> ByteSet {
+        let mut set = ByteSet::default();
+        for &b in bytes {
+            set.add(b);
+        }
+        set
+    }
+
+    /// Add the given byte to this set.
+    pub fn add(&mut self, byte: u8) {
+        self.bits[usize::from(byte >> 6)] |= 1 << (byte & 0x3F);
+    }
+
+    /// Return true if and only if the given byte is in this set.
+    #[inline(always)]
+    pub fn contains(&self, byte: u8) -> bool {
+        self.bits[usize::from(byte >> 6)] & (1 << (byte & 0x3F)) != 0
+    }
+
+    /// Returns the number of distinct bytes in this set.
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns true if and only if this set contains no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.bits == [0; 4]
+    }
+
+    /// Write the bytes in this set to `out` in ascending order, and return
+    /// how many there are. If there are more than fit in `out`, then `None`
+    /// is returned and the contents of `out` are unspecified.
+    pub(crate) fn write_members(&self, out: &mut [u8]) -> Option<usize> {
+        let mut len = 0;
+        for (i, &word) in self.bits.iter().enumerate() {
+            let mut word = word;
+            while word != 0 {
+                *out.get_mut(len)? =
+                    (i * 64) as u8 + word.trailing_zeros() as u8;
+                word &= word - 1;
+                len += 1;
+            }
+        }
+        Some(len)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::memchr::memchr;
+
+/// The error returned when a search by [`memchr_cancellable`] is cancelled.
+///
+/// It records how much of the haystack was searched without finding the
+/// needle, so that a search may be resumed later from that point.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cancelled {
+    searched: usize,
+}
+
+impl Cancelled {
+    /// Returns the number of bytes at the start of the haystack that were
+    /// searched before cancellation. None of them are the needle.
+    pub fn searched(&self) -> usize {
+        self.searched
+    }
+}
+
+/// Search for the first occurrence of a byte in a slice, periodically
+/// checking whether to give up.
+///
+/// The haystack is searched `chunk` bytes at a time, and `should_continue`
+/// is called after each chunk that doesn't contain the needle, before the
+/// next one is searched. If it returns `false`, then the search stops and
+/// [`Cancelled`] is returned. Otherwise, this returns the same result as
+/// [`memchr`](crate::memchr).
+///
+/// `should_continue` is never called once the needle is found, or after the
+/// last chunk. Each chunk is searched with the vectorized `memchr` routine,
+/// so chunks should be big (many kilobytes) to keep the overhead of
+/// stopping and starting low.
+///
+/// # Panics
+///
+/// This panics when `chunk` is zero.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_cancellable;
+///
+/// let haystack = vec![b'a'; 1 << 20];
+/// let mut checks = 0;
+/// let result = memchr_cancellable(b'z', &haystack, 1 << 16, || {
+///     checks += 1;
+///     checks < 4
+/// });
+/// let cancelled = result.unwrap_err();
+/// assert_eq!(4 << 16, cancelled.searched());
+///
+/// let result = memchr_cancellable(b'z', b"abcz", 2, || true);
+/// assert_eq!(Ok(Some(3)), result);
+/// ```
+#[inline]
+pub fn memchr_cancellable<F: FnMut() -> bool>(
+    needle: u8,
+    haystack: &[u8],
+    chunk: usize,
+    mut should_continue: F,
+) -> Result<Option<usize>, Cancelled> {
+    assert!(chunk > 0, "chunk size must be non-zero");
+    let mut at = 0;
+    for piece in haystack.chunks(chunk) {
+        if at > 0 && !should_continue() {
+            return Err(Cancelled { searched: at });
+        }
+        if let Some(i) = memchr(needle, piece) {
+            return Ok(Some(at + i));
+        }
+        at += piece.len();
+    }
+    Ok(None)
+}
@@ -0,0 +1,79 @@
+/// The number of haystack bytes whose classes are combined before checking
+/// whether any of them is interesting.
+const BLOCK_LEN: usize = 8;
+
+/// Search for the first byte in a slice belonging to any of a set of
+/// classes.
+///
+/// Each byte `b` of the haystack belongs to the classes given by the bits of
+/// `table[b]`. This returns the index of the first byte `b` for which
+/// `table[b] & classes != 0`, along with `table[b]`, or `None` if there is no
+/// such byte. A byte may belong to more than one class, and the classes
+/// returned are all of the classes of the byte, not only those in
+/// `classes`.
+///
+/// This is the usual inner loop of a table driven lexer: find the next byte
+/// that needs attention, and then dispatch on what kind of byte it is. The
+/// table is looked up for every byte of the haystack, so unlike
+/// [`memchr`](crate::memchr), this doesn't use vector instructions. It does
+/// combine the classes of several bytes at a time, so that the common case
+/// of a run of uninteresting bytes is a sequence of lookups with few
+/// branches. When only a handful of distinct bytes are interesting, one of
+/// the `memchr` routines or [`find_byte_in`](crate::find_byte_in) is usually
+/// faster.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_classify;
+///
+/// const QUOTE: u8 = 1 << 0;
+/// const ESCAPE: u8 = 1 << 1;
+/// const SPACE: u8 = 1 << 2;
+///
+/// let mut table = [0u8; 256];
+/// table[b'"' as usize] = QUOTE;
+/// table[b'\\' as usize] = ESCAPE;
+/// table[b' ' as usize] = SPACE;
+/// table[b'\n' as usize] = SPACE;
+///
+/// let haystack = br#"say "a\"b" now"#;
+/// assert_eq!(Some((3, SPACE)), memchr_classify(&table, haystack, !0));
+/// let strings = QUOTE | ESCAPE;
+/// assert_eq!(Some((4, QUOTE)), memchr_classify(&table, haystack, strings));
+/// assert_eq!(Some((6, ESCAPE)), memchr_classify(&table, haystack, ESCAPE));
+/// assert_eq!(None, memchr_classify(&table, b"abc", !0));
+/// ```
+#[inline]
+pub fn memchr_classify(
+    table: &[u8; 256],
+    haystack: &[u8],
+    classes: u8,
+) -> Option<(usize, u8)> {
+    let mut chunks = haystack.chunks_exact(BLOCK_LEN);
+    let mut at = 0;
+    for chunk in chunks.by_ref() {
+        let any = chunk.iter().fold(0, |any, &b| any | table[b as usize]);
+        if any & classes != 0 {
+            return find_class(table, chunk, classes)
+                .map(|(i, c)| (at + i, c));
+        }
+        at += BLOCK_LEN;
+    }
+    find_class(table, chunks.remainder(), classes).map(|(i, c)| (at + i, c))
+}
+
+/// Find the first byte of `haystack` in any of `classes`, one byte at a
+/// time.
+#[inline(always)]
+fn find_class(
+    table: &[u8; 256],
+    haystack: &[u8],
+    classes: u8,
+) -> Option<(usize, u8)> {
+    haystack
+        .iter()
+        .map(|&b| table[b as usize])
+        .enumerate()
+        .find(|&(_, class)| class & classes != 0)
+}
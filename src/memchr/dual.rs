@@ -0,0 +1,84 @@
+use core::cmp;
+
+use crate::memchr::memchr;
+
+/// The number of bytes searched in each haystack before switching to the
+/// other one.
+///
+/// This bounds the amount of wasted work when a match occurs early in one
+/// haystack but late (or never) in the other. It's big enough that the
+/// vectorized `memchr` routines get to do most of the work.
+const CHUNK_LEN: usize = 512;
+
+/// Which of two haystacks a match was found in.
+///
+/// This is returned by [`memchr_dual`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    /// The match was found in the first haystack.
+    First,
+    /// The match was found in the second haystack.
+    Second,
+}
+
+/// Search for the first occurrence of a byte at the same index in either of
+/// two slices.
+///
+/// This returns the smallest index `i` such that either `haystack1[i]` or
+/// `haystack2[i] == needle`, along with the haystack in which the match
+/// occurred. If the byte occurs at index `i` in both haystacks, then
+/// [`Side::First`] is reported. If neither haystack contains the byte, then
+/// `None` is returned.
+///
+/// The haystacks need not have the same length. Each one is only searched up
+/// to its own length.
+///
+/// This is useful when a single logical sequence is stored as two parallel
+/// arrays (e.g., the even and odd positions split apart), since it avoids
+/// searching the entirety of one haystack when the other has an earlier
+/// match.
+///
+/// # Example
+///
+/// ```
+/// use memchr::{memchr_dual, Side};
+///
+/// let evens = b"acegi";
+/// let odds = b"bdfhj";
+/// assert_eq!(Some((2, Side::Second)), memchr_dual(b'f', evens, odds));
+/// assert_eq!(Some((4, Side::First)), memchr_dual(b'i', evens, odds));
+/// assert_eq!(None, memchr_dual(b'z', evens, odds));
+/// ```
+#[inline]
+pub fn memchr_dual(
+    needle: u8,
+    haystack1: &[u8],
+    haystack2: &[u8],
+) -> Option<(usize, Side)> {
+    let len = cmp::max(haystack1.len(), haystack2.len());
+    let mut at = 0;
+    while at < len {
+        let end = cmp::min(len, at + CHUNK_LEN);
+        let chunk1 = chunk(haystack1, at, end);
+        let found1 = memchr(needle, chunk1);
+        // If we found a match in the first haystack, then we only need to
+        // look for an earlier match in the second. A match at the same
+        // position is reported as belonging to the first haystack.
+        let end2 = found1.map_or(end, |i| at + i);
+        if let Some(i) = memchr(needle, chunk(haystack2, at, end2)) {
+            return Some((at + i, Side::Second));
+        }
+        if let Some(i) = found1 {
+            return Some((at + i, Side::First));
+        }
+        at = end;
+    }
+    None
+}
+
+/// Return `haystack[start..end]`, clamped to the bounds of `haystack`.
+#[inline(always)]
+fn chunk(haystack: &[u8], start: usize, end: usize) -> &[u8] {
+    let end = cmp::min(end, haystack.len());
+    haystack.get(start..end).unwrap_or(&[])
+}
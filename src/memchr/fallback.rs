@@ -4,6 +4,8 @@
 
 use core::{cmp, usize};
 
+use super::{byteset::ByteSet, summary::Summary};
+
 #[cfg(target_pointer_width = "16")]
 const USIZE_BYTES: usize = 2;
 
@@ -43,6 +45,27 @@ fn repeat_byte(b: u8) -> usize {
     (b as usize) * (usize::MAX / 255)
 }
 
+/// Return `true` if `x` contains any zero 16-bit unit.
+///
+/// This is the same trick as `contains_zero_byte`, applied to lanes that are
+/// twice as wide.
+#[inline(always)]
+fn contains_zero_u16(x: usize) -> bool {
+    const LO_U64: u64 = 0x0001000100010001;
+    const HI_U64: u64 = 0x8000800080008000;
+
+    const LO_USIZE: usize = LO_U64 as usize;
+    const HI_USIZE: usize = HI_U64 as usize;
+
+    x.wrapping_sub(LO_USIZE) & !x & HI_USIZE != 0
+}
+
+/// Repeat the given 16-bit unit into a word size number.
+#[inline(always)]
+fn repeat_u16(u: u16) -> usize {
+    (u as usize) * (usize::MAX / 0xFFFF)
+}
+
 pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = repeat_byte(n1);
     let confirm = |byte| byte == n1;
@@ -83,6 +106,12 @@ pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
 
 /// Like `memchr`, but searches for two bytes instead of one.
 pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    memchr2_which(n1, n2, haystack).map(|(i, _)| i)
+}
+
+/// Like `memchr2`, but also returns the byte that was found.
+#[inline(always)]
+pub fn memchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
     let vn1 = repeat_byte(n1);
     let vn2 = repeat_byte(n2);
     let confirm = |byte| byte == n1 || byte == n2;
@@ -93,14 +122,14 @@ pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
     unsafe {
         let end_ptr = start_ptr.add(haystack.len());
         if haystack.len() < USIZE_BYTES {
-            return forward_search(start_ptr, end_ptr, ptr, confirm);
+            return forward_search_which(start_ptr, end_ptr, ptr, confirm);
         }
 
         let chunk = (ptr as *const usize).read_unaligned();
         let eq1 = contains_zero_byte(chunk ^ vn1);
         let eq2 = contains_zero_byte(chunk ^ vn2);
         if eq1 || eq2 {
-            return forward_search(start_ptr, end_ptr, ptr, confirm);
+            return forward_search_which(start_ptr, end_ptr, ptr, confirm);
         }
 
         ptr = ptr.add(USIZE_BYTES - (start_ptr as usize & align));
@@ -117,12 +146,23 @@ pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
             }
             ptr = ptr.add(USIZE_BYTES);
         }
-        forward_search(start_ptr, end_ptr, ptr, confirm)
+        forward_search_which(start_ptr, end_ptr, ptr, confirm)
     }
 }
 
 /// Like `memchr`, but searches for three bytes instead of one.
 pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    memchr3_which(n1, n2, n3, haystack).map(|(i, _)| i)
+}
+
+/// Like `memchr3`, but also returns the byte that was found.
+#[inline(always)]
+pub fn memchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
     let vn1 = repeat_byte(n1);
     let vn2 = repeat_byte(n2);
     let vn3 = repeat_byte(n3);
@@ -134,7 +174,7 @@ pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
     unsafe {
         let end_ptr = start_ptr.add(haystack.len());
         if haystack.len() < USIZE_BYTES {
-            return forward_search(start_ptr, end_ptr, ptr, confirm);
+            return forward_search_which(start_ptr, end_ptr, ptr, confirm);
         }
 
         let chunk = (ptr as *const usize).read_unaligned();
@@ -142,7 +182,7 @@ pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
         let eq2 = contains_zero_byte(chunk ^ vn2);
         let eq3 = contains_zero_byte(chunk ^ vn3);
         if eq1 || eq2 || eq3 {
-            return forward_search(start_ptr, end_ptr, ptr, confirm);
+            return forward_search_which(start_ptr, end_ptr, ptr, confirm);
         }
 
         ptr = ptr.add(USIZE_BYTES - (start_ptr as usize & align));
@@ -160,7 +200,7 @@ pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
             }
             ptr = ptr.add(USIZE_BYTES);
         }
-        forward_search(start_ptr, end_ptr, ptr, confirm)
+        forward_search_which(start_ptr, end_ptr, ptr, confirm)
     }
 }
 
@@ -283,6 +323,229 @@ pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
     }
 }
 
+/// Write a bitset of the positions of `n1` in `haystack` to `out`, where bit
+/// `i % 64` of `out[i / 64]` corresponds to `haystack[i]`.
+///
+/// Callers must ensure that `out` has at least `ceil(haystack.len() / 64)`
+/// words. Any words after that are left untouched.
+pub fn memchr_mask(n1: u8, haystack: &[u8], out: &mut [u64]) {
+    for (chunk, word) in haystack.chunks(64).zip(out.iter_mut()) {
+        let mut bits = 0u64;
+        for (i, &b) in chunk.iter().enumerate() {
+            bits |= ((b == n1) as u64) << i;
+        }
+        *word = bits;
+    }
+}
+
+/// Call `f` with the offset and the mask of the positions of `n1` of each 64
+/// byte window of `haystack`, in order.
+pub fn memchr_masks<F: FnMut(usize, u64)>(n1: u8, haystack: &[u8], mut f: F) {
+    for (i, chunk) in haystack.chunks(64).enumerate() {
+        let mut mask = 0u64;
+        for (j, &b) in chunk.iter().enumerate() {
+            mask |= ((b == n1) as u64) << j;
+        }
+        f(i * 64, mask);
+    }
+}
+
+/// Count the occurrences of `n1` in `haystack`, and find the first and last
+/// of them, in a single pass.
+pub fn memchr_summary(n1: u8, haystack: &[u8]) -> Summary {
+    let mut summary = Summary::default();
+    memchr_masks(n1, haystack, |at, mask| summary.add_mask(at, mask));
+    summary
+}
+
+/// Count the positions in `haystack` that hold either `n1` or `n2`.
+///
+/// This is a simple loop over each byte, which the compiler tends to
+/// vectorize on its own.
+pub fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&b| b == n1 || b == n2).count()
+}
+
+/// Count the positions in `haystack` that hold any of `n1`, `n2` or `n3`.
+pub fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&b| b == n1 || b == n2 || b == n3).count()
+}
+
+/// Search for the first byte in the inclusive range `lo..=hi`.
+///
+/// Callers must ensure that `lo <= hi`.
+pub fn memchr_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    debug_assert!(lo <= hi);
+    // Shifting the range to start at zero lets us use a single comparison.
+    let width = hi - lo;
+    haystack.iter().position(|&b| b.wrapping_sub(lo) <= width)
+}
+
+/// Search for the first byte not in the inclusive range `lo..=hi`.
+///
+/// Callers must ensure that `lo <= hi`.
+pub fn memchr_not_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    debug_assert!(lo <= hi);
+    let width = hi - lo;
+    haystack.iter().position(|&b| b.wrapping_sub(lo) > width)
+}
+
+/// Returns true if and only if every byte in `haystack` is equal to `n1`.
+///
+/// This compares a word at a time against `n1` repeated, and stops at the
+/// first word that differs.
+pub fn is_all(n1: u8, haystack: &[u8]) -> bool {
+    let vn1 = repeat_byte(n1);
+    let mut i = 0;
+    while i + USIZE_BYTES <= haystack.len() {
+        // SAFETY: The slice has at least USIZE_BYTES bytes starting at i.
+        let chunk = unsafe {
+            (haystack.as_ptr().add(i) as *const usize).read_unaligned()
+        };
+        if chunk != vn1 {
+            return false;
+        }
+        i += USIZE_BYTES;
+    }
+    haystack[i..].iter().all(|&b| b == n1)
+}
+
+/// Search for the first byte with its most significant bit set.
+///
+/// This checks a word at a time against a mask of every byte's high bit,
+/// and then finds the byte from the lowest set bit in memory order.
+pub fn memchr_high_bit(haystack: &[u8]) -> Option<usize> {
+    const HI_U64: u64 = 0x8080808080808080;
+    const HI_USIZE: usize = HI_U64 as usize;
+
+    let mut i = 0;
+    while i + USIZE_BYTES <= haystack.len() {
+        // SAFETY: The slice has at least USIZE_BYTES bytes starting at i.
+        let chunk = unsafe {
+            (haystack.as_ptr().add(i) as *const usize).read_unaligned()
+        };
+        let high = chunk & HI_USIZE;
+        if high != 0 {
+            // The first byte in memory is the least significant one on
+            // little endian targets, and the most significant one otherwise.
+            let bits = if cfg!(target_endian = "little") {
+                high.trailing_zeros()
+            } else {
+                high.leading_zeros()
+            };
+            return Some(i + bits as usize / 8);
+        }
+        i += USIZE_BYTES;
+    }
+    haystack[i..].iter().position(|&b| b >= 0x80).map(|j| i + j)
+}
+
+/// Return the index of the first byte at which `a` and `b` differ.
+///
+/// This compares a word at a time, and then finds the differing byte from
+/// the XOR of the two words.
+///
+/// Callers must ensure that `a.len() == b.len()`.
+pub fn memcmp_idx(a: &[u8], b: &[u8]) -> Option<usize> {
+    debug_assert_eq!(a.len(), b.len());
+    let mut i = 0;
+    while i + USIZE_BYTES <= a.len() {
+        // SAFETY: Both slices have at least USIZE_BYTES bytes starting at i.
+        let diff = unsafe {
+            let x = (a.as_ptr().add(i) as *const usize).read_unaligned();
+            let y = (b.as_ptr().add(i) as *const usize).read_unaligned();
+            x ^ y
+        };
+        if diff != 0 {
+            // The first byte in memory is the least significant one on
+            // little endian targets, and the most significant one otherwise.
+            let bits = if cfg!(target_endian = "little") {
+                diff.trailing_zeros()
+            } else {
+                diff.leading_zeros()
+            };
+            return Some(i + bits as usize / 8);
+        }
+        i += USIZE_BYTES;
+    }
+    a[i..].iter().zip(&b[i..]).position(|(x, y)| x != y).map(|j| i + j)
+}
+
+/// Return the index of the last byte at which `a` and `b` differ.
+///
+/// This is like `memcmp_idx`, except it compares words starting at the end
+/// of the slices, and finds the differing byte from the other end of the
+/// XOR of the two words.
+///
+/// Callers must ensure that `a.len() == b.len()`.
+pub fn memcmp_ridx(a: &[u8], b: &[u8]) -> Option<usize> {
+    debug_assert_eq!(a.len(), b.len());
+    let mut end = a.len();
+    while end >= USIZE_BYTES {
+        let i = end - USIZE_BYTES;
+        // SAFETY: Both slices have at least USIZE_BYTES bytes starting at i.
+        let diff = unsafe {
+            let x = (a.as_ptr().add(i) as *const usize).read_unaligned();
+            let y = (b.as_ptr().add(i) as *const usize).read_unaligned();
+            x ^ y
+        };
+        if diff != 0 {
+            // The last byte in memory is the most significant one on little
+            // endian targets, and the least significant one otherwise.
+            let bits = if cfg!(target_endian = "little") {
+                diff.leading_zeros()
+            } else {
+                diff.trailing_zeros()
+            };
+            return Some(end - 1 - bits as usize / 8);
+        }
+        end = i;
+    }
+    a[..end].iter().zip(&b[..end]).rposition(|(x, y)| x != y)
+}
+
+/// Search for the first occurrence of `n1` in a slice of 16-bit units.
+///
+/// Like `memchr`, this checks a word at a time for a matching unit, and then
+/// finds the match within that word one unit at a time.
+pub fn memchr16(n1: u16, haystack: &[u16]) -> Option<usize> {
+    const UNITS: usize = USIZE_BYTES / 2;
+
+    let vn1 = repeat_u16(n1);
+    let mut i = 0;
+    while i + UNITS <= haystack.len() {
+        // SAFETY: The slice has at least UNITS units starting at i.
+        let chunk = unsafe {
+            (haystack.as_ptr().add(i) as *const usize).read_unaligned()
+        };
+        if contains_zero_u16(chunk ^ vn1) {
+            break;
+        }
+        i += UNITS;
+    }
+    haystack[i..].iter().position(|&u| u == n1).map(|j| i + j)
+}
+
+/// Search for the first byte in `set`, using a `ByteSet` lookup table.
+pub fn find_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    memchr_byteset(&ByteSet::new(set), haystack)
+}
+
+/// Search for the last byte in `set`, using a `ByteSet` lookup table.
+pub fn rfind_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    memrchr_byteset(&ByteSet::new(set), haystack)
+}
+
+/// Search for the first byte in `set`.
+pub fn memchr_byteset(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| set.contains(b))
+}
+
+/// Search for the last byte in `set`.
+pub fn memrchr_byteset(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|&b| set.contains(b))
+}
+
 #[inline(always)]
 unsafe fn forward_search<F: Fn(u8) -> bool>(
     start_ptr: *const u8,
@@ -302,6 +565,28 @@ unsafe fn forward_search<F: Fn(u8) -> bool>(
     None
 }
 
+/// Like `forward_search`, but also returns the byte that was confirmed, so
+/// that it doesn't need to be loaded again.
+#[inline(always)]
+unsafe fn forward_search_which<F: Fn(u8) -> bool>(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    mut ptr: *const u8,
+    confirm: F,
+) -> Option<(usize, u8)> {
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr);
+
+    while ptr < end_ptr {
+        let byte = *ptr;
+        if confirm(byte) {
+            return Some((sub(ptr, start_ptr), byte));
+        }
+        ptr = ptr.offset(1);
+    }
+    None
+}
+
 #[inline(always)]
 unsafe fn reverse_search<F: Fn(u8) -> bool>(
     start_ptr: *const u8,
@@ -1,4 +1,9 @@
-use crate::{memchr, memchr2, memchr3, memrchr, memrchr2, memrchr3};
+use core::iter::FusedIterator;
+
+use crate::{
+    memchr, memchr2, memchr3, memchr_not_range, memrchr, memrchr2, memrchr3,
+    memrchr_batch,
+};
 
 macro_rules! iter_next {
     // Common code for the memchr iterators:
@@ -45,6 +50,42 @@ impl<'a> Memchr<'a> {
     pub fn new(needle: u8, haystack: &[u8]) -> Memchr<'_> {
         Memchr { needle: needle, haystack: haystack, position: 0 }
     }
+
+    /// Writes the positions of up to `out.len()` of the remaining
+    /// occurrences of the needle to `out`, starting from the back, and
+    /// returns how many were written.
+    ///
+    /// This reports the same positions, in the same order, as calling
+    /// `next_back` repeatedly, but uses [`memrchr_batch`] to do so with less
+    /// overhead per position. It may be mixed freely with calls to `next`
+    /// and `next_back`. Once this writes fewer than `out.len()` positions,
+    /// the iterator is exhausted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::Memchr;
+    ///
+    /// let mut it = Memchr::new(b'x', b"x.x.x.x.x");
+    /// let mut out = [0; 2];
+    /// assert_eq!(2, it.next_back_batch(&mut out));
+    /// assert_eq!([8, 6], out);
+    /// assert_eq!(Some(0), it.next());
+    /// assert_eq!(2, it.next_back_batch(&mut out));
+    /// assert_eq!([4, 2], out);
+    /// assert_eq!(0, it.next_back_batch(&mut out));
+    /// ```
+    #[inline]
+    pub fn next_back_batch(&mut self, out: &mut [usize]) -> usize {
+        let n = memrchr_batch(self.needle, self.haystack, out);
+        if n > 0 {
+            self.haystack = &self.haystack[..out[n - 1]];
+        }
+        for pos in &mut out[..n] {
+            *pos += self.position;
+        }
+        n
+    }
 }
 
 impl<'a> Iterator for Memchr<'a> {
@@ -68,6 +109,8 @@ impl<'a> DoubleEndedIterator for Memchr<'a> {
     }
 }
 
+impl<'a> FusedIterator for Memchr<'a> {}
+
 /// An iterator for `memchr2`.
 pub struct Memchr2<'a> {
     needle1: u8,
@@ -115,6 +158,8 @@ impl<'a> DoubleEndedIterator for Memchr2<'a> {
     }
 }
 
+impl<'a> FusedIterator for Memchr2<'a> {}
+
 /// An iterator for `memchr3`.
 pub struct Memchr3<'a> {
     needle1: u8,
@@ -171,3 +216,183 @@ impl<'a> DoubleEndedIterator for Memchr3<'a> {
         )
     }
 }
+
+impl<'a> FusedIterator for Memchr3<'a> {}
+
+/// An iterator for `memchr_runs`.
+pub struct MemchrRuns<'a> {
+    needle: u8,
+    // The haystack to iterate over
+    haystack: &'a [u8],
+    // The index
+    position: usize,
+}
+
+impl<'a> MemchrRuns<'a> {
+    /// Creates a new iterator that yields all maximal runs of needle in
+    /// haystack, as pairs of a starting position and a length.
+    #[inline]
+    pub fn new(needle: u8, haystack: &[u8]) -> MemchrRuns<'_> {
+        MemchrRuns { needle, haystack, position: 0 }
+    }
+}
+
+impl<'a> Iterator for MemchrRuns<'a> {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let start = memchr(self.needle, self.haystack)?;
+        let rest = &self.haystack[start..];
+        // The run ends at the first byte that isn't the needle, which is
+        // itself a vectorized search.
+        let len = memchr_not_range(self.needle, self.needle, rest)
+            .unwrap_or(rest.len());
+        let found_position = self.position + start;
+        self.haystack = &rest[len..];
+        self.position = found_position + len;
+        Some((found_position, len))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Runs are separated by at least one byte.
+        (0, Some((self.haystack.len() + 1) / 2))
+    }
+}
+
+impl<'a> FusedIterator for MemchrRuns<'a> {}
+
+/// An iterator for `memchr_split`.
+pub struct MemchrSplit<'a> {
+    needle: u8,
+    // The part of the haystack that hasn't been split yet, or `None` once
+    // every subslice has been yielded.
+    haystack: Option<&'a [u8]>,
+}
+
+impl<'a> MemchrSplit<'a> {
+    /// Creates a new iterator that yields the subslices of haystack
+    /// separated by needle.
+    #[inline]
+    pub fn new(needle: u8, haystack: &[u8]) -> MemchrSplit<'_> {
+        MemchrSplit { needle, haystack: Some(haystack) }
+    }
+}
+
+impl<'a> Iterator for MemchrSplit<'a> {
+    type Item = &'a [u8];
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let haystack = self.haystack?;
+        match memchr(self.needle, haystack) {
+            None => {
+                self.haystack = None;
+                Some(haystack)
+            }
+            Some(i) => {
+                self.haystack = Some(&haystack[i + 1..]);
+                Some(&haystack[..i])
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.haystack {
+            None => (0, Some(0)),
+            // Every byte may be the needle, giving one more subslice than
+            // there are bytes.
+            Some(haystack) => (1, haystack.len().checked_add(1)),
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for MemchrSplit<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let haystack = self.haystack?;
+        match memrchr(self.needle, haystack) {
+            None => {
+                self.haystack = None;
+                Some(haystack)
+            }
+            Some(i) => {
+                self.haystack = Some(&haystack[..i]);
+                Some(&haystack[i + 1..])
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for MemchrSplit<'a> {}
+
+/// An iterator for `memchr_split_indices`.
+pub struct MemchrSplitIndices<'a> {
+    needle: u8,
+    // The part of the haystack that hasn't been split yet, or `None` once
+    // every subslice has been yielded.
+    haystack: Option<&'a [u8]>,
+    // The offset of `haystack` in the original haystack.
+    position: usize,
+}
+
+impl<'a> MemchrSplitIndices<'a> {
+    /// Creates a new iterator that yields the subslices of haystack
+    /// separated by needle, along with the offset at which each starts.
+    #[inline]
+    pub fn new(needle: u8, haystack: &[u8]) -> MemchrSplitIndices<'_> {
+        MemchrSplitIndices { needle, haystack: Some(haystack), position: 0 }
+    }
+}
+
+impl<'a> Iterator for MemchrSplitIndices<'a> {
+    type Item = (usize, &'a [u8]);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, &'a [u8])> {
+        let haystack = self.haystack?;
+        let start = self.position;
+        match memchr(self.needle, haystack) {
+            None => {
+                self.haystack = None;
+                Some((start, haystack))
+            }
+            Some(i) => {
+                self.haystack = Some(&haystack[i + 1..]);
+                self.position = start + i + 1;
+                Some((start, &haystack[..i]))
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.haystack {
+            None => (0, Some(0)),
+            // Every byte may be the needle, giving one more subslice than
+            // there are bytes.
+            Some(haystack) => (1, haystack.len().checked_add(1)),
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for MemchrSplitIndices<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let haystack = self.haystack?;
+        match memrchr(self.needle, haystack) {
+            None => {
+                self.haystack = None;
+                Some((self.position, haystack))
+            }
+            Some(i) => {
+                self.haystack = Some(&haystack[..i]);
+                Some((self.position + i + 1, &haystack[i + 1..]))
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for MemchrSplitIndices<'a> {}
@@ -0,0 +1,111 @@
+use std::vec::Vec;
+
+use crate::memchr;
+
+/// A splitter of lines from a stream of chunks of bytes.
+///
+/// Chunks are given to the splitter with [`LineStreamer::push`], which
+/// reports every line completed by that chunk. Lines are terminated by `\n`,
+/// and may span any number of chunks. The bytes of an incomplete trailing
+/// line are buffered until a later chunk completes it, or until
+/// [`LineStreamer::finish`] is called at the end of the stream.
+///
+/// The lines reported never include their `\n` terminator. When
+/// [`LineStreamer::strip_cr`] is enabled, a `\r` immediately preceding the
+/// `\n` is removed as well.
+///
+/// Lines that are entirely contained within a single chunk are reported as
+/// slices of that chunk without copying them. Only a line spanning chunks
+/// is copied into an internal buffer.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::LineStreamer;
+///
+/// let mut lines = vec![];
+/// let mut streamer = LineStreamer::new();
+/// streamer.strip_cr(true);
+/// for chunk in &[&b"GET / HTTP/1.1\r\nHo"[..], b"st: a\r", b"\n\r\nbody"] {
+///     streamer.push(chunk, |line| lines.push(line.to_vec()));
+/// }
+/// streamer.finish(|line| lines.push(line.to_vec()));
+/// assert_eq!(lines, vec![
+///     b"GET / HTTP/1.1".to_vec(),
+///     b"Host: a".to_vec(),
+///     b"".to_vec(),
+///     b"body".to_vec(),
+/// ]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LineStreamer {
+    /// The bytes of the current line that were seen in previous chunks.
+    partial: Vec<u8>,
+    strip_cr: bool,
+}
+
+impl LineStreamer {
+    /// Create a new line splitter with an empty buffer.
+    ///
+    /// By default, `\r` is not stripped from the end of lines.
+    pub fn new() -> LineStreamer {
+        LineStreamer::default()
+    }
+
+    /// Whether to strip a `\r` immediately preceding each `\n` terminator.
+    ///
+    /// A `\r` at the end of a final line that isn't terminated by `\n` is
+    /// never stripped.
+    pub fn strip_cr(&mut self, yes: bool) -> &mut LineStreamer {
+        self.strip_cr = yes;
+        self
+    }
+
+    /// Split the given chunk into lines, calling `f` with each line that is
+    /// completed by it, in order.
+    ///
+    /// Any bytes following the last `\n` in the chunk are buffered, and are
+    /// reported as the beginning of the next line.
+    pub fn push<F: FnMut(&[u8])>(&mut self, chunk: &[u8], mut f: F) {
+        let mut rest = chunk;
+        while let Some(i) = memchr(b'\n', rest) {
+            if self.partial.is_empty() {
+                f(self.trim(&rest[..i]));
+            } else {
+                self.partial.extend_from_slice(&rest[..i]);
+                f(self.trim(&self.partial));
+                self.partial.clear();
+            }
+            rest = &rest[i + 1..];
+        }
+        self.partial.extend_from_slice(rest);
+    }
+
+    /// Signal the end of the stream, calling `f` with the final line if it
+    /// wasn't terminated by `\n`.
+    ///
+    /// `f` isn't called if the stream was empty or ended with `\n`. After
+    /// this returns, the buffer is empty and the splitter may be reused for
+    /// a new stream.
+    pub fn finish<F: FnOnce(&[u8])>(&mut self, f: F) {
+        if !self.partial.is_empty() {
+            f(&self.partial);
+            self.partial.clear();
+        }
+    }
+
+    /// Returns the bytes of the incomplete line buffered so far.
+    pub fn partial(&self) -> &[u8] {
+        &self.partial
+    }
+
+    /// Remove a trailing `\r` from the given line, if configured to do so.
+    fn trim<'a>(&self, line: &'a [u8]) -> &'a [u8] {
+        match line.split_last() {
+            Some((&b'\r', init)) if self.strip_cr => init,
+            _ => line,
+        }
+    }
+}
@@ -1,17 +1,44 @@
-use core::iter::Rev;
+#[cfg(all(
+    target_arch = "x86_64",
+    memchr_runtime_simd,
+    feature = "std",
+    not(miri),
+))]
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::{iter::Rev, num::NonZeroUsize};
 
-pub use self::iter::{Memchr, Memchr2, Memchr3};
+pub use self::byteset::ByteSet;
+pub use self::cancel::{memchr_cancellable, Cancelled};
+pub use self::classify::memchr_classify;
+pub use self::dual::{memchr_dual, Side};
+pub use self::iter::{
+    Memchr, Memchr2, Memchr3, MemchrRuns, MemchrSplit, MemchrSplitIndices,
+};
+#[cfg(feature = "std")]
+pub use self::lines::LineStreamer;
+pub use self::summary::Summary;
 
 // N.B. If you're looking for the cfg knobs for libc, see build.rs.
+mod byteset;
 #[cfg(memchr_libc)]
 mod c;
+mod cancel;
+mod classify;
+mod dual;
 #[allow(dead_code)]
 pub mod fallback;
 mod iter;
+#[cfg(feature = "std")]
+mod lines;
 pub mod naive;
+mod summary;
 #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
 mod x86;
 
+/// The largest set of bytes that `find_byte_in` and `rfind_byte_in` search
+/// for by comparing each byte individually. Larger sets use a `ByteSet`.
+const SMALL_SET_LEN: usize = 8;
+
 /// An iterator over all occurrences of the needle in a haystack.
 #[inline]
 pub fn memchr_iter(needle: u8, haystack: &[u8]) -> Memchr<'_> {
@@ -35,6 +62,83 @@ pub fn memchr3_iter(
     Memchr3::new(needle1, needle2, needle3, haystack)
 }
 
+/// An iterator over all maximal runs of the needle in a haystack.
+///
+/// Each item is a pair of the position at which a run starts and the number
+/// of consecutive occurrences of the needle beginning there. Every run is
+/// followed by a byte that isn't the needle, or the end of the haystack.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_runs;
+///
+/// let haystack = b"\t\tfoo\n\t\t\tbar\t";
+/// let runs: Vec<(usize, usize)> = memchr_runs(b'\t', haystack).collect();
+/// assert_eq!(runs, vec![(0, 2), (6, 3), (12, 1)]);
+/// ```
+#[inline]
+pub fn memchr_runs(needle: u8, haystack: &[u8]) -> MemchrRuns<'_> {
+    MemchrRuns::new(needle, haystack)
+}
+
+/// An iterator over the subslices of a haystack separated by the needle.
+///
+/// This behaves like `haystack.split(|&b| b == needle)`, but uses `memchr`
+/// to find each separator. The separators themselves are never yielded. A
+/// separator at the start or end of the haystack, or two adjacent
+/// separators, produce an empty subslice, and an empty haystack produces a
+/// single empty subslice.
+///
+/// The iterator is double ended, so `.rev()` yields the same subslices from
+/// last to first.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_split;
+///
+/// let fields: Vec<&[u8]> = memchr_split(b',', b"a,bc,,d,").collect();
+/// assert_eq!(fields, vec![&b"a"[..], b"bc", b"", b"d", b""]);
+/// ```
+#[inline]
+pub fn memchr_split(needle: u8, haystack: &[u8]) -> MemchrSplit<'_> {
+    MemchrSplit::new(needle, haystack)
+}
+
+/// An iterator over the subslices of a haystack separated by the needle,
+/// along with the offset in `haystack` at which each subslice starts.
+///
+/// This yields the same subslices as [`memchr_split`], so the same rules
+/// apply to separators at the edges of the haystack and to adjacent
+/// separators. The offset of an empty subslice is the position just after
+/// the separator that ends the previous subslice (or `0`, for the first
+/// one), which means `offset + subslice.len()` is always the position of the
+/// following separator, or the end of the haystack.
+///
+/// The iterator is double ended, so `.rev()` yields the same pairs from last
+/// to first.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_split_indices;
+///
+/// let fields: Vec<(usize, &[u8])> =
+///     memchr_split_indices(b',', b"a,bc,,d,").collect();
+/// assert_eq!(
+///     fields,
+///     vec![(0, &b"a"[..]), (2, b"bc"), (5, b""), (6, b"d"), (8, b"")],
+/// );
+/// ```
+#[inline]
+pub fn memchr_split_indices(
+    needle: u8,
+    haystack: &[u8],
+) -> MemchrSplitIndices<'_> {
+    MemchrSplitIndices::new(needle, haystack)
+}
+
 /// An iterator over all occurrences of the needle in a haystack, in reverse.
 #[inline]
 pub fn memrchr_iter(needle: u8, haystack: &[u8]) -> Rev<Memchr<'_>> {
@@ -62,59 +166,940 @@ pub fn memrchr3_iter(
     Memchr3::new(needle1, needle2, needle3, haystack).rev()
 }
 
+/// Returns true if and only if the routines in this crate that search for
+/// bytes, such as [`memchr`], use vector instructions in this process.
+///
+/// This reflects how this crate was compiled (e.g., for which target, and
+/// whether the `minimal` feature is enabled). When it returns `false`,
+/// searches use portable routines that work on a `usize` at a time, or the C
+/// library's `memchr` when the `libc` feature is enabled. (The latter may
+/// well be vectorized, but there is no way to tell.)
+///
+/// This is useful when deciding whether to search many tiny haystacks
+/// individually or to concatenate them first, since vectorized searches have
+/// more fixed overhead per call. The CPU's support for AVX2 doesn't change
+/// the result. It only decides which vectorized routines are used.
+///
+/// # Example
+///
+/// ```
+/// use memchr::simd_available;
+///
+/// if simd_available() {
+///     println!("memchr is vectorized");
+/// }
+/// ```
+#[inline]
+pub fn simd_available() -> bool {
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    {
+        x86::simd_available()
+    }
+    #[cfg(not(all(target_arch = "x86_64", memchr_runtime_simd, not(miri))))]
+    {
+        false
+    }
+}
+
+/// Whether AVX2 routines are skipped even when the CPU supports them. See
+/// `prefer_avoid_avx`.
+#[cfg(all(
+    target_arch = "x86_64",
+    memchr_runtime_simd,
+    feature = "std",
+    not(miri),
+))]
+static AVOID_AVX: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether this crate avoids its AVX2 routines, even on CPUs that
+/// support them.
+///
+/// When enabled, searches that would pick an AVX2 routine use the
+/// equivalent SSE2 routine instead. On some CPUs, sustained use of 256-bit
+/// vector instructions lowers the clock frequency of the core for a while
+/// afterwards, which slows down the code that runs after a search. This lets
+/// latency-sensitive programs trade some search throughput to avoid that.
+///
+/// Routines are picked once, so this should be called early, before any
+/// searches are run. In particular, it doesn't change the routines used by a
+/// [`memmem::Finder`](crate::memmem::Finder) that was already built, or by a
+/// function such as [`memchr`] that was already called once.
+///
+/// This only does anything on `x86_64` targets with vectorized routines
+/// enabled, and only when the `std` feature is enabled, since that's when
+/// routines are picked by detecting CPU features at runtime. Without `std`,
+/// AVX2 is only used when it's enabled at compile time, and the routines are
+/// called directly without reading this setting, so it is a no-op.
+///
+/// # Example
+///
+/// ```
+/// use memchr::{memchr, memmem, prefer_avoid_avx};
+///
+/// prefer_avoid_avx(true);
+/// assert_eq!(Some(4), memchr(b'q', b"the quick"));
+/// assert_eq!(Some(4), memmem::find(b"the quick", b"quick"));
+/// ```
+#[inline]
+pub fn prefer_avoid_avx(yes: bool) {
+    #[cfg(all(
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        feature = "std",
+        not(miri),
+    ))]
+    {
+        AVOID_AVX.store(yes, Ordering::Relaxed);
+    }
+    #[cfg(not(all(
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        feature = "std",
+        not(miri),
+    )))]
+    {
+        let _ = yes;
+    }
+}
+
+/// Returns true if AVX2 routines should not be picked, as requested with
+/// `prefer_avoid_avx`.
+#[cfg(all(
+    target_arch = "x86_64",
+    memchr_runtime_simd,
+    feature = "std",
+    not(miri),
+))]
+#[inline(always)]
+pub(crate) fn is_avx_avoided() -> bool {
+    AVOID_AVX.load(Ordering::Relaxed)
+}
+
 /// Search for the first occurrence of a byte in a slice.
 ///
 /// This returns the index corresponding to the first occurrence of `needle` in
 /// `haystack`, or `None` if one is not found. If an index is returned, it is
 /// guaranteed to be less than `usize::MAX`.
 ///
-/// While this is operationally the same as something like
-/// `haystack.iter().position(|&b| b == needle)`, `memchr` will use a highly
-/// optimized routine that can be up to an order of magnitude faster in some
-/// cases.
+/// While this is operationally the same as something like
+/// `haystack.iter().position(|&b| b == needle)`, `memchr` will use a highly
+/// optimized routine that can be up to an order of magnitude faster in some
+/// cases.
+///
+/// # Example
+///
+/// This shows how to find the first position of a byte in a byte string.
+///
+/// ```
+/// use memchr::memchr;
+///
+/// let haystack = b"the quick brown fox";
+/// assert_eq!(memchr(b'k', haystack), Some(8));
+/// ```
+#[inline]
+pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memchr(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memchr(n1, haystack)
+    }
+
+    #[cfg(all(
+        memchr_libc,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        c::memchr(n1, haystack)
+    }
+
+    #[cfg(all(
+        not(memchr_libc),
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memchr(n1, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle, haystack)
+    }
+}
+
+/// Like `memchr`, but returns one more than the index found.
+///
+/// Since the value returned is never zero, `Option<NonZeroUsize>` is the same
+/// size as a `usize`, where `None` is represented by `0`. This is convenient
+/// for FFI, where "not found" is often reported as `0`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_nz;
+///
+/// let haystack = b"the quick brown fox";
+/// let found = memchr_nz(b'k', haystack).map_or(0, |i| i.get());
+/// assert_eq!(found, 9);
+/// assert_eq!(memchr_nz(b'z', haystack), None);
+/// ```
+#[inline]
+pub fn memchr_nz(needle: u8, haystack: &[u8]) -> Option<NonZeroUsize> {
+    memchr(needle, haystack).and_then(|i| NonZeroUsize::new(i + 1))
+}
+
+/// Search for the `n`th occurrence of a byte in a slice, where `n` is
+/// zero-based.
+///
+/// This returns the index corresponding to the `n`th occurrence of `needle`
+/// in `haystack`, or `None` if there are `n` or fewer occurrences. This is
+/// equivalent to `memchr_iter(needle, haystack).nth(n)`.
+///
+/// # Example
+///
+/// This shows how to find the start of the third field in a comma delimited
+/// record.
+///
+/// ```
+/// use memchr::memchr_nth;
+///
+/// let record = b"foo,bar,baz,quux";
+/// assert_eq!(memchr_nth(b',', record, 1), Some(7));
+/// assert_eq!(memchr_nth(b',', record, 3), None);
+/// ```
+#[inline]
+pub fn memchr_nth(needle: u8, haystack: &[u8], n: usize) -> Option<usize> {
+    let mut pos = 0;
+    for _ in 0..n {
+        pos += memchr(needle, &haystack[pos..])? + 1;
+    }
+    memchr(needle, &haystack[pos..]).map(|i| pos + i)
+}
+
+/// Search for the first occurrence of any byte in the inclusive range
+/// `lo..=hi`.
+///
+/// This returns the index of the first byte `b` in `haystack` such that
+/// `lo <= b && b <= hi`, or `None` if there is no such byte. If `lo > hi`,
+/// then the range is empty and this always returns `None`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().position(|&b| lo <= b && b <= hi)`, `memchr_range` will
+/// use a vectorized routine when one is available. Searching a contiguous
+/// range of bytes is also cheaper than searching an arbitrary set of bytes.
+///
+/// # Example
+///
+/// This shows how to find the first ASCII control character.
+///
+/// ```
+/// use memchr::memchr_range;
+///
+/// let haystack = b"foo bar\tbaz\n";
+/// assert_eq!(memchr_range(0x00, 0x1F, haystack), Some(7));
+/// ```
+#[inline]
+pub fn memchr_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memchr_range(lo, hi, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memchr_range(lo, hi, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memchr_range(lo, hi, haystack)
+    }
+
+    if haystack.is_empty() || lo > hi {
+        None
+    } else {
+        imp(lo, hi, haystack)
+    }
+}
+
+/// Search for the first occurrence of any byte outside of the inclusive range
+/// `lo..=hi`.
+///
+/// This returns the index of the first byte `b` in `haystack` such that
+/// `b < lo || hi < b`, or `None` if there is no such byte. If `lo > hi`, then
+/// the range is empty and this returns `0` for any non-empty haystack.
+///
+/// # Example
+///
+/// This shows how to find the first byte that isn't printable ASCII.
+///
+/// ```
+/// use memchr::memchr_not_range;
+///
+/// let haystack = b"foo bar\tbaz\n";
+/// assert_eq!(memchr_not_range(0x20, 0x7E, haystack), Some(7));
+/// assert_eq!(memchr_not_range(0x20, 0x7E, b"foo bar"), None);
+/// ```
+#[inline]
+pub fn memchr_not_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memchr_not_range(lo, hi, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memchr_not_range(lo, hi, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memchr_not_range(lo, hi, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else if lo > hi {
+        Some(0)
+    } else {
+        imp(lo, hi, haystack)
+    }
+}
+
+/// Returns true if and only if every byte in `haystack` is equal to `byte`.
+///
+/// This is equivalent to `memchr_not_range(byte, byte, haystack).is_none()`,
+/// but is a bit faster since it doesn't need to report where the first
+/// differing byte is. This returns `true` for an empty haystack.
+///
+/// # Example
+///
+/// This shows how to check whether a buffer is entirely zeroed.
+///
+/// ```
+/// use memchr::is_all;
+///
+/// assert!(is_all(0, &[0; 100]));
+/// assert!(!is_all(0, b"\x00\x00\x01\x00"));
+/// assert!(is_all(b'z', b""));
+/// ```
+#[inline]
+pub fn is_all(byte: u8, haystack: &[u8]) -> bool {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(byte: u8, haystack: &[u8]) -> bool {
+        naive::is_all(byte, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(byte: u8, haystack: &[u8]) -> bool {
+        x86::is_all(byte, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(byte: u8, haystack: &[u8]) -> bool {
+        fallback::is_all(byte, haystack)
+    }
+
+    imp(byte, haystack)
+}
+
+/// Search for the first byte in `haystack` with its most significant bit
+/// set, i.e., the first byte that isn't ASCII.
+///
+/// This is equivalent to `memchr_range(0x80, 0xFF, haystack)`, but it's
+/// faster, since the high bit of each byte can be checked without comparing
+/// it to anything. This makes it a good first step for a UTF-8 validator,
+/// which can skip ahead over ASCII before looking more closely at what
+/// follows.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_high_bit;
+///
+/// assert_eq!(Some(2), memchr_high_bit("naïve".as_bytes()));
+/// assert_eq!(None, memchr_high_bit(b"plain ASCII"));
+/// ```
+#[inline]
+pub fn memchr_high_bit(haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(haystack: &[u8]) -> Option<usize> {
+        naive::memchr_high_bit(haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(haystack: &[u8]) -> Option<usize> {
+        x86::memchr_high_bit(haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(haystack: &[u8]) -> Option<usize> {
+        fallback::memchr_high_bit(haystack)
+    }
+
+    imp(haystack)
+}
+
+/// Write a bitset of every position at which a byte occurs in a slice.
+///
+/// Bit `i % 64` of `out[i / 64]` is set if and only if `haystack[i] ==
+/// needle`. Every other bit in the first `ceil(haystack.len() / 64)` words
+/// of `out` is cleared. Any words after that are left untouched.
+///
+/// This is much faster than setting a bit for each position reported by
+/// [`memchr_iter`] when matches are frequent, since the bitset can be built
+/// directly from the results of vectorized comparisons.
+///
+/// # Panics
+///
+/// This panics if `out` has fewer than `ceil(haystack.len() / 64)` words.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_mask;
+///
+/// let haystack = b"a,b,,c";
+/// let mut out = [0u64; 1];
+/// memchr_mask(b',', haystack, &mut out);
+/// assert_eq!(out[0], 0b011010);
+/// ```
+#[inline]
+pub fn memchr_mask(needle: u8, haystack: &[u8], out: &mut [u64]) {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8], out: &mut [u64]) {
+        naive::memchr_mask(n1, haystack, out)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8], out: &mut [u64]) {
+        x86::memchr_mask(n1, haystack, out)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8], out: &mut [u64]) {
+        fallback::memchr_mask(n1, haystack, out)
+    }
+
+    let words = (haystack.len() + 63) / 64;
+    assert!(
+        out.len() >= words,
+        "bitset has {} words but a haystack of length {} needs {}",
+        out.len(),
+        haystack.len(),
+        words,
+    );
+    imp(needle, haystack, out)
+}
+
+/// Call a closure with the mask of the positions at which a byte occurs in
+/// each 64 byte window of a slice.
+///
+/// For every window `haystack[at..at + 64]`, in order, `f` is called with
+/// `at` and a mask in which bit `i` is set if and only if `haystack[at + i]
+/// == needle`. The last window may be shorter than 64 bytes, in which case
+/// its bits past the end of the haystack are zero. `f` is never called for
+/// an empty haystack.
+///
+/// This gives access to the raw comparison masks, so that they can be
+/// combined with other bitmasks before resolving them into offsets. If all
+/// you need is the masks themselves, then [`memchr_mask`] writes them to a
+/// slice instead.
+///
+/// # Example
+///
+/// This finds the commas that aren't inside double quotes, assuming there
+/// are no escaped quotes, by combining the masks of both bytes.
+///
+/// ```
+/// use memchr::memchr_masks;
+///
+/// let haystack = br#"a,"b,c",d"#;
+/// let mut quotes = vec![];
+/// memchr_masks(b'"', haystack, |_, mask| quotes.push(mask));
+///
+/// let mut commas = vec![];
+/// let mut in_quotes = false;
+/// memchr_masks(b',', haystack, |at, mut mask| {
+///     let (mut quote_mask, mut unquoted) = (quotes[at / 64], 0);
+///     for i in 0..64 {
+///         if quote_mask & 1 != 0 {
+///             in_quotes = !in_quotes;
+///         }
+///         if mask & 1 != 0 && !in_quotes {
+///             unquoted |= 1 << i;
+///         }
+///         quote_mask >>= 1;
+///         mask >>= 1;
+///     }
+///     commas.push(unquoted);
+/// });
+/// assert_eq!(commas, vec![0b1000_0010]);
+/// ```
+#[inline]
+pub fn memchr_masks<F: FnMut(usize, u64)>(needle: u8, haystack: &[u8], f: F) {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp<F: FnMut(usize, u64)>(n1: u8, haystack: &[u8], f: F) {
+        naive::memchr_masks(n1, haystack, f)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp<F: FnMut(usize, u64)>(n1: u8, haystack: &[u8], f: F) {
+        x86::memchr_masks(n1, haystack, f)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp<F: FnMut(usize, u64)>(n1: u8, haystack: &[u8], f: F) {
+        fallback::memchr_masks(n1, haystack, f)
+    }
+
+    imp(needle, haystack, f)
+}
+
+/// Count the occurrences of a byte in a slice, and find the first and last
+/// of them.
+///
+/// This returns the same results as counting the items of [`memchr_iter`],
+/// [`memchr`] and [`memrchr`] together, but does so in a single pass over
+/// the haystack. Each block of the haystack is compared with the needle once,
+/// and the resulting mask of matching positions is used for all three.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_summary;
+///
+/// let haystack = b"a,b,,c";
+/// let summary = memchr_summary(b',', haystack);
+/// assert_eq!(3, summary.count());
+/// assert_eq!(Some(1), summary.first());
+/// assert_eq!(Some(4), summary.last());
+///
+/// let summary = memchr_summary(b'z', haystack);
+/// assert_eq!(0, summary.count());
+/// assert_eq!(None, summary.first());
+/// ```
+#[inline]
+pub fn memchr_summary(needle: u8, haystack: &[u8]) -> Summary {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Summary {
+        naive::memchr_summary(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Summary {
+        x86::memchr_summary(n1, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Summary {
+        fallback::memchr_summary(n1, haystack)
+    }
+
+    imp(needle, haystack)
+}
+
+/// Search for the first occurrence of any byte in a set.
+///
+/// This returns the index corresponding to the first byte in `haystack` that
+/// is equal to any byte in `set`, or `None` if there is no such byte.
+///
+/// This is a generalization of `memchr`, `memchr2` and `memchr3` to an
+/// arbitrary number of bytes, and is operationally the same as
+/// `haystack.iter().position(|b| set.contains(b))`. The strategy used depends
+/// on the size of the set: sets of 1, 2 or 3 bytes use `memchr`, `memchr2` or
+/// `memchr3`, sets of up to 8 bytes compare each byte against every haystack
+/// byte using vector instructions where available, and larger sets look up
+/// each haystack byte in a table. Duplicate bytes in `set` count towards its
+/// size.
+///
+/// # Example
+///
+/// ```
+/// use memchr::find_byte_in;
+///
+/// let haystack = b"key = \"value\"\n";
+/// assert_eq!(find_byte_in(haystack, b"\"\\\n"), Some(6));
+/// assert_eq!(find_byte_in(haystack, b"{}[]"), None);
+/// ```
+#[inline]
+pub fn find_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(haystack: &[u8], set: &[u8]) -> Option<usize> {
+        naive::find_byte_in(haystack, set)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(haystack: &[u8], set: &[u8]) -> Option<usize> {
+        x86::find_byte_in(haystack, set)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(haystack: &[u8], set: &[u8]) -> Option<usize> {
+        fallback::find_byte_in(haystack, set)
+    }
+
+    match *set {
+        [] => None,
+        [n1] => memchr(n1, haystack),
+        [n1, n2] => memchr2(n1, n2, haystack),
+        [n1, n2, n3] => memchr3(n1, n2, n3, haystack),
+        _ if set.len() <= SMALL_SET_LEN => imp(haystack, set),
+        _ => fallback::find_byte_in(haystack, set),
+    }
+}
+
+/// Search for the last occurrence of any byte in a set.
+///
+/// This returns the index corresponding to the last byte in `haystack` that
+/// is equal to any byte in `set`, or `None` if there is no such byte.
+///
+/// This is the reverse of [`find_byte_in`], and picks a strategy based on the
+/// size of the set in the same way.
+///
+/// # Example
+///
+/// ```
+/// use memchr::rfind_byte_in;
+///
+/// let haystack = b"src/memchr/mod.rs";
+/// assert_eq!(rfind_byte_in(haystack, b"/\\"), Some(10));
+/// ```
+#[inline]
+pub fn rfind_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(haystack: &[u8], set: &[u8]) -> Option<usize> {
+        naive::rfind_byte_in(haystack, set)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(haystack: &[u8], set: &[u8]) -> Option<usize> {
+        x86::rfind_byte_in(haystack, set)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(haystack: &[u8], set: &[u8]) -> Option<usize> {
+        fallback::rfind_byte_in(haystack, set)
+    }
+
+    match *set {
+        [] => None,
+        [n1] => memrchr(n1, haystack),
+        [n1, n2] => memrchr2(n1, n2, haystack),
+        [n1, n2, n3] => memrchr3(n1, n2, n3, haystack),
+        _ if set.len() <= SMALL_SET_LEN => imp(haystack, set),
+        _ => fallback::rfind_byte_in(haystack, set),
+    }
+}
+
+/// Search for the first occurrence of any byte in a [`ByteSet`].
+///
+/// This returns the index corresponding to the first byte in `haystack` that
+/// is in `set`, or `None` if there is no such byte.
+///
+/// This picks a strategy based on the size of the set in the same way as
+/// [`find_byte_in`]. Sets of up to 8 bytes are searched with vector
+/// instructions where available, while larger sets look up each haystack
+/// byte in the set's table.
+///
+/// # Example
+///
+/// ```
+/// use memchr::{memchr_byteset, ByteSet};
+///
+/// let space = ByteSet::new(b" \t\r\n");
+/// assert_eq!(memchr_byteset(&space, b"foo\tbar baz"), Some(3));
+/// assert_eq!(memchr_byteset(&space, b"foo"), None);
+/// ```
+#[inline]
+pub fn memchr_byteset(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    let mut small = [0u8; SMALL_SET_LEN];
+    match set.write_members(&mut small) {
+        Some(len) => find_byte_in(haystack, &small[..len]),
+        None => fallback::memchr_byteset(set, haystack),
+    }
+}
+
+/// Search for the last occurrence of any byte in a [`ByteSet`].
+///
+/// This returns the index corresponding to the last byte in `haystack` that
+/// is in `set`, or `None` if there is no such byte.
+///
+/// This is the reverse of [`memchr_byteset`], and picks a strategy based on
+/// the size of the set in the same way.
+///
+/// # Example
+///
+/// Trimming whitespace from the end of a line:
+///
+/// ```
+/// use memchr::{memrchr_byteset, ByteSet};
+///
+/// let space = ByteSet::new(b" \t\r\n");
+/// let mut notspace = ByteSet::default();
+/// for b in 0..=255u8 {
+///     if !space.contains(b) {
+///         notspace.add(b);
+///     }
+/// }
+/// let line = b"foo bar \t\r\n";
+/// let end = memrchr_byteset(&notspace, line).map_or(0, |i| i + 1);
+/// assert_eq!(b"foo bar", &line[..end]);
+/// ```
+#[inline]
+pub fn memrchr_byteset(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    let mut small = [0u8; SMALL_SET_LEN];
+    match set.write_members(&mut small) {
+        Some(len) => rfind_byte_in(haystack, &small[..len]),
+        None => fallback::memrchr_byteset(set, haystack),
+    }
+}
+
+/// Search for the first occurrence of a byte in a row-major grid.
+///
+/// `grid` is interpreted as consecutive rows of `width` bytes each. This
+/// returns the `(row, column)` coordinates of the first occurrence of
+/// `needle`, or `None` if it is not found. This is equivalent to converting
+/// the index returned by `memchr(needle, grid)` to `(i / width, i % width)`.
+///
+/// The length of `grid` need not be a multiple of `width`. If it isn't, then
+/// the trailing partial row is still searched, and coordinates of a match
+/// in that row are still computed with respect to `width`.
+///
+/// # Panics
+///
+/// This panics if `width` is `0`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_grid;
+///
+/// let grid = b"\
+///     ....\
+///     ..#.\
+///     .#..";
+/// assert_eq!(memchr_grid(b'#', grid, 4), Some((1, 2)));
+/// assert_eq!(memchr_grid(b'@', grid, 4), None);
+/// ```
+#[inline]
+pub fn memchr_grid(
+    needle: u8,
+    grid: &[u8],
+    width: usize,
+) -> Option<(usize, usize)> {
+    assert!(width > 0, "grid width must be non-zero");
+    memchr(needle, grid).map(|i| (i / width, i % width))
+}
+
+/// Returns the index of the first byte at which `a` and `b` differ.
+///
+/// This returns `None` if and only if `a == b`. When one slice is a proper
+/// prefix of the other, the index returned is the length of the shorter
+/// slice.
+///
+/// This compares many bytes at a time, and uses a vectorized routine when
+/// one is available.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memcmp_idx;
+///
+/// assert_eq!(memcmp_idx(b"foo bar", b"foo baz"), Some(6));
+/// assert_eq!(memcmp_idx(b"foo", b"foo bar"), Some(3));
+/// assert_eq!(memcmp_idx(b"foo", b"foo"), None);
+/// ```
+#[inline]
+pub fn memcmp_idx(a: &[u8], b: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(a: &[u8], b: &[u8]) -> Option<usize> {
+        naive::memcmp_idx(a, b)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(a: &[u8], b: &[u8]) -> Option<usize> {
+        x86::memcmp_idx(a, b)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(a: &[u8], b: &[u8]) -> Option<usize> {
+        fallback::memcmp_idx(a, b)
+    }
+
+    let len = core::cmp::min(a.len(), b.len());
+    match imp(&a[..len], &b[..len]) {
+        None if a.len() != b.len() => Some(len),
+        result => result,
+    }
+}
+
+/// Returns the index of the last byte at which `a` and `b` differ.
+///
+/// This is the reverse of [`memcmp_idx`]. Both slices are indexed from their
+/// start, so when they have different lengths, every index past the end of
+/// the shorter slice counts as a difference and the index returned is
+/// `max(a.len(), b.len()) - 1`. This returns `None` if and only if `a == b`.
+///
+/// Together with `memcmp_idx`, this can be used to find the common prefix
+/// and suffix of two slices of the same length, which is the usual first
+/// step of a diff.
+///
+/// # Example
+///
+/// ```
+/// use memchr::{memcmp_idx, memcmp_ridx};
+///
+/// let (a, b) = (b"foo bar baz", b"foo quux baz");
+/// assert_eq!(memcmp_ridx(b"foo bar", b"fob bar"), Some(2));
+/// assert_eq!(memcmp_ridx(b"foo", b"foo bar"), Some(6));
+/// assert_eq!(memcmp_ridx(b"foo", b"foo"), None);
+///
+/// // Trim the common prefix and suffix of two slices.
+/// let prefix = memcmp_idx(a, b).unwrap();
+/// let (a, b) = (&a[prefix..], &b[prefix..]);
+/// let n = core::cmp::min(a.len(), b.len());
+/// let (ta, tb) = (&a[a.len() - n..], &b[b.len() - n..]);
+/// let suffix = n - memcmp_ridx(ta, tb).map_or(0, |i| i + 1);
+/// assert_eq!(&a[..a.len() - suffix], b"bar");
+/// assert_eq!(&b[..b.len() - suffix], b"quux");
+/// ```
+#[inline]
+pub fn memcmp_ridx(a: &[u8], b: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(a: &[u8], b: &[u8]) -> Option<usize> {
+        naive::memcmp_ridx(a, b)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(a: &[u8], b: &[u8]) -> Option<usize> {
+        x86::memcmp_ridx(a, b)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(a: &[u8], b: &[u8]) -> Option<usize> {
+        fallback::memcmp_ridx(a, b)
+    }
+
+    if a.len() != b.len() {
+        return Some(core::cmp::max(a.len(), b.len()) - 1);
+    }
+    imp(a, b)
+}
+
+/// Search for the first occurrence of a 16-bit unit in a slice.
+///
+/// This is like [`memchr`], but for haystacks of wide characters such as
+/// UTF-16 code units. The index returned counts units, not bytes.
+///
+/// This uses a vectorized routine when one is available.
 ///
 /// # Example
 ///
-/// This shows how to find the first position of a byte in a byte string.
-///
 /// ```
-/// use memchr::memchr;
+/// use memchr::memchr16;
 ///
-/// let haystack = b"the quick brown fox";
-/// assert_eq!(memchr(b'k', haystack), Some(8));
+/// let haystack: Vec<u16> = "the quick brown fox".encode_utf16().collect();
+/// assert_eq!(memchr16(b'k' as u16, &haystack), Some(8));
+/// assert_eq!(memchr16(0x263A, &haystack), None);
 /// ```
 #[inline]
-pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+pub fn memchr16(needle: u16, haystack: &[u16]) -> Option<usize> {
     #[cfg(miri)]
     #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        naive::memchr(n1, haystack)
+    fn imp(n1: u16, haystack: &[u16]) -> Option<usize> {
+        naive::memchr16(n1, haystack)
     }
 
     #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
     #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        x86::memchr(n1, haystack)
-    }
-
-    #[cfg(all(
-        memchr_libc,
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        not(miri),
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        c::memchr(n1, haystack)
+    fn imp(n1: u16, haystack: &[u16]) -> Option<usize> {
+        x86::memchr16(n1, haystack)
     }
 
     #[cfg(all(
-        not(memchr_libc),
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
         not(miri),
     ))]
     #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        fallback::memchr(n1, haystack)
+    fn imp(n1: u16, haystack: &[u16]) -> Option<usize> {
+        fallback::memchr16(n1, haystack)
     }
 
     if haystack.is_empty() {
@@ -124,6 +1109,135 @@ pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
     }
 }
 
+/// Search for the first occurrence of a little endian 16-bit value in a
+/// byte slice.
+///
+/// This returns the first index `i` such that
+/// `haystack[i] | (haystack[i + 1] << 8) == value`. Unlike [`memchr16`],
+/// the haystack is a slice of bytes, and the value may start at any index,
+/// not just an even one. So occurrences may overlap with each other.
+///
+/// This is the same as [`memmem::find`](crate::memmem::find) with the bytes
+/// of `value` as the needle, and exists so that the byte order is explicit.
+///
+/// # Example
+///
+/// ```
+/// use memchr::find_u16_le;
+///
+/// let haystack = b"\x00\x34\x12\x00";
+/// assert_eq!(find_u16_le(0x1234, haystack), Some(1));
+/// assert_eq!(find_u16_le(0x3412, haystack), None);
+/// ```
+#[inline]
+pub fn find_u16_le(value: u16, haystack: &[u8]) -> Option<usize> {
+    crate::memmem::find(haystack, &value.to_le_bytes())
+}
+
+/// Search for the first occurrence of a big endian 16-bit value in a byte
+/// slice.
+///
+/// This returns the first index `i` such that
+/// `(haystack[i] << 8) | haystack[i + 1] == value`. See [`find_u16_le`] for
+/// more details.
+///
+/// # Example
+///
+/// ```
+/// use memchr::find_u16_be;
+///
+/// let haystack = b"\x00\x12\x34\x00";
+/// assert_eq!(find_u16_be(0x1234, haystack), Some(1));
+/// assert_eq!(find_u16_be(0x3412, haystack), None);
+/// ```
+#[inline]
+pub fn find_u16_be(value: u16, haystack: &[u8]) -> Option<usize> {
+    crate::memmem::find(haystack, &value.to_be_bytes())
+}
+
+/// Search for the first occurrence of a little endian 32-bit value in a
+/// byte slice.
+///
+/// This returns the first index `i` such that the four bytes starting at
+/// `haystack[i]`, read as a little endian integer, are equal to `value`. The
+/// value may start at any index, so occurrences may overlap with each other.
+/// See [`find_u16_le`] for more details.
+///
+/// # Example
+///
+/// ```
+/// use memchr::find_u32_le;
+///
+/// let haystack = b"\x00\x78\x56\x34\x12";
+/// assert_eq!(find_u32_le(0x12345678, haystack), Some(1));
+/// assert_eq!(find_u32_le(0x78563412, haystack), None);
+/// ```
+#[inline]
+pub fn find_u32_le(value: u32, haystack: &[u8]) -> Option<usize> {
+    crate::memmem::find(haystack, &value.to_le_bytes())
+}
+
+/// Search for the first occurrence of a big endian 32-bit value in a byte
+/// slice.
+///
+/// This returns the first index `i` such that the four bytes starting at
+/// `haystack[i]`, read as a big endian integer, are equal to `value`. See
+/// [`find_u16_le`] for more details.
+///
+/// # Example
+///
+/// ```
+/// use memchr::find_u32_be;
+///
+/// let haystack = b"\x00\x12\x34\x56\x78";
+/// assert_eq!(find_u32_be(0x12345678, haystack), Some(1));
+/// assert_eq!(find_u32_be(0x78563412, haystack), None);
+/// ```
+#[inline]
+pub fn find_u32_be(value: u32, haystack: &[u8]) -> Option<usize> {
+    crate::memmem::find(haystack, &value.to_be_bytes())
+}
+
+/// Search for the first occurrence of a byte in a ring buffer, in read order.
+///
+/// `buf` is interpreted as a ring buffer whose oldest byte is at `head`. That
+/// is, `buf[head..]` is searched first, followed by `buf[..head]`. This
+/// returns the distance from `head` in read order of the first occurrence of
+/// `needle`, or `None` if it is not found. An index `i` returned corresponds
+/// to `buf[(head + i) % buf.len()]`.
+///
+/// This is equivalent to searching a rotated copy of `buf`, but doesn't copy
+/// anything.
+///
+/// # Panics
+///
+/// This panics if `head > buf.len()`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_ring;
+///
+/// let ring = b"ef\nabcd";
+/// assert_eq!(memchr_ring(b'\n', ring, 3), Some(6));
+/// assert_eq!(memchr_ring(b'c', ring, 3), Some(2));
+/// assert_eq!(memchr_ring(b'z', ring, 3), None);
+/// ```
+#[inline]
+pub fn memchr_ring(needle: u8, buf: &[u8], head: usize) -> Option<usize> {
+    assert!(
+        head <= buf.len(),
+        "ring head {} is out of bounds for a buffer of length {}",
+        head,
+        buf.len(),
+    );
+    let (newer, older) = buf.split_at(head);
+    match memchr(needle, older) {
+        Some(i) => Some(i),
+        None => memchr(needle, newer).map(|i| older.len() + i),
+    }
+}
+
 /// Like `memchr`, but searches for either of two bytes instead of just one.
 ///
 /// This returns the index corresponding to the first occurrence of `needle1`
@@ -131,6 +1245,11 @@ pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
 /// earlier), or `None` if neither one is found. If an index is returned, it is
 /// guaranteed to be less than `usize::MAX`.
 ///
+/// That is, the index returned is always the minimum index of any match
+/// of any of the needles. The needles are not required to be distinct. For
+/// example, `memchr2(b'a', b'a', haystack)` is equivalent to
+/// `memchr(b'a', haystack)`.
+///
 /// While this is operationally the same as something like
 /// `haystack.iter().position(|&b| b == needle1 || b == needle2)`, `memchr2`
 /// will use a highly optimized routine that can be up to an order of magnitude
@@ -177,6 +1296,112 @@ pub fn memchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
     }
 }
 
+/// Like `memchr2`, but also returns which of the two bytes was matched.
+///
+/// This returns the index of the first occurrence of either `needle1` or
+/// `needle2` in `haystack` along with the byte found at that index, or `None`
+/// if neither is found.
+///
+/// This saves callers from re-reading `haystack[i]` and comparing it with
+/// each needle in order to dispatch on the byte that was found, since the
+/// search routine already knows which needle matched.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr2_which;
+///
+/// let haystack = b"the quick brown fox";
+/// assert_eq!(memchr2_which(b'k', b'q', haystack), Some((4, b'q')));
+/// ```
+#[inline]
+pub fn memchr2_which(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        naive::memchr2_which(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        x86::memchr2_which(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        fallback::memchr2_which(n1, n2, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, haystack)
+    }
+}
+
+/// Search for the first occurrence of `needle` that isn't escaped.
+///
+/// An occurrence is escaped when it's immediately preceded by an odd number
+/// of consecutive `escape` bytes. For example, with `"` as the needle and
+/// `\` as the escape, the quote in `\"` is escaped, but the one in `\\"`
+/// isn't, since its escape is itself escaped. This is how the end of a
+/// quoted string is found in many formats, including JSON.
+///
+/// Escapes are only counted from the start of `haystack`, so it should
+/// start at a position that is known not to be escaped, such as just after
+/// the opening quote.
+///
+/// If `needle == escape`, then there's nothing to escape, and this is the
+/// same as `memchr(needle, haystack)`.
+///
+/// This uses `memchr2` to jump between occurrences of either byte, so it's
+/// fast when both are rare.
+///
+/// # Example
+///
+/// This shows how to find the closing quote of a string.
+///
+/// ```
+/// use memchr::memchr_unescaped;
+///
+/// let haystack = br#"say \"hi\" \\" and more"#;
+/// assert_eq!(Some(13), memchr_unescaped(b'"', b'\\', haystack));
+/// assert_eq!(None, memchr_unescaped(b'"', b'\\', br#"a\""#));
+/// ```
+#[inline]
+pub fn memchr_unescaped(
+    needle: u8,
+    escape: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    if needle == escape {
+        return memchr(needle, haystack);
+    }
+    let mut pos = 0;
+    while let Some((i, b)) = memchr2_which(needle, escape, &haystack[pos..]) {
+        let i = pos + i;
+        if b == needle {
+            return Some(i);
+        }
+        // An escape escapes the byte after it, whatever it is, so skip both.
+        // This pairs up each run of escapes and leaves parity implicit.
+        pos = i + 2;
+        if pos >= haystack.len() {
+            return None;
+        }
+    }
+    None
+}
+
 /// Like `memchr`, but searches for any of three bytes instead of just one.
 ///
 /// This returns the index corresponding to the first occurrence of `needle1`,
@@ -184,6 +1409,11 @@ pub fn memchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
 /// `haystack` (whichever occurs earliest), or `None` if none are found. If an
 /// index is returned, it is guaranteed to be less than `usize::MAX`.
 ///
+/// That is, the index returned is always the minimum index of any match
+/// of any of the needles. The needles are not required to be distinct. For
+/// example, `memchr3(b'a', b'b', b'a', haystack)` is equivalent to
+/// `memchr2(b'a', b'b', haystack)`.
+///
 /// While this is operationally the same as something like
 /// `haystack.iter().position(|&b| b == needle1 || b == needle2 ||
 /// b == needle3)`, `memchr3` will use a highly optimized routine that can be
@@ -235,6 +1465,156 @@ pub fn memchr3(
     }
 }
 
+/// Like `memchr3`, but also returns which of the three bytes was matched.
+///
+/// This returns the index of the first occurrence of any of `needle1`,
+/// `needle2` or `needle3` in `haystack` along with the byte found at that
+/// index, or `None` if none are found.
+///
+/// This saves callers from re-reading `haystack[i]` and comparing it with
+/// each needle in order to dispatch on the byte that was found, since the
+/// search routine already knows which needle matched.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr3_which;
+///
+/// let haystack = b"the quick brown fox";
+/// assert_eq!(memchr3_which(b'k', b'q', b'e', haystack), Some((2, b'e')));
+/// ```
+#[inline]
+pub fn memchr3_which(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        naive::memchr3_which(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        x86::memchr3_which(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        fallback::memchr3_which(n1, n2, n3, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, needle3, haystack)
+    }
+}
+
+/// Count the positions in `haystack` that hold either `needle1` or
+/// `needle2`.
+///
+/// This is the same as adding up the number of occurrences of each needle,
+/// except that it makes a single pass over the haystack. Each position is
+/// counted at most once, so when `needle1 == needle2`, this is the number of
+/// occurrences of that one byte, not twice that.
+///
+/// This uses a vectorized routine when one is available.
+///
+/// # Example
+///
+/// This counts the line terminators in a haystack with both Unix and Windows
+/// line endings.
+///
+/// ```
+/// use memchr::memchr2_count;
+///
+/// let haystack = b"foo\r\nbar\nbaz\r\n";
+/// assert_eq!(memchr2_count(b'\n', b'\r', haystack), 5);
+/// assert_eq!(memchr2_count(b'\n', b'\n', haystack), 3);
+/// ```
+#[inline]
+pub fn memchr2_count(needle1: u8, needle2: u8, haystack: &[u8]) -> usize {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+        naive::memchr2_count(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+        x86::memchr2_count(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+        fallback::memchr2_count(n1, n2, haystack)
+    }
+
+    imp(needle1, needle2, haystack)
+}
+
+/// Count the positions in `haystack` that hold any of `needle1`, `needle2`
+/// or `needle3`.
+///
+/// As with [`memchr2_count`], this makes a single pass over the haystack,
+/// and each position is counted at most once, even when the needles aren't
+/// distinct.
+///
+/// This uses a vectorized routine when one is available.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr3_count;
+///
+/// let haystack = b"the quick brown fox";
+/// assert_eq!(memchr3_count(b'o', b'q', b' ', haystack), 6);
+/// assert_eq!(memchr3_count(b'o', b'o', b'o', haystack), 2);
+/// ```
+#[inline]
+pub fn memchr3_count(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> usize {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+        naive::memchr3_count(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+        x86::memchr3_count(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+        fallback::memchr3_count(n1, n2, n3, haystack)
+    }
+
+    imp(needle1, needle2, needle3, haystack)
+}
+
 /// Search for the last occurrence of a byte in a slice.
 ///
 /// This returns the index corresponding to the last occurrence of `needle` in
@@ -298,6 +1678,79 @@ pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
     }
 }
 
+/// Write the offsets of the last occurrences of a byte in a slice to a
+/// buffer, from the end of the slice toward its start.
+///
+/// This writes the offsets of up to `out.len()` occurrences of `needle` in
+/// `haystack`, in descending order, and returns how many were written. If
+/// fewer than `out.len()` offsets were written, then there are no more
+/// occurrences. Otherwise, the search can be resumed by calling this again
+/// on `&haystack[..out[out.len() - 1]]`. [`Memchr::next_back_batch`] does
+/// this bookkeeping for you.
+///
+/// This reports the same offsets as [`memrchr_iter`], but with less overhead
+/// per occurrence when occurrences are close together. Each time an
+/// occurrence is found, the 64 bytes ending at it are compared with the
+/// needle at once, and every occurrence among them is reported from the
+/// resulting mask.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memrchr_batch;
+///
+/// let haystack = b"a\nb\nc\nd\n";
+/// let mut out = [0; 3];
+/// assert_eq!(3, memrchr_batch(b'\n', haystack, &mut out));
+/// assert_eq!([7, 5, 3], out);
+/// assert_eq!(1, memrchr_batch(b'\n', &haystack[..out[2]], &mut out));
+/// assert_eq!(1, out[0]);
+/// ```
+#[inline]
+pub fn memrchr_batch(needle: u8, haystack: &[u8], out: &mut [usize]) -> usize {
+    let mut n = 0;
+    let mut end = haystack.len();
+    while n < out.len() {
+        let last = match memrchr(needle, &haystack[..end]) {
+            None => break,
+            Some(last) => last,
+        };
+        let start = last.saturating_sub(63);
+        let mut mask = [0u64; 1];
+        memchr_mask(needle, &haystack[start..last + 1], &mut mask);
+        // Drain the mask from its high end, since bit `i` is the byte at
+        // `start + i`.
+        let mut bits = mask[0];
+        while bits != 0 && n < out.len() {
+            let i = 63 - bits.leading_zeros() as usize;
+            out[n] = start + i;
+            n += 1;
+            bits &= !(1 << i);
+        }
+        end = start;
+    }
+    n
+}
+
+/// Like `memrchr`, but returns one more than the index found.
+///
+/// See [`memchr_nz`] for why this is useful.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memrchr_nz;
+///
+/// let haystack = b"the quick brown fox";
+/// let found = memrchr_nz(b'o', haystack).map_or(0, |i| i.get());
+/// assert_eq!(found, 18);
+/// assert_eq!(memrchr_nz(b'z', haystack), None);
+/// ```
+#[inline]
+pub fn memrchr_nz(needle: u8, haystack: &[u8]) -> Option<NonZeroUsize> {
+    memrchr(needle, haystack).and_then(|i| NonZeroUsize::new(i + 1))
+}
+
 /// Like `memrchr`, but searches for either of two bytes instead of just one.
 ///
 /// This returns the index corresponding to the last occurrence of `needle1` or
@@ -305,6 +1758,11 @@ pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
 /// `None` if neither one is found. If an index is returned, it is guaranteed
 /// to be less than `usize::MAX`.
 ///
+/// That is, the index returned is always the maximum index of any match
+/// of any of the needles. The needles are not required to be distinct. For
+/// example, `memrchr2(b'a', b'a', haystack)` is equivalent to
+/// `memrchr(b'a', haystack)`.
+///
 /// While this is operationally the same as something like
 /// `haystack.iter().rposition(|&b| b == needle1 || b == needle2)`, `memrchr2`
 /// will use a highly optimized routine that can be up to an order of magnitude
@@ -358,6 +1816,11 @@ pub fn memrchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
 /// `haystack` (whichever occurs later), or `None` if none are found. If an
 /// index is returned, it is guaranteed to be less than `usize::MAX`.
 ///
+/// That is, the index returned is always the maximum index of any match
+/// of any of the needles. The needles are not required to be distinct. For
+/// example, `memrchr3(b'a', b'b', b'a', haystack)` is equivalent to
+/// `memrchr2(b'a', b'b', haystack)`.
+///
 /// While this is operationally the same as something like
 /// `haystack.iter().rposition(|&b| b == needle1 || b == needle2 ||
 /// b == needle3)`, `memrchr3` will use a highly optimized routine that can be
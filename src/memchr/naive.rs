@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use super::summary::Summary;
+
 pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     haystack.iter().position(|&b| b == n1)
 }
@@ -12,6 +14,35 @@ pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
     haystack.iter().position(|&b| b == n1 || b == n2 || b == n3)
 }
 
+pub fn memchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    haystack
+        .iter()
+        .enumerate()
+        .find(|&(_, &b)| b == n1 || b == n2)
+        .map(|(i, &b)| (i, b))
+}
+
+pub fn memchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    haystack
+        .iter()
+        .enumerate()
+        .find(|&(_, &b)| b == n1 || b == n2 || b == n3)
+        .map(|(i, &b)| (i, b))
+}
+
+pub fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&b| b == n1 || b == n2).count()
+}
+
+pub fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&b| b == n1 || b == n2 || b == n3).count()
+}
+
 pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     haystack.iter().rposition(|&b| b == n1)
 }
@@ -23,3 +54,78 @@ pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
 pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
     haystack.iter().rposition(|&b| b == n1 || b == n2 || b == n3)
 }
+
+pub fn memchr_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| lo <= b && b <= hi)
+}
+
+pub fn memchr_not_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b < lo || hi < b)
+}
+
+pub fn is_all(n1: u8, haystack: &[u8]) -> bool {
+    haystack.iter().all(|&b| b == n1)
+}
+
+pub fn memchr_high_bit(haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b >= 0x80)
+}
+
+pub fn memchr16(n1: u16, haystack: &[u16]) -> Option<usize> {
+    haystack.iter().position(|&u| u == n1)
+}
+
+pub fn memcmp_idx(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter().zip(b).position(|(x, y)| x != y)
+}
+
+pub fn memcmp_ridx(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter().zip(b).rposition(|(x, y)| x != y)
+}
+
+pub fn find_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    haystack.iter().position(|b| set.contains(b))
+}
+
+pub fn rfind_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|b| set.contains(b))
+}
+
+pub fn memchr_mask(n1: u8, haystack: &[u8], out: &mut [u64]) {
+    for word in out.iter_mut().take((haystack.len() + 63) / 64) {
+        *word = 0;
+    }
+    for (i, &b) in haystack.iter().enumerate() {
+        if b == n1 {
+            out[i / 64] |= 1 << (i % 64);
+        }
+    }
+}
+
+pub fn memchr_masks<F: FnMut(usize, u64)>(n1: u8, haystack: &[u8], mut f: F) {
+    let mut at = 0;
+    while at < haystack.len() {
+        let mut mask = 0u64;
+        for (i, &b) in haystack[at..].iter().take(64).enumerate() {
+            if b == n1 {
+                mask |= 1 << i;
+            }
+        }
+        f(at, mask);
+        at += 64;
+    }
+}
+
+pub fn memchr_summary(n1: u8, haystack: &[u8]) -> Summary {
+    let mut summary = Summary::default();
+    for (i, &b) in haystack.iter().enumerate() {
+        if b == n1 {
+            summary.count += 1;
+            if summary.first.is_none() {
+                summary.first = Some(i);
+            }
+            summary.last = Some(i);
+        }
+    }
+    summary
+}
@@ -0,0 +1,48 @@
+/// The number of occurrences of a byte in a haystack, along with the
+/// positions of the first and last of them.
+///
+/// This is returned by [`memchr_summary`](crate::memchr_summary).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Summary {
+    pub(super) count: usize,
+    pub(super) first: Option<usize>,
+    pub(super) last: Option<usize>,
+}
+
+impl Summary {
+    /// Returns the number of occurrences of the byte.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the position of the first occurrence of the byte, or `None`
+    /// if it doesn't occur.
+    ///
+    /// This is the same as the result of [`memchr`](crate::memchr).
+    pub fn first(&self) -> Option<usize> {
+        self.first
+    }
+
+    /// Returns the position of the last occurrence of the byte, or `None` if
+    /// it doesn't occur.
+    ///
+    /// This is the same as the result of [`memrchr`](crate::memrchr).
+    pub fn last(&self) -> Option<usize> {
+        self.last
+    }
+
+    /// Fold the occurrences in a 64 byte block starting at `offset` into
+    /// this summary, where bit `i` of `mask` is set when the byte at
+    /// `offset + i` is an occurrence. Blocks must be given in order.
+    #[inline(always)]
+    pub(super) fn add_mask(&mut self, offset: usize, mask: u64) {
+        if mask == 0 {
+            return;
+        }
+        self.count += mask.count_ones() as usize;
+        if self.first.is_none() {
+            self.first = Some(offset + mask.trailing_zeros() as usize);
+        }
+        self.last = Some(offset + 63 - mask.leading_zeros() as usize);
+    }
+}
@@ -1,8 +1,8 @@
-use super::fallback;
+use super::{fallback, summary::Summary};
 
 // We only use AVX when we can detect at runtime whether it's available, which
-// requires std.
-#[cfg(feature = "std")]
+// requires std, or when it's enabled at compile time.
+#[cfg(any(feature = "std", target_feature = "avx2"))]
 mod avx;
 mod sse2;
 
@@ -42,7 +42,10 @@ macro_rules! unsafe_ifunc {
 
         fn detect($($needle: u8),+, haystack: &[u8]) -> Option<usize> {
             let fun =
-                if cfg!(memchr_runtime_avx) && is_x86_feature_detected!("avx2") {
+                if cfg!(memchr_runtime_avx)
+                    && !super::is_avx_avoided()
+                    && is_x86_feature_detected!("avx2")
+                {
                     avx::$name as FnRaw
                 } else if cfg!(memchr_runtime_sse2) {
                     sse2::$name as FnRaw
@@ -70,6 +73,29 @@ macro_rules! unsafe_ifunc {
     }}
 }
 
+/// When std isn't available to provide runtime CPU feature detection, but
+/// AVX2 is enabled at compile time (e.g., with `-C target-feature=+avx2`),
+/// then call our AVX2 routine directly.
+///
+/// # Safety
+///
+/// There are no safety requirements for this definition of the macro. It is
+/// safe for all inputs since the AVX2 routine is only called when the avx2
+/// target feature is enabled for the entire compilation, which means the
+/// target CPU is asserted to support it.
+#[cfg(all(not(feature = "std"), target_feature = "avx2"))]
+macro_rules! unsafe_ifunc {
+    ($fnty:ty, $name:ident, $haystack:ident, $($needle:ident),+) => {{
+        if cfg!(memchr_runtime_avx) {
+            unsafe { avx::$name($($needle),+, $haystack) }
+        } else if cfg!(memchr_runtime_sse2) {
+            unsafe { sse2::$name($($needle),+, $haystack) }
+        } else {
+            fallback::$name($($needle),+, $haystack)
+        }
+    }}
+}
+
 /// When std isn't available to provide runtime CPU feature detection, or if
 /// runtime CPU feature detection has been explicitly disabled, then just
 /// call our optimized SSE2 routine directly. SSE2 is avalbale on all x86_64
@@ -80,7 +106,7 @@ macro_rules! unsafe_ifunc {
 /// There are no safety requirements for this definition of the macro. It is
 /// safe for all inputs since it is restricted to either the fallback routine
 /// or the SSE routine, which is always safe to call on x86_64.
-#[cfg(not(feature = "std"))]
+#[cfg(all(not(feature = "std"), not(target_feature = "avx2")))]
 macro_rules! unsafe_ifunc {
     ($fnty:ty, $name:ident, $haystack:ident, $($needle:ident),+) => {{
         if cfg!(memchr_runtime_sse2) {
@@ -91,6 +117,22 @@ macro_rules! unsafe_ifunc {
     }}
 }
 
+/// Returns true when the routines below use vector instructions.
+///
+/// The `unsafe_ifunc` dispatch only falls back to the scalar routines when
+/// SSE2 is unavailable. Runtime detection only ever picks between the AVX2
+/// and SSE2 routines, so there is nothing to detect here.
+#[inline(always)]
+pub fn simd_available() -> bool {
+    cfg!(memchr_runtime_sse2)
+}
+
+// The dispatchers below are only ever called from the `imp` shims inside the
+// public routines, which are themselves plain `#[inline]`. Forcing these to
+// inline just collapses the shim and the function pointer load into the
+// public routine, so there's no extra call in front of the indirect one. It
+// doesn't force anything into callers of the public routines: whether those
+// get inlined is still left to the compiler (and to PGO).
 #[inline(always)]
 pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     unsafe_ifunc!(fn(u8, &[u8]) -> Option<usize>, memchr, haystack, n1)
@@ -146,3 +188,172 @@ pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
         n3
     )
 }
+
+// There are no AVX2 routines for searching byte ranges, small sets of bytes
+// or 16-bit units, comparing slices or writing bitsets, so these skip the
+// runtime CPU feature detection entirely.
+
+#[inline(always)]
+pub fn memchr_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr_range(lo, hi, haystack) }
+    } else {
+        fallback::memchr_range(lo, hi, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn memchr_not_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr_not_range(lo, hi, haystack) }
+    } else {
+        fallback::memchr_not_range(lo, hi, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn memcmp_ridx(a: &[u8], b: &[u8]) -> Option<usize> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memcmp_ridx(a, b) }
+    } else {
+        fallback::memcmp_ridx(a, b)
+    }
+}
+
+#[inline(always)]
+pub fn memchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr2_which(n1, n2, haystack) }
+    } else {
+        fallback::memchr2_which(n1, n2, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn memchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr3_which(n1, n2, n3, haystack) }
+    } else {
+        fallback::memchr3_which(n1, n2, n3, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn is_all(n1: u8, haystack: &[u8]) -> bool {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::is_all(n1, haystack) }
+    } else {
+        fallback::is_all(n1, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn memchr_high_bit(haystack: &[u8]) -> Option<usize> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr_high_bit(haystack) }
+    } else {
+        fallback::memchr_high_bit(haystack)
+    }
+}
+
+#[inline(always)]
+pub fn memcmp_idx(a: &[u8], b: &[u8]) -> Option<usize> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memcmp_idx(a, b) }
+    } else {
+        fallback::memcmp_idx(a, b)
+    }
+}
+
+#[inline(always)]
+pub fn memchr16(n1: u16, haystack: &[u16]) -> Option<usize> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr16(n1, haystack) }
+    } else {
+        fallback::memchr16(n1, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn memchr_mask(n1: u8, haystack: &[u8], out: &mut [u64]) {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr_mask(n1, haystack, out) }
+    } else {
+        fallback::memchr_mask(n1, haystack, out)
+    }
+}
+
+#[inline(always)]
+pub fn memchr_masks<F: FnMut(usize, u64)>(n1: u8, haystack: &[u8], f: F) {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr_masks(n1, haystack, f) }
+    } else {
+        fallback::memchr_masks(n1, haystack, f)
+    }
+}
+
+#[inline(always)]
+pub fn memchr_summary(n1: u8, haystack: &[u8]) -> Summary {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr_summary(n1, haystack) }
+    } else {
+        fallback::memchr_summary(n1, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr2_count(n1, n2, haystack) }
+    } else {
+        fallback::memchr2_count(n1, n2, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::memchr3_count(n1, n2, n3, haystack) }
+    } else {
+        fallback::memchr3_count(n1, n2, n3, haystack)
+    }
+}
+
+#[inline(always)]
+pub fn find_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::find_byte_in(haystack, set) }
+    } else {
+        fallback::find_byte_in(haystack, set)
+    }
+}
+
+#[inline(always)]
+pub fn rfind_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    if cfg!(memchr_runtime_sse2) {
+        // SAFETY: SSE2 is available on all x86_64 targets.
+        unsafe { sse2::rfind_byte_in(haystack, set) }
+    } else {
+        fallback::rfind_byte_in(haystack, set)
+    }
+}
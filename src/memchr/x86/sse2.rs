@@ -1,5 +1,7 @@
 use core::{arch::x86_64::*, cmp, mem::size_of};
 
+use crate::memchr::summary::Summary;
+
 const VECTOR_SIZE: usize = size_of::<__m128i>();
 const VECTOR_ALIGN: usize = VECTOR_SIZE - 1;
 
@@ -338,6 +340,179 @@ pub unsafe fn memchr3(
     None
 }
 
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr2_which(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    let vn1 = _mm_set1_epi8(n1 as i8);
+    let vn2 = _mm_set1_epi8(n2 as i8);
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_SIZE2, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < VECTOR_SIZE {
+        while ptr < end_ptr {
+            let byte = *ptr;
+            if byte == n1 || byte == n2 {
+                return Some((sub(ptr, start_ptr), byte));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    if let Some(m) =
+        forward_search2_which(start_ptr, end_ptr, ptr, vn1, vn2, n1, n2)
+    {
+        return Some(m);
+    }
+
+    ptr = ptr.add(VECTOR_SIZE - (start_ptr as usize & VECTOR_ALIGN));
+    debug_assert!(ptr > start_ptr && end_ptr.sub(VECTOR_SIZE) >= start_ptr);
+    while loop_size == LOOP_SIZE2 && ptr <= end_ptr.sub(loop_size) {
+        debug_assert_eq!(0, (ptr as usize) % VECTOR_SIZE);
+
+        let a = _mm_load_si128(ptr as *const __m128i);
+        let b = _mm_load_si128(ptr.add(VECTOR_SIZE) as *const __m128i);
+        let eqa1 = _mm_cmpeq_epi8(vn1, a);
+        let eqb1 = _mm_cmpeq_epi8(vn1, b);
+        let eqa2 = _mm_cmpeq_epi8(vn2, a);
+        let eqb2 = _mm_cmpeq_epi8(vn2, b);
+        let or1 = _mm_or_si128(eqa1, eqb1);
+        let or2 = _mm_or_si128(eqa2, eqb2);
+        let or3 = _mm_or_si128(or1, or2);
+        if _mm_movemask_epi8(or3) != 0 {
+            let mut at = sub(ptr, start_ptr);
+            let mask1 = _mm_movemask_epi8(eqa1);
+            let mask2 = _mm_movemask_epi8(eqa2);
+            if mask1 != 0 || mask2 != 0 {
+                let (i, byte) = forward_which2(mask1, mask2, n1, n2);
+                return Some((at + i, byte));
+            }
+
+            at += VECTOR_SIZE;
+            let mask1 = _mm_movemask_epi8(eqb1);
+            let mask2 = _mm_movemask_epi8(eqb2);
+            let (i, byte) = forward_which2(mask1, mask2, n1, n2);
+            return Some((at + i, byte));
+        }
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(VECTOR_SIZE) {
+        if let Some(m) =
+            forward_search2_which(start_ptr, end_ptr, ptr, vn1, vn2, n1, n2)
+        {
+            return Some(m);
+        }
+        ptr = ptr.add(VECTOR_SIZE);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < VECTOR_SIZE);
+        ptr = ptr.sub(VECTOR_SIZE - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), VECTOR_SIZE);
+
+        return forward_search2_which(
+            start_ptr, end_ptr, ptr, vn1, vn2, n1, n2,
+        );
+    }
+    None
+}
+
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    let vn1 = _mm_set1_epi8(n1 as i8);
+    let vn2 = _mm_set1_epi8(n2 as i8);
+    let vn3 = _mm_set1_epi8(n3 as i8);
+    let needles = (n1, n2, n3);
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_SIZE2, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < VECTOR_SIZE {
+        while ptr < end_ptr {
+            let byte = *ptr;
+            if byte == n1 || byte == n2 || byte == n3 {
+                return Some((sub(ptr, start_ptr), byte));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    if let Some(m) =
+        forward_search3_which(start_ptr, end_ptr, ptr, vn1, vn2, vn3, needles)
+    {
+        return Some(m);
+    }
+
+    ptr = ptr.add(VECTOR_SIZE - (start_ptr as usize & VECTOR_ALIGN));
+    debug_assert!(ptr > start_ptr && end_ptr.sub(VECTOR_SIZE) >= start_ptr);
+    while loop_size == LOOP_SIZE2 && ptr <= end_ptr.sub(loop_size) {
+        debug_assert_eq!(0, (ptr as usize) % VECTOR_SIZE);
+
+        let a = _mm_load_si128(ptr as *const __m128i);
+        let b = _mm_load_si128(ptr.add(VECTOR_SIZE) as *const __m128i);
+        let eqa1 = _mm_cmpeq_epi8(vn1, a);
+        let eqb1 = _mm_cmpeq_epi8(vn1, b);
+        let eqa2 = _mm_cmpeq_epi8(vn2, a);
+        let eqb2 = _mm_cmpeq_epi8(vn2, b);
+        let eqa3 = _mm_cmpeq_epi8(vn3, a);
+        let eqb3 = _mm_cmpeq_epi8(vn3, b);
+        let or1 = _mm_or_si128(eqa1, eqb1);
+        let or2 = _mm_or_si128(eqa2, eqb2);
+        let or3 = _mm_or_si128(eqa3, eqb3);
+        let or4 = _mm_or_si128(or1, or2);
+        let or5 = _mm_or_si128(or3, or4);
+        if _mm_movemask_epi8(or5) != 0 {
+            let mut at = sub(ptr, start_ptr);
+            let mask1 = _mm_movemask_epi8(eqa1);
+            let mask2 = _mm_movemask_epi8(eqa2);
+            let mask3 = _mm_movemask_epi8(eqa3);
+            if mask1 != 0 || mask2 != 0 || mask3 != 0 {
+                let (i, byte) = forward_which3(mask1, mask2, mask3, needles);
+                return Some((at + i, byte));
+            }
+
+            at += VECTOR_SIZE;
+            let mask1 = _mm_movemask_epi8(eqb1);
+            let mask2 = _mm_movemask_epi8(eqb2);
+            let mask3 = _mm_movemask_epi8(eqb3);
+            let (i, byte) = forward_which3(mask1, mask2, mask3, needles);
+            return Some((at + i, byte));
+        }
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(VECTOR_SIZE) {
+        if let Some(m) = forward_search3_which(
+            start_ptr, end_ptr, ptr, vn1, vn2, vn3, needles,
+        ) {
+            return Some(m);
+        }
+        ptr = ptr.add(VECTOR_SIZE);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < VECTOR_SIZE);
+        ptr = ptr.sub(VECTOR_SIZE - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), VECTOR_SIZE);
+
+        return forward_search3_which(
+            start_ptr, end_ptr, ptr, vn1, vn2, vn3, needles,
+        );
+    }
+    None
+}
+
 #[target_feature(enable = "sse2")]
 pub unsafe fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = _mm_set1_epi8(n1 as i8);
@@ -565,6 +740,598 @@ pub unsafe fn memrchr3(
     None
 }
 
+/// Write a bitset of the positions of `n1` in `haystack` to `out`.
+///
+/// Each 64 byte chunk of the haystack corresponds to a single word in `out`,
+/// which is built directly from the masks of four vector comparisons. Any
+/// bytes remaining after the last full chunk are handled by the fallback
+/// routine.
+///
+/// Callers must ensure that `out` has at least `ceil(haystack.len() / 64)`
+/// words.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr_mask(n1: u8, haystack: &[u8], out: &mut [u64]) {
+    debug_assert!(out.len() >= (haystack.len() + 63) / 64);
+
+    let vn1 = _mm_set1_epi8(n1 as i8);
+    let mut chunks = haystack.chunks_exact(LOOP_SIZE);
+    for (chunk, word) in chunks.by_ref().zip(out.iter_mut()) {
+        *word = mask64(vn1, chunk.as_ptr());
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        let i = haystack.len() / LOOP_SIZE;
+        super::fallback::memchr_mask(n1, rest, &mut out[i..]);
+    }
+}
+
+/// Call `f` with the offset and the mask of the positions of `n1` of each 64
+/// byte window of `haystack`, in order.
+///
+/// The last window is built by the fallback when it's shorter than 64 bytes,
+/// so its bits past the end of the haystack are always zero.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr_masks<F: FnMut(usize, u64)>(
+    n1: u8,
+    haystack: &[u8],
+    mut f: F,
+) {
+    let vn1 = _mm_set1_epi8(n1 as i8);
+    let mut chunks = haystack.chunks_exact(LOOP_SIZE);
+    for (i, chunk) in chunks.by_ref().enumerate() {
+        f(i * LOOP_SIZE, mask64(vn1, chunk.as_ptr()));
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        let mut word = [0u64; 1];
+        super::fallback::memchr_mask(n1, rest, &mut word);
+        f(haystack.len() - rest.len(), word[0]);
+    }
+}
+
+/// Count the occurrences of `n1` in `haystack`, and find the first and last
+/// of them, in a single pass.
+///
+/// This folds each of the masks built by `memchr_masks` into the summary.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr_summary(n1: u8, haystack: &[u8]) -> Summary {
+    let mut summary = Summary::default();
+    memchr_masks(n1, haystack, |at, mask| summary.add_mask(at, mask));
+    summary
+}
+
+/// Count the positions in `haystack` that hold either `n1` or `n2`.
+///
+/// The comparisons against each needle are OR'd together before taking a
+/// mask of each vector, so a position is counted once even when `n1 == n2`.
+/// Any bytes remaining after the last full chunk are handled by the fallback
+/// routine.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    let vn1 = _mm_set1_epi8(n1 as i8);
+    let vn2 = _mm_set1_epi8(n2 as i8);
+    let mut count = 0;
+    let mut chunks = haystack.chunks_exact(VECTOR_SIZE);
+    for chunk in chunks.by_ref() {
+        let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+        let eq = _mm_or_si128(_mm_cmpeq_epi8(vn1, v), _mm_cmpeq_epi8(vn2, v));
+        count += _mm_movemask_epi8(eq).count_ones() as usize;
+    }
+    count + super::fallback::memchr2_count(n1, n2, chunks.remainder())
+}
+
+/// Count the positions in `haystack` that hold any of `n1`, `n2` or `n3`.
+///
+/// This works the same way as `memchr2_count`.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    let vn1 = _mm_set1_epi8(n1 as i8);
+    let vn2 = _mm_set1_epi8(n2 as i8);
+    let vn3 = _mm_set1_epi8(n3 as i8);
+    let mut count = 0;
+    let mut chunks = haystack.chunks_exact(VECTOR_SIZE);
+    for chunk in chunks.by_ref() {
+        let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+        let eq = _mm_or_si128(
+            _mm_or_si128(_mm_cmpeq_epi8(vn1, v), _mm_cmpeq_epi8(vn2, v)),
+            _mm_cmpeq_epi8(vn3, v),
+        );
+        count += _mm_movemask_epi8(eq).count_ones() as usize;
+    }
+    count + super::fallback::memchr3_count(n1, n2, n3, chunks.remainder())
+}
+
+/// Returns a mask of the positions of `vn1` in the 64 bytes at `ptr`, where
+/// bit `i` corresponds to the byte at `ptr.add(i)`.
+///
+/// Callers must ensure that 64 bytes may be read from `ptr`.
+#[inline(always)]
+unsafe fn mask64(vn1: __m128i, ptr: *const u8) -> u64 {
+    let a = _mm_loadu_si128(ptr as *const __m128i);
+    let b = _mm_loadu_si128(ptr.add(VECTOR_SIZE) as *const __m128i);
+    let c = _mm_loadu_si128(ptr.add(2 * VECTOR_SIZE) as *const __m128i);
+    let d = _mm_loadu_si128(ptr.add(3 * VECTOR_SIZE) as *const __m128i);
+    // Each movemask result only has its low 16 bits set, one for each byte
+    // in the vector.
+    let ma = _mm_movemask_epi8(_mm_cmpeq_epi8(vn1, a)) as u16 as u64;
+    let mb = _mm_movemask_epi8(_mm_cmpeq_epi8(vn1, b)) as u16 as u64;
+    let mc = _mm_movemask_epi8(_mm_cmpeq_epi8(vn1, c)) as u16 as u64;
+    let md = _mm_movemask_epi8(_mm_cmpeq_epi8(vn1, d)) as u16 as u64;
+    ma | (mb << 16) | (mc << 32) | (md << 48)
+}
+
+/// Search for the first byte in the inclusive range `lo..=hi`.
+///
+/// Callers must ensure that `lo <= hi`.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr_range(lo: u8, hi: u8, haystack: &[u8]) -> Option<usize> {
+    range_search(lo, hi, haystack, false)
+}
+
+/// Search for the first byte not in the inclusive range `lo..=hi`.
+///
+/// Callers must ensure that `lo <= hi`.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr_not_range(
+    lo: u8,
+    hi: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    range_search(lo, hi, haystack, true)
+}
+
+/// Search for the first byte in (or, if `negate` is true, not in) the
+/// inclusive range `lo..=hi`.
+///
+/// SSE2 only has signed byte comparisons, so we subtract `lo` from each byte
+/// (which shifts the range to start at `0`) and then flip the top bit of
+/// each byte. A signed comparison against the similarly biased width of the
+/// range is then equivalent to an unsigned comparison, and tells us which
+/// bytes are outside of the range.
+///
+/// Unlike memchr, this doesn't bother with aligned loads or unrolling. It
+/// still processes 16 bytes at a time, and finishes with an unaligned load
+/// that may overlap with a previous one.
+#[inline(always)]
+unsafe fn range_search(
+    lo: u8,
+    hi: u8,
+    haystack: &[u8],
+    negate: bool,
+) -> Option<usize> {
+    debug_assert!(lo <= hi);
+
+    let width = hi - lo;
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < VECTOR_SIZE {
+        while ptr < end_ptr {
+            if ((*ptr).wrapping_sub(lo) > width) == negate {
+                return Some(sub(ptr, start_ptr));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    let vlo = _mm_set1_epi8(lo as i8);
+    let vwidth = _mm_set1_epi8((width ^ 0x80) as i8);
+    while ptr <= end_ptr.sub(VECTOR_SIZE) {
+        let found =
+            forward_search_range(start_ptr, end_ptr, ptr, vlo, vwidth, negate);
+        if found.is_some() {
+            return found;
+        }
+        ptr = ptr.add(VECTOR_SIZE);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < VECTOR_SIZE);
+        ptr = ptr.sub(VECTOR_SIZE - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), VECTOR_SIZE);
+
+        return forward_search_range(
+            start_ptr, end_ptr, ptr, vlo, vwidth, negate,
+        );
+    }
+    None
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn forward_search_range(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vlo: __m128i,
+    vwidth: __m128i,
+    negate: bool,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= VECTOR_SIZE);
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(VECTOR_SIZE));
+
+    let chunk = _mm_loadu_si128(ptr as *const __m128i);
+    let biased =
+        _mm_xor_si128(_mm_sub_epi8(chunk, vlo), _mm_set1_epi8(0x80u8 as i8));
+    let outside = _mm_movemask_epi8(_mm_cmpgt_epi8(biased, vwidth));
+    let mask = if negate { outside } else { !outside & 0xFFFF };
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + forward_pos(mask))
+    } else {
+        None
+    }
+}
+
+/// Return the index of the first byte at which `a` and `b` differ.
+///
+/// Callers must ensure that `a.len() == b.len()`.
+///
+/// Like `range_search`, this doesn't bother with aligned loads or
+/// unrolling. The last chunk is compared with an unaligned load that may
+/// overlap with the previous one, which is fine since every byte before it
+/// is already known to be equal.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memcmp_idx(a: &[u8], b: &[u8]) -> Option<usize> {
+    debug_assert_eq!(a.len(), b.len());
+
+    let len = a.len();
+    if len < VECTOR_SIZE {
+        return a.iter().zip(b).position(|(x, y)| x != y);
+    }
+    let mut i = 0;
+    while i + VECTOR_SIZE <= len {
+        if let Some(pos) = forward_search_mismatch(a, b, i) {
+            return Some(i + pos);
+        }
+        i += VECTOR_SIZE;
+    }
+    if i < len {
+        i = len - VECTOR_SIZE;
+        return forward_search_mismatch(a, b, i).map(|pos| i + pos);
+    }
+    None
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn forward_search_mismatch(
+    a: &[u8],
+    b: &[u8],
+    i: usize,
+) -> Option<usize> {
+    debug_assert!(i + VECTOR_SIZE <= a.len());
+    debug_assert!(i + VECTOR_SIZE <= b.len());
+
+    let x = _mm_loadu_si128(a.as_ptr().add(i) as *const __m128i);
+    let y = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+    let mask = !_mm_movemask_epi8(_mm_cmpeq_epi8(x, y)) & 0xFFFF;
+    if mask != 0 {
+        Some(forward_pos(mask))
+    } else {
+        None
+    }
+}
+
+/// Return the index of the last byte at which `a` and `b` differ.
+///
+/// Callers must ensure that `a.len() == b.len()`.
+///
+/// This is `memcmp_idx` run backwards: it compares chunks starting at the
+/// end of the slices, and the first chunk is compared with an unaligned load
+/// that may overlap with the chunk after it.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memcmp_ridx(a: &[u8], b: &[u8]) -> Option<usize> {
+    debug_assert_eq!(a.len(), b.len());
+
+    let len = a.len();
+    if len < VECTOR_SIZE {
+        return a.iter().zip(b).rposition(|(x, y)| x != y);
+    }
+    let mut end = len;
+    while end >= VECTOR_SIZE {
+        let i = end - VECTOR_SIZE;
+        if let Some(pos) = reverse_search_mismatch(a, b, i) {
+            return Some(i + pos);
+        }
+        end = i;
+    }
+    if end > 0 {
+        return reverse_search_mismatch(a, b, 0);
+    }
+    None
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn reverse_search_mismatch(
+    a: &[u8],
+    b: &[u8],
+    i: usize,
+) -> Option<usize> {
+    debug_assert!(i + VECTOR_SIZE <= a.len());
+    debug_assert!(i + VECTOR_SIZE <= b.len());
+
+    let x = _mm_loadu_si128(a.as_ptr().add(i) as *const __m128i);
+    let y = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+    let mask = !_mm_movemask_epi8(_mm_cmpeq_epi8(x, y)) & 0xFFFF;
+    if mask != 0 {
+        Some(reverse_pos(mask))
+    } else {
+        None
+    }
+}
+
+/// Returns true if and only if every byte in `haystack` is equal to `n1`.
+///
+/// Since this only needs a yes or no answer, the main loop ANDs together the
+/// comparisons of four vectors and checks them with a single movemask. As
+/// with `memcmp_idx`, the tail is handled with an overlapping unaligned load.
+#[target_feature(enable = "sse2")]
+pub unsafe fn is_all(n1: u8, haystack: &[u8]) -> bool {
+    const ALL: i32 = 0xFFFF;
+
+    let len = haystack.len();
+    if len < VECTOR_SIZE {
+        return haystack.iter().all(|&b| b == n1);
+    }
+    let vn1 = _mm_set1_epi8(n1 as i8);
+    let ptr = haystack.as_ptr();
+    let mut i = 0;
+    while i + LOOP_SIZE <= len {
+        let a = _mm_loadu_si128(ptr.add(i) as *const __m128i);
+        let b = _mm_loadu_si128(ptr.add(i + VECTOR_SIZE) as *const __m128i);
+        let c =
+            _mm_loadu_si128(ptr.add(i + 2 * VECTOR_SIZE) as *const __m128i);
+        let d =
+            _mm_loadu_si128(ptr.add(i + 3 * VECTOR_SIZE) as *const __m128i);
+        let eqab =
+            _mm_and_si128(_mm_cmpeq_epi8(a, vn1), _mm_cmpeq_epi8(b, vn1));
+        let eqcd =
+            _mm_and_si128(_mm_cmpeq_epi8(c, vn1), _mm_cmpeq_epi8(d, vn1));
+        if _mm_movemask_epi8(_mm_and_si128(eqab, eqcd)) != ALL {
+            return false;
+        }
+        i += LOOP_SIZE;
+    }
+    while i + VECTOR_SIZE <= len {
+        let a = _mm_loadu_si128(ptr.add(i) as *const __m128i);
+        if _mm_movemask_epi8(_mm_cmpeq_epi8(a, vn1)) != ALL {
+            return false;
+        }
+        i += VECTOR_SIZE;
+    }
+    if i < len {
+        let a = _mm_loadu_si128(ptr.add(len - VECTOR_SIZE) as *const __m128i);
+        return _mm_movemask_epi8(_mm_cmpeq_epi8(a, vn1)) == ALL;
+    }
+    true
+}
+
+/// Search for the first byte with its most significant bit set.
+///
+/// `movemask` already extracts the most significant bit of each byte, so
+/// this needs no comparisons at all. The main loop ORs four vectors together
+/// and checks them with a single movemask, and as with `is_all`, the tail is
+/// handled with an overlapping unaligned load. The bytes it shares with the
+/// previous vector are known not to have their high bit set, so the first
+/// set bit in the mask is still the first byte at or after `i`.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr_high_bit(haystack: &[u8]) -> Option<usize> {
+    let len = haystack.len();
+    if len < VECTOR_SIZE {
+        return haystack.iter().position(|&b| b >= 0x80);
+    }
+    let ptr = haystack.as_ptr();
+    let mut i = 0;
+    while i + LOOP_SIZE <= len {
+        let a = _mm_loadu_si128(ptr.add(i) as *const __m128i);
+        let b = _mm_loadu_si128(ptr.add(i + VECTOR_SIZE) as *const __m128i);
+        let c =
+            _mm_loadu_si128(ptr.add(i + 2 * VECTOR_SIZE) as *const __m128i);
+        let d =
+            _mm_loadu_si128(ptr.add(i + 3 * VECTOR_SIZE) as *const __m128i);
+        let or = _mm_or_si128(_mm_or_si128(a, b), _mm_or_si128(c, d));
+        if _mm_movemask_epi8(or) != 0 {
+            let mask = _mm_movemask_epi8(a);
+            if mask != 0 {
+                return Some(i + forward_pos(mask));
+            }
+            let mask = _mm_movemask_epi8(b);
+            if mask != 0 {
+                return Some(i + VECTOR_SIZE + forward_pos(mask));
+            }
+            let mask = _mm_movemask_epi8(c);
+            if mask != 0 {
+                return Some(i + 2 * VECTOR_SIZE + forward_pos(mask));
+            }
+            let mask = _mm_movemask_epi8(d);
+            return Some(i + 3 * VECTOR_SIZE + forward_pos(mask));
+        }
+        i += LOOP_SIZE;
+    }
+    while i + VECTOR_SIZE <= len {
+        let mask =
+            _mm_movemask_epi8(_mm_loadu_si128(ptr.add(i) as *const __m128i));
+        if mask != 0 {
+            return Some(i + forward_pos(mask));
+        }
+        i += VECTOR_SIZE;
+    }
+    if i < len {
+        let start = len - VECTOR_SIZE;
+        let mask = _mm_movemask_epi8(_mm_loadu_si128(
+            ptr.add(start) as *const __m128i
+        ));
+        if mask != 0 {
+            return Some(start + forward_pos(mask));
+        }
+    }
+    None
+}
+
+/// Search for the first occurrence of `n1` in a slice of 16-bit units.
+///
+/// Like `memcmp_idx`, this doesn't bother with aligned loads or unrolling,
+/// and handles the tail with an overlapping unaligned load.
+#[target_feature(enable = "sse2")]
+pub unsafe fn memchr16(n1: u16, haystack: &[u16]) -> Option<usize> {
+    const UNITS: usize = VECTOR_SIZE / 2;
+
+    let len = haystack.len();
+    if len < UNITS {
+        return haystack.iter().position(|&u| u == n1);
+    }
+    let vn1 = _mm_set1_epi16(n1 as i16);
+    let mut i = 0;
+    while i + UNITS <= len {
+        if let Some(pos) = forward_search16(vn1, haystack, i) {
+            return Some(i + pos);
+        }
+        i += UNITS;
+    }
+    if i < len {
+        i = len - UNITS;
+        return forward_search16(vn1, haystack, i).map(|pos| i + pos);
+    }
+    None
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn forward_search16(
+    vn1: __m128i,
+    haystack: &[u16],
+    i: usize,
+) -> Option<usize> {
+    debug_assert!(i + VECTOR_SIZE / 2 <= haystack.len());
+
+    let chunk = _mm_loadu_si128(haystack.as_ptr().add(i) as *const __m128i);
+    let mask = _mm_movemask_epi8(_mm_cmpeq_epi16(chunk, vn1));
+    if mask != 0 {
+        // Each matching unit sets two adjacent bits in the mask.
+        Some(forward_pos(mask) / 2)
+    } else {
+        None
+    }
+}
+
+/// The largest set of bytes supported by `find_byte_in` and `rfind_byte_in`.
+const SET_SIZE: usize = 8;
+
+/// Search for the first byte in `set`.
+///
+/// Callers must ensure that `set` has at least 1 and at most 8 bytes.
+#[target_feature(enable = "sse2")]
+pub unsafe fn find_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < VECTOR_SIZE {
+        while ptr < end_ptr {
+            if set.contains(&*ptr) {
+                return Some(sub(ptr, start_ptr));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    let vset = set_vectors(set);
+    while ptr <= end_ptr.sub(VECTOR_SIZE) {
+        let mask = set_mask(&vset, ptr);
+        if mask != 0 {
+            return Some(sub(ptr, start_ptr) + forward_pos(mask));
+        }
+        ptr = ptr.add(VECTOR_SIZE);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < VECTOR_SIZE);
+        ptr = ptr.sub(VECTOR_SIZE - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), VECTOR_SIZE);
+
+        let mask = set_mask(&vset, ptr);
+        if mask != 0 {
+            return Some(sub(ptr, start_ptr) + forward_pos(mask));
+        }
+    }
+    None
+}
+
+/// Search for the last byte in `set`.
+///
+/// Callers must ensure that `set` has at least 1 and at most 8 bytes.
+#[target_feature(enable = "sse2")]
+pub unsafe fn rfind_byte_in(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = end_ptr;
+
+    if haystack.len() < VECTOR_SIZE {
+        while ptr > start_ptr {
+            ptr = ptr.offset(-1);
+            if set.contains(&*ptr) {
+                return Some(sub(ptr, start_ptr));
+            }
+        }
+        return None;
+    }
+
+    let vset = set_vectors(set);
+    while ptr >= start_ptr.add(VECTOR_SIZE) {
+        ptr = ptr.sub(VECTOR_SIZE);
+        let mask = set_mask(&vset, ptr);
+        if mask != 0 {
+            return Some(sub(ptr, start_ptr) + reverse_pos(mask));
+        }
+    }
+    if ptr > start_ptr {
+        debug_assert!(sub(ptr, start_ptr) < VECTOR_SIZE);
+        let mask = set_mask(&vset, start_ptr);
+        if mask != 0 {
+            return Some(reverse_pos(mask));
+        }
+    }
+    None
+}
+
+/// Broadcast each byte in `set` to its own vector. If there are fewer than
+/// `SET_SIZE` bytes, then the remaining vectors repeat the first byte, which
+/// lets `set_mask` always do the same number of comparisons.
+#[target_feature(enable = "sse2")]
+unsafe fn set_vectors(set: &[u8]) -> [__m128i; SET_SIZE] {
+    debug_assert!(!set.is_empty() && set.len() <= SET_SIZE);
+
+    let mut vset = [_mm_set1_epi8(set[0] as i8); SET_SIZE];
+    for (v, &b) in vset.iter_mut().zip(set) {
+        *v = _mm_set1_epi8(b as i8);
+    }
+    vset
+}
+
+/// Return the movemask of the 16 bytes at `ptr` that are equal to any of the
+/// bytes broadcast in `vset`.
+#[target_feature(enable = "sse2")]
+unsafe fn set_mask(vset: &[__m128i; SET_SIZE], ptr: *const u8) -> i32 {
+    let chunk = _mm_loadu_si128(ptr as *const __m128i);
+    let eq01 = _mm_or_si128(
+        _mm_cmpeq_epi8(vset[0], chunk),
+        _mm_cmpeq_epi8(vset[1], chunk),
+    );
+    let eq23 = _mm_or_si128(
+        _mm_cmpeq_epi8(vset[2], chunk),
+        _mm_cmpeq_epi8(vset[3], chunk),
+    );
+    let eq45 = _mm_or_si128(
+        _mm_cmpeq_epi8(vset[4], chunk),
+        _mm_cmpeq_epi8(vset[5], chunk),
+    );
+    let eq67 = _mm_or_si128(
+        _mm_cmpeq_epi8(vset[6], chunk),
+        _mm_cmpeq_epi8(vset[7], chunk),
+    );
+    let eq = _mm_or_si128(_mm_or_si128(eq01, eq23), _mm_or_si128(eq45, eq67));
+    _mm_movemask_epi8(eq)
+}
+
 #[target_feature(enable = "sse2")]
 pub unsafe fn forward_search1(
     start_ptr: *const u8,
@@ -637,6 +1404,63 @@ pub unsafe fn forward_search3(
     }
 }
 
+#[target_feature(enable = "sse2")]
+unsafe fn forward_search2_which(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vn1: __m128i,
+    vn2: __m128i,
+    n1: u8,
+    n2: u8,
+) -> Option<(usize, u8)> {
+    debug_assert!(sub(end_ptr, start_ptr) >= VECTOR_SIZE);
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(VECTOR_SIZE));
+
+    let chunk = _mm_loadu_si128(ptr as *const __m128i);
+    let eq1 = _mm_cmpeq_epi8(chunk, vn1);
+    let eq2 = _mm_cmpeq_epi8(chunk, vn2);
+    if _mm_movemask_epi8(_mm_or_si128(eq1, eq2)) != 0 {
+        let mask1 = _mm_movemask_epi8(eq1);
+        let mask2 = _mm_movemask_epi8(eq2);
+        let (i, byte) = forward_which2(mask1, mask2, n1, n2);
+        Some((sub(ptr, start_ptr) + i, byte))
+    } else {
+        None
+    }
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn forward_search3_which(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vn1: __m128i,
+    vn2: __m128i,
+    vn3: __m128i,
+    needles: (u8, u8, u8),
+) -> Option<(usize, u8)> {
+    debug_assert!(sub(end_ptr, start_ptr) >= VECTOR_SIZE);
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(VECTOR_SIZE));
+
+    let chunk = _mm_loadu_si128(ptr as *const __m128i);
+    let eq1 = _mm_cmpeq_epi8(chunk, vn1);
+    let eq2 = _mm_cmpeq_epi8(chunk, vn2);
+    let eq3 = _mm_cmpeq_epi8(chunk, vn3);
+    let or = _mm_or_si128(eq1, eq2);
+    if _mm_movemask_epi8(_mm_or_si128(or, eq3)) != 0 {
+        let mask1 = _mm_movemask_epi8(eq1);
+        let mask2 = _mm_movemask_epi8(eq2);
+        let mask3 = _mm_movemask_epi8(eq3);
+        let (i, byte) = forward_which3(mask1, mask2, mask3, needles);
+        Some((sub(ptr, start_ptr) + i, byte))
+    } else {
+        None
+    }
+}
+
 #[target_feature(enable = "sse2")]
 unsafe fn reverse_search1(
     start_ptr: *const u8,
@@ -745,6 +1569,37 @@ fn forward_pos3(mask1: i32, mask2: i32, mask3: i32) -> usize {
     forward_pos(mask1 | mask2 | mask3)
 }
 
+/// Like `forward_pos2`, but also returns the needle that matched at the
+/// position returned, which is found by checking which mask has that bit
+/// set. When both do, the needles are equal, so either one is correct.
+fn forward_which2(mask1: i32, mask2: i32, n1: u8, n2: u8) -> (usize, u8) {
+    let i = forward_pos2(mask1, mask2);
+    if mask1 & (1 << i) != 0 {
+        (i, n1)
+    } else {
+        (i, n2)
+    }
+}
+
+/// Like `forward_pos3`, but also returns the needle that matched at the
+/// position returned, in the same way as `forward_which2`.
+fn forward_which3(
+    mask1: i32,
+    mask2: i32,
+    mask3: i32,
+    (n1, n2, n3): (u8, u8, u8),
+) -> (usize, u8) {
+    let i = forward_pos3(mask1, mask2, mask3);
+    let bit = 1 << i;
+    if mask1 & bit != 0 {
+        (i, n1)
+    } else if mask2 & bit != 0 {
+        (i, n2)
+    } else {
+        (i, n3)
+    }
+}
+
 /// Compute the position of the last matching byte from the given mask. The
 /// position returned is always in the range [0, 15].
 ///
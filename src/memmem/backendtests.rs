@@ -0,0 +1,290 @@
+/*
+This module checks that every substring search routine in this crate agrees
+with a naive search on the same inputs.
+
+Each routine also has its own tests, but those tend to be shaped around the
+routine they test: the vector routines are only given haystacks they accept,
+Two-Way is only given needles that fit, and so on. Here, every routine that
+accepts a (haystack, needle) pair is run on it, so a bug in how one of them
+handles an edge case that the others get right shows up as a disagreement.
+
+The corpus is generated from a fixed seed. Needles come from tiny alphabets
+(so that they have short periods and many partial matches) as well as from
+arbitrary bytes, with lengths around the boundaries that the searchers care
+about: the single byte case, the generic SIMD limits and vector widths.
+Haystacks are random bytes from the same alphabet, with copies of the needle
+and of its prefix planted at the start, the end and somewhere in between.
+
+On x86_64, the whole suite runs once for each family of vector routines,
+using `x86::force` to select it regardless of what the CPU supports.
+*/
+
+use crate::memmem::{
+    horspool, twoway, Finder, FinderBuilder, FinderRev, Prefilter, Strategy,
+};
+
+/// A search routine under test.
+///
+/// This returns `None` when the routine doesn't accept the given haystack
+/// and needle, and otherwise returns the result of the search.
+type Search = fn(&[u8], &[u8]) -> Option<Option<usize>>;
+
+const FORWARD: &[(&str, Search)] = &[
+    ("Finder", |h, n| Some(Finder::new(n).find(h))),
+    ("Finder/no-prefilter", |h, n| {
+        Some(
+            FinderBuilder::new()
+                .prefilter(Prefilter::None)
+                .build_forward(n)
+                .find(h),
+        )
+    }),
+    ("Finder/adaptive", |h, n| {
+        Some(
+            FinderBuilder::new()
+                .prefilter(Prefilter::Adaptive)
+                .build_forward(n)
+                .find(h),
+        )
+    }),
+    ("Finder/no-simd", |h, n| {
+        Some(
+            FinderBuilder::new()
+                .max_simd_needle_len(0)
+                .build_forward(n)
+                .find(h),
+        )
+    }),
+    ("Finder/horspool", |h, n| {
+        Some(
+            FinderBuilder::new()
+                .strategy(Strategy::Horspool)
+                .build_forward(n)
+                .find(h),
+        )
+    }),
+    ("twoway", |h, n| {
+        if n.is_empty() || n.len() > h.len() {
+            return None;
+        }
+        Some(twoway::Forward::new(n).find(None, h, n))
+    }),
+    ("horspool", |h, n| {
+        if n.len() < 2 {
+            return None;
+        }
+        Some(horspool::find(h, n))
+    }),
+    #[cfg(not(feature = "no-rabinkarp"))]
+    ("rabinkarp", |h, n| Some(crate::memmem::rabinkarp::find(h, n))),
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+    ("genericsimd/sse2", |h, n| {
+        use crate::memmem::{x86::sse::Forward, NeedleInfo};
+
+        let fwd = Forward::new(&NeedleInfo::new(n), n, 255, 0)?;
+        if h.len() < fwd.min_haystack_len() {
+            return None;
+        }
+        Some(fwd.find(h, n))
+    }),
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+    ("genericsimd/avx2", |h, n| {
+        use crate::memmem::{x86::avx::Forward, NeedleInfo};
+
+        let fwd = Forward::new(&NeedleInfo::new(n), n, 255, 0)?;
+        if h.len() < fwd.min_haystack_len() {
+            return None;
+        }
+        Some(fwd.find(h, n))
+    }),
+];
+
+const REVERSE: &[(&str, Search)] = &[
+    ("FinderRev", |h, n| Some(FinderRev::new(n).rfind(h))),
+    ("FinderRev/no-prefilter", |h, n| {
+        Some(
+            FinderBuilder::new()
+                .prefilter(Prefilter::None)
+                .build_reverse(n)
+                .rfind(h),
+        )
+    }),
+    ("FinderRev/no-simd", |h, n| {
+        Some(
+            FinderBuilder::new()
+                .max_simd_needle_len(0)
+                .build_reverse(n)
+                .rfind(h),
+        )
+    }),
+    ("twoway", |h, n| {
+        if n.is_empty() || n.len() > h.len() {
+            return None;
+        }
+        Some(twoway::Reverse::new(n).rfind(None, h, n))
+    }),
+    #[cfg(not(feature = "no-rabinkarp"))]
+    ("rabinkarp", |h, n| Some(crate::memmem::rabinkarp::rfind(h, n))),
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+    ("genericsimd/sse2", |h, n| {
+        use crate::memmem::{x86::sse::Reverse, NeedleInfo};
+
+        let rev = Reverse::new(&NeedleInfo::reverse(n), n, 255)?;
+        if h.len() < rev.min_haystack_len() {
+            return None;
+        }
+        Some(rev.rfind(h, n))
+    }),
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+    ("genericsimd/avx2", |h, n| {
+        use crate::memmem::{x86::avx::Reverse, NeedleInfo};
+
+        let rev = Reverse::new(&NeedleInfo::reverse(n), n, 255)?;
+        if h.len() < rev.min_haystack_len() {
+            return None;
+        }
+        Some(rev.rfind(h, n))
+    }),
+];
+
+const NEEDLE_LENS: &[usize] =
+    &[0, 1, 2, 3, 4, 5, 7, 8, 9, 15, 16, 17, 31, 32, 33, 40];
+
+const HAYSTACK_LENS: &[usize] =
+    &[0, 1, 2, 5, 15, 16, 17, 31, 32, 33, 47, 48, 63, 64, 65, 100, 257];
+
+/// A small xorshift generator, so the corpus is the same on every run.
+struct Rng(u32);
+
+impl Rng {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.next() as usize % n
+    }
+
+    fn bytes(&mut self, alphabet: &[u8], len: usize) -> Vec<u8> {
+        (0..len).map(|_| alphabet[self.below(alphabet.len())]).collect()
+    }
+}
+
+/// Returns the (haystack, needle) pairs that every routine is checked on.
+fn corpus() -> Vec<(Vec<u8>, Vec<u8>)> {
+    let any: Vec<u8> = (0..=255).collect();
+    let alphabets: &[&[u8]] = &[b"ab", b"abz", &any];
+
+    let mut rng = Rng(0x9E37_79B9);
+    let mut pairs = vec![];
+    for &alphabet in alphabets {
+        for &nlen in NEEDLE_LENS {
+            let needle = rng.bytes(alphabet, nlen);
+            for &hlen in HAYSTACK_LENS {
+                let haystack = rng.bytes(alphabet, hlen);
+                pairs.push((haystack.clone(), needle.clone()));
+                if nlen > hlen {
+                    continue;
+                }
+                let plant = |at: usize| {
+                    let mut h = haystack.clone();
+                    h[at..at + nlen].copy_from_slice(&needle);
+                    h
+                };
+                let mid = rng.below(hlen - nlen + 1);
+                pairs.push((plant(0), needle.clone()));
+                pairs.push((plant(hlen - nlen), needle.clone()));
+                pairs.push((plant(mid), needle.clone()));
+                // A near miss: everything but the last byte of the needle,
+                // just before the end of the haystack.
+                if nlen >= 2 {
+                    let mut h = plant(hlen - nlen);
+                    h[hlen - 1] = !needle[nlen - 1];
+                    pairs.push((h, needle.clone()));
+                }
+            }
+        }
+    }
+    pairs
+}
+
+fn naive_find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    (0..=haystack.len()).find(|&i| haystack[i..].starts_with(needle))
+}
+
+fn naive_rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    (0..=haystack.len()).rev().find(|&i| haystack[i..].starts_with(needle))
+}
+
+/// Run `f` once for each family of vector routines, with the name of the
+/// family. Searchers built inside `f` only use routines from that family.
+#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+fn each_isa<F: FnMut(&str)>(mut f: F) {
+    use crate::memmem::x86::force;
+
+    for &isa in force::ALL {
+        force::with_max_isa(isa, || f(&format!("{:?}", isa)));
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", memchr_runtime_simd)))]
+fn each_isa<F: FnMut(&str)>(mut f: F) {
+    f("default")
+}
+
+/// Check every routine in `backends` against `naive` on the whole corpus,
+/// and return how many times each one accepted a pair.
+fn check_all(
+    backends: &[(&str, Search)],
+    naive: fn(&[u8], &[u8]) -> Option<usize>,
+) -> Vec<usize> {
+    let corpus = corpus();
+    let mut accepted = vec![0; backends.len()];
+    each_isa(|isa| {
+        for (haystack, needle) in &corpus {
+            let expected = naive(haystack, needle);
+            for (i, &(name, search)) in backends.iter().enumerate() {
+                let got = match search(haystack, needle) {
+                    None => continue,
+                    Some(got) => got,
+                };
+                accepted[i] += 1;
+                assert_eq!(
+                    expected, got,
+                    "isa: {}, backend: {}, needle: {:?}, haystack: {:?}",
+                    isa, name, needle, haystack,
+                );
+            }
+        }
+    });
+    accepted
+}
+
+/// Make sure that no routine was vacuously skipped on every input.
+fn assert_all_ran(backends: &[(&str, Search)], accepted: &[usize]) {
+    for (&(name, _), &count) in backends.iter().zip(accepted) {
+        // The AVX2 routines never run on a CPU without AVX2.
+        #[cfg(target_arch = "x86_64")]
+        {
+            if name.ends_with("avx2") && !is_x86_feature_detected!("avx2") {
+                continue;
+            }
+        }
+        assert!(count > 0, "{} never ran", name);
+    }
+}
+
+#[test]
+fn forward_backends_agree() {
+    let accepted = check_all(FORWARD, naive_find);
+    assert_all_ran(FORWARD, &accepted);
+}
+
+#[test]
+fn reverse_backends_agree() {
+    let accepted = check_all(REVERSE, naive_rfind);
+    assert_all_ran(REVERSE, &accepted);
+}
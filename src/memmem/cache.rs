@@ -0,0 +1,162 @@
+/*
+This module implements a small cache of finders, for callers that switch
+between a handful of needles often enough that building a new finder each
+time shows up in profiles.
+
+The cache is a vector of owned finders ordered from least to most recently
+used, and lookups scan it linearly. This is meant for a small number of
+needles, where a linear scan comparing needles is cheaper than hashing one,
+and it keeps eviction trivial: the least recently used finder is always
+first.
+*/
+
+use crate::memmem::{Finder, FinderBuilder};
+
+/// A bounded cache of finders, keyed on their needles.
+///
+/// [`FinderCache::get`] returns a finder for the needle given, building and
+/// caching one if there isn't one already. Once the cache is full, building
+/// a new finder evicts the least recently used one. Every finder is built
+/// with the same [`FinderBuilder`] configuration.
+///
+/// Lookups compare the needle given with every cached needle, so this is
+/// meant for a small number of needles (at most a few dozen).
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::FinderCache;
+///
+/// let mut cache = FinderCache::new(2);
+/// assert_eq!(Some(4), cache.get("bar").find(b"foo bar"));
+/// assert_eq!(Some(0), cache.get("foo").find(b"foo bar"));
+/// // "bar" is still cached, so this doesn't build a new finder.
+/// assert_eq!(None, cache.get("bar").find(b"quux"));
+/// assert_eq!(2, cache.len());
+/// ```
+#[derive(Clone, Debug)]
+pub struct FinderCache {
+    /// The configuration used to build every finder in this cache.
+    builder: FinderBuilder,
+    /// The maximum number of finders to keep. This is always at least 1.
+    capacity: usize,
+    /// The cached finders, ordered from least to most recently used.
+    finders: Vec<Finder<'static>>,
+}
+
+impl FinderCache {
+    /// Create a new empty cache that holds at most `capacity` finders, each
+    /// built with the default configuration.
+    ///
+    /// # Panics
+    ///
+    /// This panics when `capacity` is zero.
+    pub fn new(capacity: usize) -> FinderCache {
+        FinderCache::with_builder(FinderBuilder::new(), capacity)
+    }
+
+    /// Create a new empty cache that holds at most `capacity` finders, each
+    /// built with the configuration of the given builder.
+    ///
+    /// # Panics
+    ///
+    /// This panics when `capacity` is zero.
+    pub fn with_builder(
+        builder: FinderBuilder,
+        capacity: usize,
+    ) -> FinderCache {
+        assert!(capacity > 0, "finder cache capacity must be at least 1");
+        FinderCache {
+            builder,
+            capacity,
+            finders: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns a finder for the given needle.
+    ///
+    /// If there's no finder for this needle in the cache, then one is built
+    /// and added to it, evicting the least recently used finder if the cache
+    /// is full. Either way, the finder returned becomes the most recently
+    /// used one.
+    pub fn get<B: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        needle: &B,
+    ) -> &Finder<'static> {
+        let needle = needle.as_ref();
+        match self.finders.iter().position(|f| f.needle() == needle) {
+            Some(i) => self.finders[i..].rotate_left(1),
+            None => {
+                if self.finders.len() == self.capacity {
+                    self.finders.remove(0);
+                }
+                let finder = self.builder.build_forward(needle).into_owned();
+                self.finders.push(finder);
+            }
+        }
+        // Either branch leaves the finder for `needle` at the end.
+        self.finders.last().unwrap()
+    }
+
+    /// Returns the number of finders in this cache.
+    pub fn len(&self) -> usize {
+        self.finders.len()
+    }
+
+    /// Returns true if and only if this cache has no finders.
+    pub fn is_empty(&self) -> bool {
+        self.finders.is_empty()
+    }
+
+    /// Returns the maximum number of finders this cache holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Remove every finder from this cache.
+    pub fn clear(&mut self) {
+        self.finders.clear();
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use super::FinderCache;
+    use crate::memmem::FinderBuilder;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = FinderCache::new(2);
+        cache.get("a");
+        cache.get("b");
+        // Using "a" again makes "b" the least recently used.
+        cache.get("a");
+        cache.get("c");
+        let needles: Vec<&[u8]> =
+            cache.finders.iter().map(|f| f.needle()).collect();
+        assert_eq!(vec![&b"a"[..], b"c"], needles);
+        assert_eq!(2, cache.len());
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(2, cache.capacity());
+    }
+
+    #[test]
+    fn uses_builder() {
+        let mut builder = FinderBuilder::new();
+        builder.empty_needle_matches(false);
+        let mut cache = FinderCache::with_builder(builder, 1);
+        assert_eq!(None, cache.get("").find(b"abc"));
+        assert_eq!(Some(1), cache.get("b").find(b"abc"));
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_capacity() {
+        FinderCache::new(0);
+    }
+}
@@ -0,0 +1,76 @@
+/*
+This module implements a very simple substring search for short needles whose
+first and last bytes differ. It runs memchr on the first byte of the needle,
+and for each candidate, checks the last byte of the needle at the implied
+offset before comparing the rest. This is roughly the approach taken by the
+sliceslice crate, minus the vectorization of the two byte check.
+
+Since the first byte of a needle is often a common one, this can do very
+poorly on long haystacks. (On the sliceslice-i386 benchmarks, it's several
+times slower than the generic SIMD searcher.) So it is only used by the
+oneshot `memmem::find` on short haystacks, in place of Rabin-Karp. There, it
+avoids hashing the needle on every call, and measured 10-15% faster on the
+sliceslice-words/words benchmark. A `Finder` has already hashed its needle,
+and swapping this in for its Rabin-Karp path measured 10-15% slower on the
+same benchmark, so it isn't used there.
+*/
+
+use crate::memmem::util;
+
+/// The longest needle for which this search is used.
+const MAX_NEEDLE_LEN: usize = 8;
+
+/// Whether this search is believed to be faster than a oneshot Rabin-Karp
+/// search for the given needle on a very short haystack.
+pub(crate) fn is_fast(needle: &[u8]) -> bool {
+    2 <= needle.len()
+        && needle.len() <= MAX_NEEDLE_LEN
+        && needle[0] != needle[needle.len() - 1]
+}
+
+/// Search for the first occurrence of needle in haystack.
+///
+/// The needle must have at least 2 bytes.
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    debug_assert!(needle.len() >= 2);
+    if haystack.len() < needle.len() {
+        return None;
+    }
+    let (first, lasti) = (needle[0], needle.len() - 1);
+    let last = needle[lasti];
+    let max = haystack.len() - needle.len();
+    let mut pos = 0;
+    while pos <= max {
+        pos += crate::memchr(first, &haystack[pos..=max])?;
+        if haystack[pos + lasti] == last
+            && util::memcmp(&haystack[pos + 1..pos + lasti], &needle[1..lasti])
+        {
+            return Some(pos);
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Like `find`, but accepts needles of any length, so that it can be run
+/// through the generic substring search test suites. There is no reverse
+/// variant of this search, so those suites check the meta searcher's `rfind`
+/// instead.
+#[cfg(test)]
+fn find_any(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.len() < 2 {
+        crate::memmem::find(haystack, needle)
+    } else {
+        find(haystack, needle)
+    }
+}
+
+#[cfg(test)]
+mod simpletests {
+    define_memmem_simple_tests!(super::find_any, crate::memmem::rfind);
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod proptests {
+    define_memmem_quickcheck_tests!(super::find_any, crate::memmem::rfind);
+}
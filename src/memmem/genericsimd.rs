@@ -7,7 +7,7 @@ use crate::memmem::{util::memcmp, vector::Vector, NeedleInfo};
 /// a case handled by this searcher.
 pub(crate) const MIN_NEEDLE_LEN: usize = 2;
 
-/// The maximum length of a needle required for this algorithm.
+/// The default maximum length of a needle required for this algorithm.
 ///
 /// In reality, there is no hard max here. The code below can handle any
 /// length needle. (Perhaps that suggests there are missing optimizations.)
@@ -32,8 +32,17 @@ pub(crate) const MIN_NEEDLE_LEN: usize = 2;
 /// impact of pathological cases. I did try 64, and some benchmarks got a
 /// little better, and others (particularly the pathological ones), got a lot
 /// worse. So... 32 it is?
+///
+/// Callers may raise this via `FinderBuilder::max_simd_needle_len` when they
+/// trust their inputs and care more about latency than the worst case.
 pub(crate) const MAX_NEEDLE_LEN: usize = 32;
 
+/// The largest needle length that may be configured for this algorithm.
+///
+/// This is a hard limit, since offsets of rare bytes are stored as a `u8`.
+/// (And no rare bytes are selected at all for needles longer than this.)
+pub(crate) const MAX_CONFIGURABLE_NEEDLE_LEN: usize = core::u8::MAX as usize;
+
 /// The implementation of the forward vector accelerated substring search.
 ///
 /// This is extremely similar to the prefilter vector module by the same name.
@@ -49,31 +58,45 @@ pub(crate) const MAX_NEEDLE_LEN: usize = 32;
 /// want to guarantee worst case additive time. Some of the benchmarks try to
 /// justify this (see the pathological ones).
 ///
-/// The prefilter variant of this has more comments. Also note that we only
-/// implement this for forward searches for now. If you have a compelling use
-/// case for accelerated reverse search, please file an issue.
+/// The prefilter variant of this has more comments. See `Reverse` for the
+/// same algorithm applied to reverse searches.
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct Forward {
     rare1i: u8,
     rare2i: u8,
+    /// How far ahead of the current position, in bytes, to issue a software
+    /// prefetch in the main loop. Zero disables prefetching.
+    prefetch_distance: usize,
 }
 
 impl Forward {
     /// Create a new "generic simd" forward searcher. If one could not be
     /// created from the given inputs, then None is returned.
-    pub(crate) fn new(ninfo: &NeedleInfo, needle: &[u8]) -> Option<Forward> {
+    ///
+    /// `max_needle_len` is the longest needle this searcher should be used
+    /// for. It is usually `MAX_NEEDLE_LEN`, and is clamped to
+    /// `MAX_CONFIGURABLE_NEEDLE_LEN`. `prefetch_distance` is usually `0`,
+    /// which disables prefetching.
+    pub(crate) fn new(
+        ninfo: &NeedleInfo,
+        needle: &[u8],
+        max_needle_len: usize,
+        prefetch_distance: usize,
+    ) -> Option<Forward> {
         let (rare1i, rare2i) = ninfo.rarebytes.as_rare_ordered_u8();
+        let max_needle_len =
+            core::cmp::min(max_needle_len, MAX_CONFIGURABLE_NEEDLE_LEN);
         // If the needle is too short or too long, give up. Also, give up
         // if the rare bytes detected are at the same position. (It likely
         // suggests a degenerate case, although it should technically not be
         // possible.)
         if needle.len() < MIN_NEEDLE_LEN
-            || needle.len() > MAX_NEEDLE_LEN
+            || needle.len() > max_needle_len
             || rare1i == rare2i
         {
             return None;
         }
-        Some(Forward { rare1i, rare2i })
+        Some(Forward { rare1i, rare2i, prefetch_distance })
     }
 
     /// Returns the minimum length of haystack that is needed for this searcher
@@ -83,6 +106,51 @@ impl Forward {
     pub(crate) fn min_haystack_len<V: Vector>(&self) -> usize {
         self.rare2i as usize + size_of::<V>()
     }
+
+    /// Returns the number of bytes that `fwd_find_padded` may read past the
+    /// end of the haystack for a particular vector.
+    #[inline(always)]
+    pub(crate) fn padding_len<V: Vector>(&self) -> usize {
+        size_of::<V>()
+    }
+}
+
+/// The implementation of the reverse vector accelerated substring search.
+///
+/// This is the same algorithm as `Forward`, except chunks are visited from
+/// the end of the haystack to its beginning, and candidates within a chunk
+/// are confirmed from last to first. The rare bytes are the same in either
+/// direction, since they're just offsets into the needle.
+///
+/// There is no prefetching here, since hardware prefetchers tend to handle
+/// descending scans just as well as ascending ones.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Reverse {
+    rare1i: u8,
+    rare2i: u8,
+}
+
+impl Reverse {
+    /// Create a new "generic simd" reverse searcher. If one could not be
+    /// created from the given inputs, then None is returned.
+    ///
+    /// `max_needle_len` is the same as for `Forward::new`.
+    pub(crate) fn new(
+        ninfo: &NeedleInfo,
+        needle: &[u8],
+        max_needle_len: usize,
+    ) -> Option<Reverse> {
+        Forward::new(ninfo, needle, max_needle_len, 0)
+            .map(|fwd| Reverse { rare1i: fwd.rare1i, rare2i: fwd.rare2i })
+    }
+
+    /// Returns the minimum length of haystack that is needed for this searcher
+    /// to work for a particular vector. Passing a haystack with a length
+    /// smaller than this will cause `rev_find` to panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len<V: Vector>(&self) -> usize {
+        self.rare2i as usize + size_of::<V>()
+    }
 }
 
 /// Searches the given haystack for the given needle. The needle given should
@@ -122,18 +190,22 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
         MIN_NEEDLE_LEN,
     );
     debug_assert!(
-        needle.len() <= MAX_NEEDLE_LEN,
+        needle.len() <= MAX_CONFIGURABLE_NEEDLE_LEN,
         "needle must be at most {} bytes",
-        MAX_NEEDLE_LEN,
+        MAX_CONFIGURABLE_NEEDLE_LEN,
     );
 
     let (rare1i, rare2i) = (fwd.rare1i as usize, fwd.rare2i as usize);
+    let prefetch_distance = fwd.prefetch_distance;
     let rare1chunk = V::splat(needle[rare1i]);
     let rare2chunk = V::splat(needle[rare2i]);
 
     let start_ptr = haystack.as_ptr();
     let end_ptr = start_ptr.add(haystack.len());
-    let max_ptr = end_ptr.sub(min_haystack_len);
+    // The offset of the last chunk whose loads are entirely within the
+    // haystack.
+    let max_at = haystack.len() - min_haystack_len;
+    let max_ptr = start_ptr.add(max_at);
     let mut ptr = start_ptr;
 
     // N.B. I did experiment with unrolling the loop to deal with size(V)
@@ -142,6 +214,9 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
     // In the end, I decided the complexity from unrolling wasn't worth it. I
     // used the memmem/krate/prebuilt/huge-en/ benchmarks to compare.
     while ptr <= max_ptr {
+        if prefetch_distance != 0 {
+            prefetch(ptr.wrapping_add(prefetch_distance));
+        }
         let m = fwd_find_in_chunk(
             fwd, needle, ptr, end_ptr, rare1chunk, rare2chunk, !0,
         );
@@ -150,39 +225,14 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
         }
         ptr = ptr.add(size_of::<V>());
     }
-    if ptr < end_ptr {
-        let remaining = diff(end_ptr, ptr);
-        debug_assert!(
-            remaining < min_haystack_len,
-            "remaining bytes should be smaller than the minimum haystack \
-             length of {}, but there are {} bytes remaining",
-            min_haystack_len,
-            remaining,
-        );
-        if remaining < needle.len() {
-            return None;
-        }
-        debug_assert!(
-            max_ptr < ptr,
-            "after main loop, ptr should have exceeded max_ptr",
-        );
-        let overlap = diff(ptr, max_ptr);
-        debug_assert!(
-            overlap > 0,
-            "overlap ({}) must always be non-zero",
-            overlap,
-        );
-        debug_assert!(
-            overlap < size_of::<V>(),
-            "overlap ({}) cannot possibly be >= than a vector ({})",
-            overlap,
-            size_of::<V>(),
-        );
-        // The mask has all of its bits set except for the first N least
-        // significant bits, where N=overlap. This way, any matches that
-        // occur in find_in_chunk within the overlap are automatically
-        // ignored.
-        let mask = !((1 << overlap) - 1);
+    let mask = fwd_last_chunk_mask(
+        haystack.len(),
+        needle.len(),
+        max_at,
+        diff(ptr, start_ptr),
+        size_of::<V>(),
+    );
+    if let Some(mask) = mask {
         ptr = max_ptr;
         let m = fwd_find_in_chunk(
             fwd, needle, ptr, end_ptr, rare1chunk, rare2chunk, mask,
@@ -236,6 +286,12 @@ unsafe fn fwd_find_in_chunk<V: Vector>(
         if end_ptr.sub(needle.len()) < ptr {
             return None;
         }
+        // Confirmation uses memcmp for every needle length we support. One
+        // might think a single vector comparison would be faster for needles
+        // that fit in a vector, but a candidate may begin fewer than size(V)
+        // bytes before the end of the haystack, so a full vector load isn't
+        // always possible here. memcmp on these tiny needles is only a
+        // handful of unaligned 4-byte loads anyway.
         let chunk = core::slice::from_raw_parts(ptr, needle.len());
         if memcmp(needle, chunk) {
             return Some(offset);
@@ -245,22 +301,403 @@ unsafe fn fwd_find_in_chunk<V: Vector>(
     None
 }
 
-/// Accepts a chunk-relative offset and returns a haystack relative offset
-/// after updating the prefilter state.
+/// Searches the given haystack for the last occurrence of the given needle.
+/// The needle given should be the same as the needle that this searcher was
+/// initialized with.
+///
+/// # Panics
+///
+/// When the given haystack has a length smaller than `min_haystack_len`.
+///
+/// # Safety
+///
+/// The same as for `fwd_find`.
+#[inline(always)]
+pub(crate) unsafe fn rev_find<V: Vector>(
+    rev: &Reverse,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    if haystack.len() < needle.len() {
+        return None;
+    }
+    let min_haystack_len = rev.min_haystack_len::<V>();
+    assert!(haystack.len() >= min_haystack_len, "haystack too small");
+    debug_assert!(
+        needle.len() >= MIN_NEEDLE_LEN,
+        "needle must be at least {} bytes",
+        MIN_NEEDLE_LEN,
+    );
+    debug_assert!(
+        needle.len() <= MAX_CONFIGURABLE_NEEDLE_LEN,
+        "needle must be at most {} bytes",
+        MAX_CONFIGURABLE_NEEDLE_LEN,
+    );
+
+    let rare1chunk = V::splat(needle[rev.rare1i as usize]);
+    let rare2chunk = V::splat(needle[rev.rare2i as usize]);
+
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    // The last chunk whose loads are entirely within the haystack. Since the
+    // needle is longer than `rare2i`, this chunk covers the last position at
+    // which a match can start.
+    let mut ptr = end_ptr.sub(min_haystack_len);
+    loop {
+        let m = rev_find_in_chunk(
+            rev, needle, ptr, end_ptr, rare1chunk, rare2chunk, !0,
+        );
+        if let Some(chunki) = m {
+            return Some(matched(start_ptr, ptr, chunki));
+        }
+        if diff(ptr, start_ptr) < size_of::<V>() {
+            break;
+        }
+        ptr = ptr.sub(size_of::<V>());
+    }
+    if let Some(mask) =
+        rev_first_chunk_mask(diff(ptr, start_ptr), size_of::<V>())
+    {
+        let m = rev_find_in_chunk(
+            rev, needle, start_ptr, end_ptr, rare1chunk, rare2chunk, mask,
+        );
+        if let Some(chunki) = m {
+            return Some(matched(start_ptr, start_ptr, chunki));
+        }
+    }
+    None
+}
+
+/// Like `fwd_find_in_chunk`, but confirms candidates from the last position
+/// in the chunk to the first, and returns the last match.
+///
+/// # Safety
+///
+/// The same as for `fwd_find_in_chunk`.
+#[inline(always)]
+unsafe fn rev_find_in_chunk<V: Vector>(
+    rev: &Reverse,
+    needle: &[u8],
+    ptr: *const u8,
+    end_ptr: *const u8,
+    rare1chunk: V,
+    rare2chunk: V,
+    mask: u32,
+) -> Option<usize> {
+    let chunk0 = V::load_unaligned(ptr.add(rev.rare1i as usize));
+    let chunk1 = V::load_unaligned(ptr.add(rev.rare2i as usize));
+
+    let eq0 = chunk0.cmpeq(rare1chunk);
+    let eq1 = chunk1.cmpeq(rare2chunk);
+
+    let max_ptr = end_ptr.sub(needle.len());
+    let mut match_offsets = eq0.and(eq1).movemask() & mask;
+    while match_offsets != 0 {
+        let offset = 31 - match_offsets.leading_zeros() as usize;
+        match_offsets &= !(1 << offset);
+        let ptr = ptr.add(offset);
+        // Unlike in the forward direction, a candidate that runs past the
+        // end of the haystack doesn't rule out the earlier ones.
+        if max_ptr < ptr {
+            continue;
+        }
+        let chunk = core::slice::from_raw_parts(ptr, needle.len());
+        if memcmp(needle, chunk) {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+/// Searches `haystack[..len]` for the given needle, where the bytes after
+/// `len` are padding that may be read but never match. The needle given
+/// should be the same as the needle that this searcher was initialized with.
+///
+/// Unlike `fwd_find`, every load is a full vector, even in the last
+/// iteration, so there is no special handling of the end of the haystack and
+/// no minimum haystack length.
+///
+/// # Panics
+///
+/// When there are fewer than `padding_len` bytes after `len`.
+///
+/// # Safety
+///
+/// The same as for `fwd_find`.
+#[inline(always)]
+pub(crate) unsafe fn fwd_find_padded<V: Vector>(
+    fwd: &Forward,
+    haystack: &[u8],
+    len: usize,
+    needle: &[u8],
+) -> Option<usize> {
+    assert!(
+        len <= haystack.len()
+            && haystack.len() - len >= fwd.padding_len::<V>(),
+        "padding too small",
+    );
+    if len < needle.len() {
+        return None;
+    }
+
+    let prefetch_distance = fwd.prefetch_distance;
+    let rare1chunk = V::splat(needle[fwd.rare1i as usize]);
+    let rare2chunk = V::splat(needle[fwd.rare2i as usize]);
+
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    // The last position at which a match can start. Since both rare byte
+    // offsets are less than the needle's length, the loads at this position
+    // start before `end_ptr`, and so end within the padding.
+    let max_ptr = end_ptr.sub(needle.len());
+    let mut ptr = start_ptr;
+    while ptr <= max_ptr {
+        if prefetch_distance != 0 {
+            prefetch(ptr.wrapping_add(prefetch_distance));
+        }
+        // Candidates past `max_ptr` are rejected by checking against
+        // `end_ptr`, so matches are never reported in the padding.
+        let m = fwd_find_in_chunk(
+            fwd, needle, ptr, end_ptr, rare1chunk, rare2chunk, !0,
+        );
+        if let Some(chunki) = m {
+            return Some(matched(start_ptr, ptr, chunki));
+        }
+        ptr = ptr.add(size_of::<V>());
+    }
+    None
+}
+
+/// Returns the mask of positions to search in the last chunk of `fwd_find`,
+/// or `None` if no match can start after the chunks its main loop searched.
+///
+/// `max_at` is the offset of the last chunk whose loads are entirely within
+/// the haystack, and `at` is the offset at which the main loop stopped, which
+/// is the first multiple of `vector_len` greater than `max_at`. The last
+/// chunk starts at `max_at`, so its first `at - max_at` positions were
+/// already searched by the main loop and are left out of the mask.
+///
+/// This is kept free of pointers so that its bounds can be tested on their
+/// own, including under miri, which can't run the vector searches.
+fn fwd_last_chunk_mask(
+    haystack_len: usize,
+    needle_len: usize,
+    max_at: usize,
+    at: usize,
+    vector_len: usize,
+) -> Option<u32> {
+    debug_assert!(max_at < at, "main loop should have passed max_at");
+    if at >= haystack_len || haystack_len - at < needle_len {
+        return None;
+    }
+    // Since the needle is longer than `rare2i`, there are fewer than
+    // `min_haystack_len - vector_len` bytes left when `at - max_at` is a
+    // whole vector, so the check above rules that case out.
+    let overlap = at - max_at;
+    debug_assert!(
+        overlap > 0 && overlap < vector_len,
+        "overlap ({}) must be non-zero and less than a vector ({})",
+        overlap,
+        vector_len,
+    );
+    // The mask has all of its bits set except for the first N least
+    // significant bits, where N=overlap. This way, any matches that occur
+    // in find_in_chunk within the overlap are automatically ignored.
+    Some(!((1 << overlap) - 1))
+}
+
+/// Returns the mask of positions to search in the chunk at the start of the
+/// haystack after the main loop of `rev_find`, or `None` if the main loop
+/// already searched every position.
+///
+/// `at` is the offset of the last chunk the main loop searched, which is
+/// less than `vector_len`. Only the first `at` positions of the chunk at the
+/// start of the haystack haven't been searched yet.
+///
+/// Like `fwd_last_chunk_mask`, this is kept free of pointers for testing.
+fn rev_first_chunk_mask(at: usize, vector_len: usize) -> Option<u32> {
+    debug_assert!(
+        at < vector_len,
+        "overlap ({}) cannot possibly be >= than a vector ({})",
+        at,
+        vector_len,
+    );
+    if at == 0 {
+        None
+    } else {
+        Some((1 << at) - 1)
+    }
+}
+
+/// Accepts a chunk-relative offset and returns a haystack relative offset.
 ///
 /// See the same function with the same name in the prefilter variant of this
 /// algorithm to learned why it's tagged with inline(never). Even here, where
 /// the function is simpler, inlining it leads to poorer codegen. (Although
 /// it does improve some benchmarks, like prebuiltiter/huge-en/common-you.)
+///
+/// This was re-measured with rustc 1.95 on an AVX2 capable x86_64 CPU, for
+/// both the AVX2 and SSE2 searchers, and the result was inconclusive. Simply
+/// inlining this made most of the huge-en/ searches 10-30% faster with AVX2,
+/// including ones that never call this. But the same change made
+/// common-you-are 1.5x slower with SSE2. And inlining it only for AVX2
+/// erased most of the AVX2 gains. Swings that large, in searches that never
+/// reach this function, point to code placement rather than the inlining
+/// itself. So we keep the split until a benchmark shows a consistent win.
 #[cold]
 #[inline(never)]
 fn matched(start_ptr: *const u8, ptr: *const u8, chunki: usize) -> usize {
     diff(ptr, start_ptr) + chunki
 }
 
+/// Hint to the CPU that the cache line containing `ptr` will be read soon.
+///
+/// A prefetch never faults, so `ptr` may point past the end of the haystack.
+/// This is why callers compute it with `wrapping_add`.
+#[inline(always)]
+unsafe fn prefetch(ptr: *const u8) {
+    use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+    _mm_prefetch(ptr as *const i8, _MM_HINT_T0);
+}
+
 /// Subtract `b` from `a` and return the difference. `a` must be greater than
 /// or equal to `b`.
 fn diff(a: *const u8, b: *const u8) -> usize {
     debug_assert!(a >= b);
     (a as usize) - (b as usize)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{fwd_last_chunk_mask, rev_first_chunk_mask};
+
+    /// Calls `f` with every combination of vector length, needle length,
+    /// `rare2i` and haystack length that the searchers accept, where the
+    /// haystack length is at least `min_haystack_len`.
+    #[cfg(feature = "std")]
+    fn each_case(mut f: impl FnMut(usize, usize, usize, usize)) {
+        for &vector_len in &[16, 32] {
+            for needle_len in 2..=40 {
+                for rare2i in 1..needle_len {
+                    let min = rare2i + vector_len;
+                    for haystack_len in min..min + 3 * vector_len {
+                        f(vector_len, needle_len, rare2i, haystack_len);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if `haystack_len` bytes can be loaded as a vector at
+    /// both `at` and `at + rare2i`.
+    #[cfg(feature = "std")]
+    fn in_bounds(
+        vector_len: usize,
+        rare2i: usize,
+        haystack_len: usize,
+        at: usize,
+    ) -> bool {
+        at + rare2i + vector_len <= haystack_len
+    }
+
+    /// Counts a search of every position in `chunk` whose bit is set in
+    /// `mask`.
+    #[cfg(feature = "std")]
+    fn search_masked(chunk: &mut [usize], mask: u32) {
+        for (i, count) in chunk.iter_mut().enumerate() {
+            if mask & (1 << i) != 0 {
+                *count += 1;
+            }
+        }
+    }
+
+    // Mirrors the chunks searched by `fwd_find`, and checks that every
+    // position at which a match can start is searched exactly once, without
+    // any load going past the end of the haystack.
+    #[cfg(feature = "std")]
+    #[test]
+    fn fwd_chunks_cover_haystack() {
+        each_case(|vector_len, needle_len, rare2i, haystack_len| {
+            let mut searched = vec![0; haystack_len];
+            let max_at = haystack_len - (rare2i + vector_len);
+            let mut at = 0;
+            while at <= max_at {
+                assert!(in_bounds(vector_len, rare2i, haystack_len, at));
+                for count in &mut searched[at..at + vector_len] {
+                    *count += 1;
+                }
+                at += vector_len;
+            }
+            let mask = fwd_last_chunk_mask(
+                haystack_len,
+                needle_len,
+                max_at,
+                at,
+                vector_len,
+            );
+            if let Some(mask) = mask {
+                assert!(in_bounds(vector_len, rare2i, haystack_len, max_at));
+                search_masked(
+                    &mut searched[max_at..max_at + vector_len],
+                    mask,
+                );
+            }
+            for (i, &count) in searched.iter().enumerate() {
+                if i + needle_len <= haystack_len {
+                    assert_eq!(
+                        1, count,
+                        "position {} searched {} times (vector length {}, \
+                         needle length {}, rare2i {}, haystack length {})",
+                        i, count, vector_len, needle_len, rare2i, haystack_len,
+                    );
+                }
+            }
+        });
+    }
+
+    // Mirrors the chunks searched by `rev_find`, and checks the same things
+    // as `fwd_chunks_cover_haystack`.
+    #[cfg(feature = "std")]
+    #[test]
+    fn rev_chunks_cover_haystack() {
+        each_case(|vector_len, needle_len, rare2i, haystack_len| {
+            let mut searched = vec![0; haystack_len];
+            let mut at = haystack_len - (rare2i + vector_len);
+            loop {
+                assert!(in_bounds(vector_len, rare2i, haystack_len, at));
+                for count in &mut searched[at..at + vector_len] {
+                    *count += 1;
+                }
+                if at < vector_len {
+                    break;
+                }
+                at -= vector_len;
+            }
+            if let Some(mask) = rev_first_chunk_mask(at, vector_len) {
+                search_masked(&mut searched[..vector_len], mask);
+            }
+            for (i, &count) in searched.iter().enumerate() {
+                if i + needle_len <= haystack_len {
+                    assert_eq!(
+                        1, count,
+                        "position {} searched {} times (vector length {}, \
+                         needle length {}, rare2i {}, haystack length {})",
+                        i, count, vector_len, needle_len, rare2i, haystack_len,
+                    );
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn masks() {
+        assert_eq!(None, fwd_last_chunk_mask(48, 17, 31, 32, 16));
+        assert_eq!(Some(!0b1), fwd_last_chunk_mask(48, 2, 31, 32, 16));
+        assert_eq!(Some(!0b111), fwd_last_chunk_mask(50, 2, 29, 32, 16));
+        assert_eq!(None, fwd_last_chunk_mask(50, 19, 29, 32, 16));
+        assert_eq!(None, rev_first_chunk_mask(0, 16));
+        assert_eq!(Some(0b111), rev_first_chunk_mask(3, 16));
+        assert_eq!(Some(0x7FFF_FFFF), rev_first_chunk_mask(31, 32));
+    }
+}
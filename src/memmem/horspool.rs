@@ -0,0 +1,120 @@
+/*
+This module implements the Boyer-Moore-Horspool substring search algorithm.
+It is never selected automatically. It's only used when a caller asks for it
+with `FinderBuilder::strategy`, for example, to compare it against the
+default searchers on a particular corpus.
+
+Horspool compares the last byte of the needle with the haystack byte that
+lines up with it, and on a mismatch (or after checking the rest of the
+window) shifts the needle by how far that haystack byte is from the end of
+the needle. Bytes that don't occur in the needle let it skip ahead by the
+whole needle length, which is why it does well on long needles made of
+bytes that are uncommon in the haystack. Its worst case is
+O(needle * haystack) though, unlike Two-Way.
+
+The skip table has an entry for every byte, so it takes 256 bytes even with
+one byte per entry. Rather than making every searcher that much bigger, the
+table is built on the stack at the start of each search. That costs a
+256-byte fill and a pass over the needle, which is small next to the
+haystacks this is meant for. Shifts are capped at 255 to fit in a byte.
+This is always safe, since shifting by less than the maximum only means
+re-examining a window that can't match.
+
+ESMAJ has a concise description:
+http://www-igm.univ-mlv.fr/~lecroq/string/node18.html
+*/
+
+use crate::memmem::util::is_prefix;
+
+/// Search for the first occurrence of `needle` in `haystack` using
+/// Boyer-Moore-Horspool.
+///
+/// Callers must ensure that `needle` has at least two bytes.
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    debug_assert!(needle.len() >= 2);
+
+    let m = needle.len();
+    if haystack.len() < m {
+        return None;
+    }
+    let last = needle[m - 1];
+    let skip = skip_table(needle);
+    let mut pos = 0;
+    while pos <= haystack.len() - m {
+        let b = haystack[pos + m - 1];
+        if b == last && is_prefix(&haystack[pos..], &needle[..m - 1]) {
+            return Some(pos);
+        }
+        pos += skip[b as usize] as usize;
+    }
+    None
+}
+
+/// Build the table of how far to shift the needle when the haystack byte
+/// lined up with the end of the needle is a particular byte.
+///
+/// That's the distance from the last occurrence of the byte in the needle,
+/// not counting the needle's last byte, to the end of the needle. Or the
+/// needle's length if it doesn't occur. Either way, it's capped at 255.
+fn skip_table(needle: &[u8]) -> [u8; 256] {
+    let m = needle.len();
+    let mut skip = [cap(m); 256];
+    for (i, &b) in needle[..m - 1].iter().enumerate() {
+        skip[b as usize] = cap(m - 1 - i);
+    }
+    skip
+}
+
+fn cap(shift: usize) -> u8 {
+    if shift > core::u8::MAX as usize {
+        core::u8::MAX
+    } else {
+        shift as u8
+    }
+}
+
+#[cfg(test)]
+mod simpletests {
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        // The searcher itself only handles needles with two or more bytes.
+        if needle.len() < 2 {
+            return crate::memmem::find(haystack, needle);
+        }
+        super::find(haystack, needle)
+    }
+
+    fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        crate::memmem::rfind(haystack, needle)
+    }
+
+    define_memmem_simple_tests!(find, rfind);
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn long_needle() {
+        // The shift for a byte not in the needle is capped, so this needs
+        // more than one step to get past the start of the haystack.
+        let mut needle = vec![b'a'; 300];
+        needle[0] = b'b';
+        let mut haystack = vec![b'c'; 1000];
+        haystack[600..900].copy_from_slice(&needle);
+        assert_eq!(Some(600), find(&haystack, &needle));
+        assert_eq!(None, find(&haystack[..899], &needle));
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod proptests {
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        if needle.len() < 2 {
+            return crate::memmem::find(haystack, needle);
+        }
+        super::find(haystack, needle)
+    }
+
+    fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        crate::memmem::rfind(haystack, needle)
+    }
+
+    define_memmem_quickcheck_tests!(find, rfind);
+}
@@ -0,0 +1,385 @@
+/*
+This module implements substring search for needles with "don't care"
+positions, as commonly found in byte signatures like `E8 ?? ?? ?? ??`.
+
+Neither Two-Way nor Rabin-Karp can cope with wildcards, so the search here is
+naive: every candidate position is confirmed by comparing the needle with the
+haystack at each fixed (non-wildcard) position. What keeps this fast in
+practice is that candidates are found using the same prefilters as the other
+searchers. The only difference is that the prefilter's rare bytes are picked
+from the fixed positions of the needle, since a wildcard position has no byte
+to look for.
+
+The adaptive prefilter is never used, since it re-picks its rare bytes from
+the whole needle as it goes.
+*/
+
+use crate::{
+    cow::CowBytes,
+    memmem::{
+        prefilter::{self, Prefilter, PrefilterFn, PrefilterState},
+        rarebytes::RareNeedleBytes,
+        rkhash::NeedleHash,
+        util, NeedleInfo, SearcherConfig,
+    },
+};
+
+/// A single substring searcher for a needle with wildcard positions.
+///
+/// The needle is paired with a mask of the same length. A position `i` at
+/// which `mask[i] == 0` is a wildcard that matches any byte, and the value of
+/// `needle[i]` is ignored. Every other position must match `needle[i]`
+/// exactly.
+///
+/// A masked finder is built with [`FinderBuilder::build_masked`].
+///
+/// # Complexity
+///
+/// Unlike [`Finder`](crate::memmem::Finder), this does not guarantee linear
+/// time. In the worst case, a search runs in time proportional to the length
+/// of the haystack multiplied by the length of the needle.
+///
+/// [`FinderBuilder::build_masked`]: crate::memmem::FinderBuilder::build_masked
+#[derive(Clone)]
+pub struct MaskedFinder<'n> {
+    needle: CowBytes<'n>,
+    mask: CowBytes<'n>,
+    /// Only the rare bytes of this are meaningful. They are always at fixed
+    /// positions of the needle.
+    ninfo: NeedleInfo,
+    prefn: Option<PrefilterFn>,
+    config: SearcherConfig,
+}
+
+/// This shows the needle, mask and configuration of the finder, but omits
+/// any facts computed about the needle.
+impl<'n> core::fmt::Debug for MaskedFinder<'n> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("MaskedFinder")
+            .field("needle", &util::DebugBytes(self.needle()))
+            .field("mask", &util::DebugBytes(self.mask()))
+            .field("prefilter", &self.config.prefilter)
+            .finish()
+    }
+}
+
+impl<'n> MaskedFinder<'n> {
+    pub(super) fn new(
+        config: SearcherConfig,
+        needle: &'n [u8],
+        mask: &'n [u8],
+    ) -> MaskedFinder<'n> {
+        assert_eq!(
+            needle.len(),
+            mask.len(),
+            "needle and mask must have the same length",
+        );
+        let mut config = config;
+        if config.prefilter.is_adaptive() {
+            config.prefilter = Prefilter::Auto;
+        }
+        let (ninfo, prefn) = match RareNeedleBytes::masked(needle, mask) {
+            None => (
+                NeedleInfo {
+                    rarebytes: RareNeedleBytes::new(0, 0),
+                    nhash: NeedleHash::default(),
+                },
+                None,
+            ),
+            Some(rarebytes) => {
+                let ninfo =
+                    NeedleInfo { rarebytes, nhash: NeedleHash::default() };
+                let prefn =
+                    prefilter::forward(&config.prefilter, &rarebytes, needle);
+                (ninfo, prefn)
+            }
+        };
+        MaskedFinder {
+            needle: CowBytes::new(needle),
+            mask: CowBytes::new(mask),
+            ninfo,
+            prefn,
+            config,
+        }
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack, treating wildcard positions as matching any byte.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// // A relative call instruction: E8 ?? ?? ?? ??
+    /// let needle: [u8; 5] = [0xE8, 0, 0, 0, 0];
+    /// let mask: [u8; 5] = [0xFF, 0, 0, 0, 0];
+    /// let finder = FinderBuilder::new().build_masked(&needle, &mask);
+    ///
+    /// let haystack = [0x90, 0xE8, 0x10, 0x20, 0x30, 0x40, 0xC3];
+    /// assert_eq!(Some(1), finder.find(&haystack));
+    /// assert_eq!(None, finder.find(&haystack[2..]));
+    /// ```
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        self.find_at(&mut self.prefilter_state(), haystack, 0)
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// needle in a haystack, or all occurrences if this finder was built
+    /// with [`FinderBuilder::overlapping`].
+    ///
+    /// The iterator returned yields the starting offset of each occurrence.
+    ///
+    /// [`FinderBuilder::overlapping`]: crate::memmem::FinderBuilder::overlapping
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> MaskedFindIter<'h, 'a> {
+        MaskedFindIter {
+            haystack,
+            prestate: self.prefilter_state(),
+            finder: self.as_ref(),
+            pos: 0,
+        }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle or the mask.
+    ///
+    /// If this is already an owned finder, then this is a no-op. Otherwise,
+    /// this copies the needle and the mask.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn into_owned(self) -> MaskedFinder<'static> {
+        MaskedFinder {
+            needle: self.needle.into_owned(),
+            mask: self.mask.into_owned(),
+            ninfo: self.ninfo,
+            prefn: self.prefn,
+            config: self.config,
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    #[inline]
+    pub fn as_ref(&self) -> MaskedFinder<'_> {
+        MaskedFinder {
+            needle: CowBytes::new(self.needle()),
+            mask: CowBytes::new(self.mask()),
+            ninfo: self.ninfo,
+            prefn: self.prefn,
+            config: self.config,
+        }
+    }
+
+    /// Returns the needle that this finder searches for.
+    ///
+    /// The bytes at wildcard positions are returned as they were given, even
+    /// though they are ignored when searching.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+
+    /// Returns the mask of this finder's needle. A position at which the
+    /// mask is `0` is a wildcard.
+    #[inline]
+    pub fn mask(&self) -> &[u8] {
+        self.mask.as_slice()
+    }
+
+    fn prefilter_state(&self) -> PrefilterState {
+        if self.prefn.is_none() {
+            PrefilterState::inert()
+        } else {
+            PrefilterState::new()
+                .with_max_warmup_bytes(self.config.max_warmup_bytes)
+        }
+    }
+
+    /// Returns the first occurrence of this needle starting at or after
+    /// `pos`.
+    fn find_at(
+        &self,
+        prestate: &mut PrefilterState,
+        haystack: &[u8],
+        mut pos: usize,
+    ) -> Option<usize> {
+        let needle = self.needle();
+        if needle.is_empty() && !self.config.empty_needle_matches {
+            return None;
+        }
+        while pos + needle.len() <= haystack.len() {
+            if let Some(prefn) = self.prefn {
+                if prestate.is_effective() {
+                    pos += prefn.call(
+                        prestate,
+                        &self.ninfo,
+                        &haystack[pos..],
+                        needle,
+                    )?;
+                    if pos + needle.len() > haystack.len() {
+                        return None;
+                    }
+                }
+            }
+            if self.is_match_at(haystack, pos) {
+                return Some(pos);
+            }
+            pos += 1;
+        }
+        None
+    }
+
+    /// Returns true if this needle occurs at `pos` in the given haystack.
+    /// The caller must ensure that the needle fits in the haystack there.
+    fn is_match_at(&self, haystack: &[u8], pos: usize) -> bool {
+        let candidate = &haystack[pos..pos + self.needle().len()];
+        self.needle()
+            .iter()
+            .zip(self.mask())
+            .zip(candidate)
+            .all(|((&n, &m), &h)| m == 0 || n == h)
+    }
+}
+
+/// An iterator over non-overlapping matches of a [`MaskedFinder`].
+///
+/// Matches are reported by the byte offset at which they begin.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle and mask.
+#[derive(Debug)]
+pub struct MaskedFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    prestate: PrefilterState,
+    finder: MaskedFinder<'n>,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for MaskedFindIter<'h, 'n> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+        let result =
+            self.finder.find_at(&mut self.prestate, self.haystack, self.pos);
+        match result {
+            None => {
+                self.pos = self.haystack.len() + 1;
+                None
+            }
+            Some(i) => {
+                // An empty needle matches everywhere, so make sure we make
+                // progress.
+                self.pos = if self.finder.config.overlapping {
+                    i + 1
+                } else {
+                    i + core::cmp::max(1, self.finder.needle().len())
+                };
+                Some(i)
+            }
+        }
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for MaskedFindIter<'h, 'n> {}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use crate::memmem::{FinderBuilder, Prefilter};
+
+    fn naive_find_iter(
+        haystack: &[u8],
+        needle: &[u8],
+        mask: &[u8],
+    ) -> Vec<usize> {
+        let mut found = vec![];
+        let mut pos = 0;
+        while pos + needle.len() <= haystack.len() {
+            let is_match = (0..needle.len())
+                .all(|i| mask[i] == 0 || needle[i] == haystack[pos + i]);
+            if is_match {
+                found.push(pos);
+                pos += core::cmp::max(1, needle.len());
+            } else {
+                pos += 1;
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn wildcards() {
+        let finder =
+            FinderBuilder::new().build_masked(b"a?c", b"\xFF\x00\xFF");
+        let found: Vec<usize> = finder.find_iter(b"abcaxcacc?c").collect();
+        assert_eq!(vec![0, 3, 6], found);
+
+        // A needle of only wildcards matches everywhere it fits.
+        let finder = FinderBuilder::new().build_masked(b"zz", b"\x00\x00");
+        let found: Vec<usize> = finder.find_iter(b"abcde").collect();
+        assert_eq!(vec![0, 2], found);
+
+        let finder = FinderBuilder::new().build_masked(b"", b"");
+        let found: Vec<usize> = finder.find_iter(b"ab").collect();
+        assert_eq!(vec![0, 1, 2], found);
+    }
+
+    #[test]
+    fn long_haystack() {
+        // Long enough for the vectorized prefilters to kick in, with the
+        // only fixed bytes at the ends of the needle.
+        let needle = b"Q??????????????????????????????Z";
+        let mut mask = vec![0; needle.len()];
+        mask[0] = 1;
+        mask[needle.len() - 1] = 1;
+        let finder = FinderBuilder::new().build_masked(needle, &mask);
+        for pos in 0..100 {
+            let mut haystack = vec![b'x'; 200];
+            haystack[pos] = b'Q';
+            haystack[pos + needle.len() - 1] = b'Z';
+            assert_eq!(Some(pos), finder.find(&haystack));
+            haystack[pos + needle.len() - 1] = b'x';
+            assert_eq!(None, finder.find(&haystack));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_mask() {
+        FinderBuilder::new().build_masked(b"abc", b"\xFF\xFF");
+    }
+
+    quickcheck! {
+        fn qc_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<(u8, bool)>,
+            prefilter: bool
+        ) -> bool {
+            // Use a tiny alphabet so that matches are likely.
+            let haystack: Vec<u8> = haystack.iter().map(|&b| b % 3).collect();
+            let mask: Vec<u8> =
+                needle.iter().map(|&(_, fixed)| fixed as u8).collect();
+            let needle: Vec<u8> = needle.iter().map(|&(b, _)| b % 3).collect();
+            let finder = FinderBuilder::new()
+                .prefilter(if prefilter {
+                    Prefilter::Auto
+                } else {
+                    Prefilter::None
+                })
+                .build_masked(&needle, &mask);
+            let found: Vec<usize> = finder.find_iter(&haystack).collect();
+            found == naive_find_iter(&haystack, &needle, &mask)
+        }
+    }
+}
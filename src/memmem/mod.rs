@@ -66,17 +66,46 @@ assert_eq!(None, finder.find(b"quux baz bar"));
 ```
 */
 
-pub use self::prefilter::Prefilter;
+#[cfg(feature = "std")]
+pub use self::cache::FinderCache;
+pub use self::masked::{MaskedFindIter, MaskedFinder};
+#[cfg(feature = "std")]
+pub use self::multicount::MultiCounter;
+#[cfg(feature = "std")]
+pub use self::multifind::{MultiFindIter, MultiFinder};
+pub use self::prefilter::{Prefilter, PrefilterState};
+pub use self::rarebytes::{
+    best_needle, byte_rank, rarest_byte, sample_byte_density,
+};
+pub use self::twoway::TwoWaySearcher;
+
+use core::num::NonZeroUsize;
 
 use crate::{
     cow::CowBytes,
+    memchr::ByteSet,
     memmem::{
-        prefilter::{Pre, PrefilterFn, PrefilterState},
-        rabinkarp::NeedleHash,
+        prefilter::{Pre, PrefilterFn},
         rarebytes::RareNeedleBytes,
+        rkhash::NeedleHash,
     },
 };
 
+/// The smallest haystack for which a forward Two-Way search first checks
+/// whether the rarest byte of the needle occurs at all.
+///
+/// Below this, the extra `memchr` call is unlikely to pay for itself, since
+/// Two-Way (usually with a prefilter) is already quick on short haystacks.
+const RARE_PREPASS_MIN_HAYSTACK_LEN: usize = 1 << 11;
+
+/// The smallest alignment for which `Finder::find_aligned` checks each
+/// conforming offset directly instead of running a search.
+///
+/// With a smaller alignment, most offsets conform anyway and a vectorized
+/// search wins. With a larger one, checking one offset in every `align` is
+/// quicker than a search that finds (and confirms) every match in between.
+const ALIGNED_STEP_MIN: usize = 32;
+
 /// Defines a suite of quickcheck properties for forward and reverse
 /// substring searching.
 ///
@@ -145,16 +174,32 @@ macro_rules! define_memmem_simple_tests {
     };
 }
 
+#[cfg(all(test, feature = "std", not(miri)))]
+mod backendtests;
 mod byte_frequencies;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(not(feature = "no-rabinkarp"))]
+mod firstlast;
 #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
 mod genericsimd;
+mod horspool;
+mod masked;
+#[cfg(feature = "std")]
+mod multicount;
+#[cfg(feature = "std")]
+mod multifind;
 mod prefilter;
+#[cfg(not(feature = "no-rabinkarp"))]
 mod rabinkarp;
 mod rarebytes;
+mod rkhash;
+#[cfg(any(all(test, feature = "std"), feature = "test-util"))]
+pub mod testutil;
 mod twoway;
 mod util;
 // SIMD is only supported on x86_64 currently.
-#[cfg(target_arch = "x86_64")]
+#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
 mod vector;
 #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
 mod x86;
@@ -255,11 +300,16 @@ pub fn rfind_iter<'h, 'n, N: 'n + ?Sized + AsRef<[u8]>>(
 /// ```
 #[inline]
 pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
-    if haystack.len() < 64 {
-        rabinkarp::find(haystack, needle)
-    } else {
-        Finder::new(needle).find(haystack)
+    #[cfg(not(feature = "no-rabinkarp"))]
+    {
+        if haystack.len() < 64 {
+            if firstlast::is_fast(needle) {
+                return firstlast::find(haystack, needle);
+            }
+            return rabinkarp::find(haystack, needle);
+        }
     }
+    Finder::new(needle).find(haystack)
 }
 
 /// Returns the index of the last occurrence of the given needle.
@@ -292,10 +342,87 @@ pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 /// ```
 #[inline]
 pub fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
-    if haystack.len() < 64 {
-        rabinkarp::rfind(haystack, needle)
-    } else {
-        FinderRev::new(needle).rfind(haystack)
+    #[cfg(not(feature = "no-rabinkarp"))]
+    {
+        if haystack.len() < 64 {
+            return rabinkarp::rfind(haystack, needle);
+        }
+    }
+    FinderRev::new(needle).rfind(haystack)
+}
+
+/// Like [`find`], but returns one more than the index found.
+///
+/// Since the value returned is never zero, `Option<NonZeroUsize>` is the same
+/// size as a `usize`, where `None` is represented by `0`. This is convenient
+/// for FFI, where "not found" is often reported as `0`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use memchr::memmem;
+///
+/// let haystack = b"foo bar baz";
+/// let found = memmem::find_nz(haystack, b"bar").map_or(0, |i| i.get());
+/// assert_eq!(found, 5);
+/// assert_eq!(None, memmem::find_nz(haystack, b"quux"));
+/// ```
+#[inline]
+pub fn find_nz(haystack: &[u8], needle: &[u8]) -> Option<NonZeroUsize> {
+    find(haystack, needle).and_then(|i| NonZeroUsize::new(i + 1))
+}
+
+/// Like [`rfind`], but returns one more than the index found.
+///
+/// See [`find_nz`] for why this is useful.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use memchr::memmem;
+///
+/// let haystack = b"foo bar baz";
+/// let found = memmem::rfind_nz(haystack, b"ba").map_or(0, |i| i.get());
+/// assert_eq!(found, 9);
+/// assert_eq!(None, memmem::rfind_nz(haystack, b"quux"));
+/// ```
+#[inline]
+pub fn rfind_nz(haystack: &[u8], needle: &[u8]) -> Option<NonZeroUsize> {
+    rfind(haystack, needle).and_then(|i| NonZeroUsize::new(i + 1))
+}
+
+/// Call `f` with the position of every occurrence of `b` in `haystack`, until
+/// `f` returns false.
+///
+/// This is used for single byte needles, whose matches are often so dense
+/// that the cost of starting a new search after each one dominates. So after
+/// each match found by memchr, the 64 bytes starting at it are compared all at
+/// once, and every match in that block is reported from the resulting bitset.
+/// This makes sparse matches only slightly more expensive, since memchr is
+/// still used to skip over stretches without any matches.
+fn for_each_byte_match<F: FnMut(usize) -> bool>(
+    b: u8,
+    haystack: &[u8],
+    mut f: F,
+) {
+    let mut pos = 0;
+    while let Some(i) = crate::memchr(b, &haystack[pos..]) {
+        pos += i;
+        let end = core::cmp::min(pos + 64, haystack.len());
+        let mut mask = [0u64; 1];
+        crate::memchr_mask(b, &haystack[pos..end], &mut mask);
+        let mut bits = mask[0];
+        while bits != 0 {
+            if !f(pos + bits.trailing_zeros() as usize) {
+                return;
+            }
+            bits &= bits - 1;
+        }
+        pos = end;
     }
 }
 
@@ -311,6 +438,8 @@ pub struct FindIter<'h, 'n> {
     prestate: PrefilterState,
     finder: Finder<'n>,
     pos: usize,
+    /// How far past the start of each match the next search begins.
+    stride: usize,
 }
 
 impl<'h, 'n> FindIter<'h, 'n> {
@@ -320,7 +449,31 @@ impl<'h, 'n> FindIter<'h, 'n> {
         finder: Finder<'n>,
     ) -> FindIter<'h, 'n> {
         let prestate = finder.searcher.prefilter_state();
-        FindIter { haystack, prestate, finder, pos: 0 }
+        let stride = finder.searcher.stride();
+        FindIter { haystack, prestate, finder, pos: 0, stride }
+    }
+
+    /// Returns the part of the haystack that hasn't been searched yet.
+    ///
+    /// This is the suffix of the haystack following the end of the last
+    /// match reported, or the entire haystack if no match has been reported
+    /// yet. It is where the next search begins. (When overlapping matches
+    /// are reported, it starts one byte after the start of the last match
+    /// instead.)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem;
+    ///
+    /// let mut it = memmem::find_iter(b"key: value\r\n\r\nbody", "\r\n");
+    /// assert_eq!(Some(10), it.next());
+    /// assert_eq!(b"\r\nbody", it.remaining());
+    /// ```
+    #[inline]
+    pub fn remaining(&self) -> &'h [u8] {
+        let pos = core::cmp::min(self.pos, self.haystack.len());
+        &self.haystack[pos..]
     }
 }
 
@@ -339,13 +492,117 @@ impl<'h, 'n> Iterator for FindIter<'h, 'n> {
             None => None,
             Some(i) => {
                 let pos = self.pos + i;
-                self.pos = pos + core::cmp::max(1, self.finder.needle().len());
+                self.pos = pos + self.stride;
                 Some(pos)
             }
         }
     }
 }
 
+impl<'h, 'n> core::iter::FusedIterator for FindIter<'h, 'n> {}
+
+/// An iterator over non-overlapping substring matches accepted by a
+/// predicate.
+///
+/// Matches are reported by the byte offset at which they begin. Rejected
+/// matches don't prevent an overlapping match from being reported.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle. `F` is the type of the predicate.
+pub struct FindIterFilter<'h, 'n, F> {
+    haystack: &'h [u8],
+    prestate: PrefilterState,
+    finder: Finder<'n>,
+    pos: usize,
+    pred: F,
+}
+
+impl<'h, 'n, F> core::fmt::Debug for FindIterFilter<'h, 'n, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("FindIterFilter")
+            .field("haystack", &self.haystack)
+            .field("prestate", &self.prestate)
+            .field("finder", &self.finder)
+            .field("pos", &self.pos)
+            .finish()
+    }
+}
+
+impl<'h, 'n, F: FnMut(usize) -> bool> Iterator for FindIterFilter<'h, 'n, F> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.pos <= self.haystack.len() {
+            let i = self
+                .finder
+                .searcher
+                .find(&mut self.prestate, &self.haystack[self.pos..])?;
+            let pos = self.pos + i;
+            if (self.pred)(pos) {
+                self.pos = pos + self.finder.searcher.stride();
+                return Some(pos);
+            }
+            self.pos = pos + 1;
+        }
+        None
+    }
+}
+
+impl<'h, 'n, F: FnMut(usize) -> bool> core::iter::FusedIterator
+    for FindIterFilter<'h, 'n, F>
+{
+}
+
+/// An iterator over non-overlapping substring matches in a `&str` haystack.
+///
+/// Matches are reported by the byte offset at which they begin, and every
+/// match both starts and ends at a char boundary.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct FindStrIter<'h, 'n> {
+    haystack: &'h str,
+    prestate: PrefilterState,
+    finder: Finder<'n>,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for FindStrIter<'h, 'n> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let haystack = self.haystack.as_bytes();
+        let nlen = self.finder.needle().len();
+        while self.pos <= haystack.len() {
+            let i = self
+                .finder
+                .searcher
+                .find(&mut self.prestate, &haystack[self.pos..])?;
+            let pos = self.pos + i;
+            if is_str_match(self.haystack, pos, nlen) {
+                self.pos = pos + core::cmp::max(1, nlen);
+                return Some(pos);
+            }
+            self.pos = pos + 1;
+        }
+        None
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for FindStrIter<'h, 'n> {}
+
+/// Returns true if and only if a match of length `len` at `start` in
+/// `haystack` begins and ends at char boundaries.
+///
+/// This is always true when the needle is valid UTF-8 and non-empty, so it
+/// only rejects matches of an empty needle inside a char, or of a needle that
+/// isn't valid UTF-8.
+#[inline(always)]
+fn is_str_match(haystack: &str, start: usize, len: usize) -> bool {
+    haystack.is_char_boundary(start) && haystack.is_char_boundary(start + len)
+}
+
 /// An iterator over non-overlapping substring matches in reverse.
 ///
 /// Matches are reported by the byte offset at which they begin.
@@ -355,6 +612,7 @@ impl<'h, 'n> Iterator for FindIter<'h, 'n> {
 #[derive(Debug)]
 pub struct FindRevIter<'h, 'n> {
     haystack: &'h [u8],
+    prestate: PrefilterState,
     finder: FinderRev<'n>,
     /// When searching with an empty needle, this gets set to `None` after
     /// we've yielded the last element at `0`.
@@ -367,8 +625,33 @@ impl<'h, 'n> FindRevIter<'h, 'n> {
         haystack: &'h [u8],
         finder: FinderRev<'n>,
     ) -> FindRevIter<'h, 'n> {
+        let prestate = finder.searcher.prefilter_state();
         let pos = Some(haystack.len());
-        FindRevIter { haystack, finder, pos }
+        FindRevIter { haystack, prestate, finder, pos }
+    }
+
+    /// Returns the part of the haystack that hasn't been searched yet.
+    ///
+    /// This is the prefix of the haystack preceding the start of the last
+    /// match reported, or the entire haystack if no match has been reported
+    /// yet. It is where the next search ends. (When overlapping matches are
+    /// reported, it ends one byte before the end of the last match instead.)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem;
+    ///
+    /// let mut it = memmem::rfind_iter(b"a/b/c", "/");
+    /// assert_eq!(Some(3), it.next());
+    /// assert_eq!(b"a/b", it.remaining());
+    /// ```
+    #[inline]
+    pub fn remaining(&self) -> &'h [u8] {
+        match self.pos {
+            None => &[],
+            Some(end) => &self.haystack[..end],
+        }
     }
 }
 
@@ -376,182 +659,570 @@ impl<'h, 'n> Iterator for FindRevIter<'h, 'n> {
     type Item = usize;
 
     fn next(&mut self) -> Option<usize> {
-        let pos = match self.pos {
-            None => return None,
-            Some(pos) => pos,
-        };
-        let result = self.finder.rfind(&self.haystack[..pos]);
-        match result {
-            None => None,
-            Some(i) => {
-                if pos == i {
-                    self.pos = pos.checked_sub(1);
-                } else {
-                    self.pos = Some(i);
-                }
-                Some(i)
-            }
-        }
+        rfind_next(
+            &self.finder,
+            &mut self.prestate,
+            self.haystack,
+            &mut self.pos,
+        )
     }
 }
 
-/// A single substring searcher fixed to a particular needle.
+impl<'h, 'n> core::iter::FusedIterator for FindRevIter<'h, 'n> {}
+
+/// An iterator over non-overlapping substring matches in reverse, using a
+/// prefilter state borrowed from the caller.
 ///
-/// The purpose of this type is to permit callers to construct a substring
-/// searcher that can be used to search haystacks without the overhead of
-/// constructing the searcher in the first place. This is a somewhat niche
-/// concern when it's necessary to re-use the same needle to search multiple
-/// different haystacks with as little overhead as possible. In general, using
-/// [`find`] is good enough, but `Finder` is useful when you can meaningfully
-/// observe searcher construction time in a profile.
+/// This is created by [`FinderRev::rfind_iter_with_state`].
 ///
-/// When the `std` feature is enabled, then this type has an `into_owned`
-/// version which permits building a `Finder` that is not connected to
-/// the lifetime of its needle.
-#[derive(Clone, Debug)]
-pub struct Finder<'n> {
-    searcher: Searcher<'n>,
+/// Matches are reported by the byte offset at which they begin.
+///
+/// `'h` is the lifetime of the haystack, `'n` is the lifetime of the needle
+/// and `'s` is the lifetime of the prefilter state.
+#[derive(Debug)]
+pub struct FindRevIterWithState<'h, 'n, 's> {
+    haystack: &'h [u8],
+    prestate: &'s mut PrefilterState,
+    finder: FinderRev<'n>,
+    /// When searching with an empty needle, this gets set to `None` after
+    /// we've yielded the last element at `0`.
+    pos: Option<usize>,
 }
 
-impl<'n> Finder<'n> {
-    /// Create a new finder for the given needle.
-    #[inline]
-    pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> Finder<'n> {
-        FinderBuilder::new().build_forward(needle)
+impl<'h, 'n, 's> Iterator for FindRevIterWithState<'h, 'n, 's> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        rfind_next(&self.finder, self.prestate, self.haystack, &mut self.pos)
     }
+}
 
-    /// Returns the index of the first occurrence of this needle in the given
-    /// haystack.
-    ///
-    /// # Complexity
-    ///
-    /// This routine is guaranteed to have worst case linear time complexity
-    /// with respect to both the needle and the haystack. That is, this runs
-    /// in `O(needle.len() + haystack.len())` time.
-    ///
-    /// This routine is also guaranteed to have worst case constant space
-    /// complexity.
-    ///
-    /// # Examples
-    ///
-    /// Basic usage:
-    ///
-    /// ```
-    /// use memchr::memmem::Finder;
-    ///
-    /// let haystack = b"foo bar baz";
-    /// assert_eq!(Some(0), Finder::new("foo").find(haystack));
-    /// assert_eq!(Some(4), Finder::new("bar").find(haystack));
-    /// assert_eq!(None, Finder::new("quux").find(haystack));
-    /// ```
-    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
-        self.searcher.find(&mut self.searcher.prefilter_state(), haystack)
+impl<'h, 'n, 's> core::iter::FusedIterator
+    for FindRevIterWithState<'h, 'n, 's>
+{
+}
+
+/// Find the next match of a reverse iteration, ending at `pos`, and advance
+/// `pos` past it.
+#[inline(always)]
+fn rfind_next(
+    finder: &FinderRev<'_>,
+    prestate: &mut PrefilterState,
+    haystack: &[u8],
+    pos: &mut Option<usize>,
+) -> Option<usize> {
+    let end = match *pos {
+        None => return None,
+        Some(end) => end,
+    };
+    let result = finder.searcher.rfind(prestate, &haystack[..end]);
+    match result {
+        None => None,
+        Some(i) => {
+            if end == i {
+                *pos = end.checked_sub(1);
+            } else if finder.searcher.overlapping {
+                // Stop just short of the end of this match, so that a match
+                // overlapping it may still be found. An empty needle always
+                // takes the branch above.
+                *pos = Some(i + finder.needle().len() - 1);
+            } else {
+                *pos = Some(i);
+            }
+            Some(i)
+        }
     }
+}
+
+/// An iterator over the matches found by a reverse finder, in ascending
+/// order.
+///
+/// Since matches can only be found from the end of a haystack by a reverse
+/// finder, every match is found and buffered when this iterator is created.
+///
+/// This is created by [`FinderRev::find_iter_fwd`]. It is only available when
+/// the `std` feature is enabled.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct FindRevFwdIter {
+    it: std::vec::IntoIter<usize>,
+}
+
+#[cfg(feature = "std")]
+impl Iterator for FindRevFwdIter {
+    type Item = usize;
 
-    /// Returns an iterator over all occurrences of a substring in a haystack.
-    ///
-    /// # Complexity
-    ///
-    /// This routine is guaranteed to have worst case linear time complexity
-    /// with respect to both the needle and the haystack. That is, this runs
-    /// in `O(needle.len() + haystack.len())` time.
-    ///
-    /// This routine is also guaranteed to have worst case constant space
-    /// complexity.
-    ///
-    /// # Examples
-    ///
-    /// Basic usage:
-    ///
-    /// ```
-    /// use memchr::memmem::Finder;
-    ///
-    /// let haystack = b"foo bar foo baz foo";
-    /// let finder = Finder::new(b"foo");
-    /// let mut it = finder.find_iter(haystack);
-    /// assert_eq!(Some(0), it.next());
-    /// assert_eq!(Some(8), it.next());
-    /// assert_eq!(Some(16), it.next());
-    /// assert_eq!(None, it.next());
-    /// ```
     #[inline]
-    pub fn find_iter<'a, 'h>(
-        &'a self,
-        haystack: &'h [u8],
-    ) -> FindIter<'h, 'a> {
-        FindIter::new(haystack, self.as_ref())
+    fn next(&mut self) -> Option<usize> {
+        self.it.next()
     }
 
-    /// Convert this finder into its owned variant, such that it no longer
-    /// borrows the needle.
-    ///
-    /// If this is already an owned finder, then this is a no-op. Otherwise,
-    /// this copies the needle.
-    ///
-    /// This is only available when the `std` feature is enabled.
-    #[cfg(feature = "std")]
     #[inline]
-    pub fn into_owned(self) -> Finder<'static> {
-        Finder { searcher: self.searcher.into_owned() }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
     }
+}
 
-    /// Convert this finder into its borrowed variant.
-    ///
-    /// This is primarily useful if your finder is owned and you'd like to
-    /// store its borrowed variant in some intermediate data structure.
-    ///
-    /// Note that the lifetime parameter of the returned finder is tied to the
-    /// lifetime of `self`, and may be shorter than the `'n` lifetime of the
-    /// needle itself. Namely, a finder's needle can be either borrowed or
-    /// owned, so the lifetime of the needle returned must necessarily be the
-    /// shorter of the two.
+#[cfg(feature = "std")]
+impl DoubleEndedIterator for FindRevFwdIter {
     #[inline]
-    pub fn as_ref(&self) -> Finder<'_> {
-        Finder { searcher: self.searcher.as_ref() }
+    fn next_back(&mut self) -> Option<usize> {
+        self.it.next_back()
     }
+}
 
-    /// Returns the needle that this finder searches for.
-    ///
-    /// Note that the lifetime of the needle returned is tied to the lifetime
-    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
-    /// finder's needle can be either borrowed or owned, so the lifetime of the
-    /// needle returned must necessarily be the shorter of the two.
-    #[inline]
-    pub fn needle(&self) -> &[u8] {
-        self.searcher.needle()
+#[cfg(feature = "std")]
+impl ExactSizeIterator for FindRevFwdIter {}
+
+#[cfg(feature = "std")]
+impl core::iter::FusedIterator for FindRevFwdIter {}
+
+/// An iterator over the tokens between non-overlapping substring matches.
+///
+/// Each item is a token along with the offset at which the match ending the
+/// token begins. The last token is always reported with no match offset,
+/// since it runs to the end of the haystack.
+///
+/// This is created by [`Finder::tokenize`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct Tokens<'h, 'n> {
+    it: FindIter<'h, 'n>,
+    /// The offset at which the next token begins, or `None` once the last
+    /// token has been yielded.
+    start: Option<usize>,
+}
+
+impl<'h, 'n> Tokens<'h, 'n> {
+    #[inline(always)]
+    pub(crate) fn new(it: FindIter<'h, 'n>) -> Tokens<'h, 'n> {
+        Tokens { it, start: Some(0) }
     }
 }
 
-/// A single substring reverse searcher fixed to a particular needle.
+impl<'h, 'n> Iterator for Tokens<'h, 'n> {
+    type Item = (&'h [u8], Option<usize>);
+
+    fn next(&mut self) -> Option<(&'h [u8], Option<usize>)> {
+        let start = self.start?;
+        let haystack = self.it.haystack;
+        match self.it.next() {
+            None => {
+                self.start = None;
+                Some((&haystack[start..], None))
+            }
+            Some(i) => {
+                self.start = Some(i + self.it.finder.needle().len());
+                Some((&haystack[start..i], Some(i)))
+            }
+        }
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for Tokens<'h, 'n> {}
+
+/// A single match of a needle in a haystack.
+///
+/// This is reported by [`Finder::find_iter_match`] and
+/// [`FinderRev::rfind_iter_match`]. Currently, the length of a match is
+/// always the length of the needle. But reporting it with each match leaves
+/// room for searchers whose matches may differ in length.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Match {
+    start: usize,
+    len: usize,
+}
+
+impl Match {
+    /// Returns the offset at which this match begins.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the offset immediately following the end of this match.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.start + self.len
+    }
+
+    /// Returns the length of this match, in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if and only if this match is empty. This only happens
+    /// when searching for an empty needle.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the range of offsets of this match, suitable for slicing the
+    /// haystack.
+    #[inline]
+    pub fn range(&self) -> core::ops::Range<usize> {
+        self.start..self.end()
+    }
+}
+
+/// An iterator over non-overlapping substring matches, reported as
+/// [`Match`] values.
+///
+/// This is created by [`Finder::find_iter_match`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct FindMatchIter<'h, 'n> {
+    it: FindIter<'h, 'n>,
+}
+
+impl<'h, 'n> Iterator for FindMatchIter<'h, 'n> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        let start = self.it.next()?;
+        Some(Match { start, len: self.it.finder.needle().len() })
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for FindMatchIter<'h, 'n> {}
+
+/// An iterator over non-overlapping substring matches, reported by the offset
+/// immediately following the end of each match.
+///
+/// This is created by [`Finder::find_end_iter`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct FindEndIter<'h, 'n> {
+    it: FindIter<'h, 'n>,
+}
+
+impl<'h, 'n> Iterator for FindEndIter<'h, 'n> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let start = self.it.next()?;
+        Some(start + self.it.finder.needle().len())
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for FindEndIter<'h, 'n> {}
+
+/// An iterator over non-overlapping substring matches, reported as absolute
+/// offsets by adding a base offset to the start of each match.
+///
+/// This is created by [`Finder::find_iter_based`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct FindIterBased<'h, 'n> {
+    it: FindIter<'h, 'n>,
+    base: u64,
+}
+
+impl<'h, 'n> Iterator for FindIterBased<'h, 'n> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let start = self.it.next()?;
+        // usize is never wider than 64 bits on the targets we support.
+        let offset = self.base.checked_add(start as u64);
+        Some(offset.expect("match offset overflows u64"))
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for FindIterBased<'h, 'n> {}
+
+/// An iterator over the maximal spans of a haystack covered by matches of a
+/// needle, where overlapping and adjacent matches are merged.
+///
+/// This is created by [`Finder::find_merged_ranges`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct FindMergedRanges<'h, 'n> {
+    haystack: &'h [u8],
+    prestate: PrefilterState,
+    finder: Finder<'n>,
+    pos: usize,
+}
+
+impl<'h, 'n> FindMergedRanges<'h, 'n> {
+    /// Returns the offset of the first match at or after `pos`.
+    fn find_at(&mut self, pos: usize) -> Option<usize> {
+        if pos > self.haystack.len() {
+            return None;
+        }
+        self.finder
+            .searcher
+            .find(&mut self.prestate, &self.haystack[pos..])
+            .map(|i| pos + i)
+    }
+}
+
+impl<'h, 'n> Iterator for FindMergedRanges<'h, 'n> {
+    type Item = core::ops::Range<usize>;
+
+    fn next(&mut self) -> Option<core::ops::Range<usize>> {
+        let nlen = self.finder.needle().len();
+        let mut last = self.find_at(self.pos)?;
+        let start = last;
+        // Every match has the same length, so each match that starts within
+        // (or right at the end of) the span so far extends it.
+        loop {
+            match self.find_at(last + 1) {
+                Some(i) if i <= last + nlen => last = i,
+                Some(i) => {
+                    self.pos = i;
+                    break;
+                }
+                None => {
+                    self.pos = self.haystack.len() + 1;
+                    break;
+                }
+            }
+        }
+        Some(start..last + nlen)
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for FindMergedRanges<'h, 'n> {}
+
+/// An iterator over non-overlapping substring matches in reverse, reported
+/// as [`Match`] values.
+///
+/// This is created by [`FinderRev::rfind_iter_match`].
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct FindRevMatchIter<'h, 'n> {
+    it: FindRevIter<'h, 'n>,
+}
+
+impl<'h, 'n> Iterator for FindRevMatchIter<'h, 'n> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        let start = self.it.next()?;
+        Some(Match { start, len: self.it.finder.needle().len() })
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for FindRevMatchIter<'h, 'n> {}
+
+/// A resumable position for forward substring searches.
+///
+/// A cursor is used with [`Finder::find_from_cursor`] to find successive
+/// non-overlapping matches. Unlike [`FindIter`], a cursor does not borrow
+/// either the haystack or the finder. It is just a position, so it can be
+/// stored in a parser's state and used to resume searching later.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Cursor {
+    pos: usize,
+}
+
+impl Cursor {
+    /// Create a new cursor positioned at the start of a haystack.
+    #[inline]
+    pub fn new() -> Cursor {
+        Cursor::at(0)
+    }
+
+    /// Create a new cursor positioned at the given offset in a haystack.
+    #[inline]
+    pub fn at(pos: usize) -> Cursor {
+        Cursor { pos }
+    }
+
+    /// Returns the offset at which the next search will begin.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+/// The facts computed about a needle by a [`Finder`].
+///
+/// This is returned by [`Finder::into_parts`] and accepted by
+/// [`Finder::from_parts`]. It permits computing these facts ahead of time,
+/// and then reconstructing a finder without computing anything at runtime.
+///
+/// The fields correspond to a critical factorization of the needle used by
+/// the Two-Way algorithm, along with the offsets of rare bytes in the needle
+/// and a Rabin-Karp hash of the needle. Their values are an implementation
+/// detail and may change in semver compatible releases. Parts should only be
+/// given to [`Finder::from_parts`] when they were produced by
+/// [`Finder::into_parts`] from the same version of this crate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TwoWayParams {
+    /// The critical position of the needle's factorization.
+    pub critical_pos: usize,
+    /// The amount to shift by after a mismatch.
+    pub shift: TwoWayShift,
+    /// A bitset where bit `i` is set when the needle contains a byte `b`
+    /// such that `b % 64 == i`.
+    pub byteset: u64,
+    /// The offset of the rarest byte in the needle.
+    pub rare1i: u8,
+    /// The offset of the second rarest byte in the needle.
+    pub rare2i: u8,
+    /// The Rabin-Karp hash of the needle.
+    pub hash: u32,
+    /// The factor used to remove a byte from a rolling Rabin-Karp hash.
+    pub hash_2pow: u32,
+}
+
+/// The shift used by the Two-Way algorithm after a mismatch.
+///
+/// This is part of [`TwoWayParams`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TwoWayShift {
+    /// The needle is periodic with the given period.
+    Small {
+        /// The period of the needle.
+        period: usize,
+    },
+    /// The needle has a large period, and it's safe to shift by the given
+    /// amount.
+    Large {
+        /// The amount to shift by.
+        shift: usize,
+    },
+}
+
+/// Statistics about a single forward search, as reported by
+/// [`Finder::find_instrumented`].
+///
+/// The number of false positives produced by the prefilter is reported by
+/// [`SearchStats::rejected`]. A large number of rejections relative to the
+/// number of bytes scanned means the haystack is full of bytes that the
+/// prefilter considers rare for this needle.
+///
+/// Note that such haystacks cannot be used to make [`Finder::find`] slow.
+/// Its prefilter measures its own effectiveness as it goes, and switches
+/// itself off once its candidates stop skipping enough of the haystack, so
+/// `find` keeps its linear time guarantee no matter how many false positives
+/// the prefilter would produce. Conversely, `find_instrumented` itself does
+/// not have that guarantee, so it shouldn't be used as a search routine on
+/// untrusted haystacks.
+///
+/// This is only available when the `instrumentation` feature is enabled.
+#[cfg(feature = "instrumentation")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchStats {
+    candidates: usize,
+    confirmations: usize,
+    rejected: usize,
+    bytes_scanned: usize,
+}
+
+#[cfg(feature = "instrumentation")]
+impl SearchStats {
+    /// The number of candidate match positions reported by the prefilter.
+    pub fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    /// The number of times a candidate was confirmed (or rejected) by
+    /// comparing it with the entire needle.
+    pub fn confirmations(&self) -> usize {
+        self.confirmations
+    }
+
+    /// The number of candidates that were false positives. That is, the
+    /// number of confirmations that did not find a match.
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    /// The total number of haystack bytes skipped over by the prefilter while
+    /// looking for candidates.
+    pub fn bytes_scanned(&self) -> usize {
+        self.bytes_scanned
+    }
+}
+
+/// A single substring searcher fixed to a particular needle.
 ///
 /// The purpose of this type is to permit callers to construct a substring
 /// searcher that can be used to search haystacks without the overhead of
 /// constructing the searcher in the first place. This is a somewhat niche
 /// concern when it's necessary to re-use the same needle to search multiple
-/// different haystacks with as little overhead as possible. In general,
-/// using [`rfind`] is good enough, but `FinderRev` is useful when you can
-/// meaningfully observe searcher construction time in a profile.
+/// different haystacks with as little overhead as possible. In general, using
+/// [`find`] is good enough, but `Finder` is useful when you can meaningfully
+/// observe searcher construction time in a profile.
 ///
 /// When the `std` feature is enabled, then this type has an `into_owned`
-/// version which permits building a `FinderRev` that is not connected to
+/// version which permits building a `Finder` that is not connected to
 /// the lifetime of its needle.
-#[derive(Clone, Debug)]
-pub struct FinderRev<'n> {
-    searcher: SearcherRev<'n>,
+#[derive(Clone)]
+pub struct Finder<'n> {
+    searcher: Searcher<'n>,
 }
 
-impl<'n> FinderRev<'n> {
-    /// Create a new reverse finder for the given needle.
+/// This shows the needle and configuration of the finder, but omits any
+/// facts computed about the needle, since they are only interesting when
+/// debugging this crate.
+impl<'n> core::fmt::Debug for Finder<'n> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Finder")
+            .field("needle", &util::DebugBytes(self.needle()))
+            .field("prefilter", &self.searcher.config.prefilter)
+            .finish()
+    }
+}
+
+/// The default finder has an empty needle, so it matches at every position.
+/// This is the same as [`Finder::empty`].
+impl<'n> Default for Finder<'n> {
     #[inline]
-    pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> FinderRev<'n> {
-        FinderBuilder::new().build_reverse(needle)
+    fn default() -> Finder<'n> {
+        Finder::empty()
     }
+}
 
-    /// Returns the index of the last occurrence of this needle in the given
-    /// haystack.
+impl<'n> Finder<'n> {
+    /// Create a new finder for the empty needle, using the default
+    /// configuration.
     ///
-    /// The haystack may be any type that can be cheaply converted into a
-    /// `&[u8]`. This includes, but is not limited to, `&str` and `&[u8]`.
+    /// An empty needle matches at every position, so this is the same as
+    /// `Finder::new("")`, except that it can be used in a constant
+    /// expression.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// const EMPTY: Finder<'static> = Finder::empty();
+    /// assert_eq!(Some(0), EMPTY.find(b"foo"));
+    /// assert_eq!(b"", EMPTY.needle());
+    /// ```
+    #[inline]
+    pub const fn empty() -> Finder<'static> {
+        Finder { searcher: Searcher::empty() }
+    }
+
+    /// Create a new finder for the given needle.
+    #[inline]
+    pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> Finder<'n> {
+        FinderBuilder::new().build_forward(needle)
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack.
     ///
     /// # Complexity
     ///
@@ -567,25 +1238,75 @@ impl<'n> FinderRev<'n> {
     /// Basic usage:
     ///
     /// ```
-    /// use memchr::memmem::FinderRev;
+    /// use memchr::memmem::Finder;
     ///
     /// let haystack = b"foo bar baz";
-    /// assert_eq!(Some(0), FinderRev::new("foo").rfind(haystack));
-    /// assert_eq!(Some(4), FinderRev::new("bar").rfind(haystack));
-    /// assert_eq!(None, FinderRev::new("quux").rfind(haystack));
+    /// assert_eq!(Some(0), Finder::new("foo").find(haystack));
+    /// assert_eq!(Some(4), Finder::new("bar").find(haystack));
+    /// assert_eq!(None, Finder::new("quux").find(haystack));
     /// ```
-    pub fn rfind<B: AsRef<[u8]>>(&self, haystack: B) -> Option<usize> {
-        self.searcher.rfind(haystack.as_ref())
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        self.searcher.find(&mut self.searcher.prefilter_state(), haystack)
     }
 
-    /// Returns a reverse iterator over all occurrences of a substring in a
-    /// haystack.
+    /// Returns the index of the first occurrence of this needle in
+    /// `haystack[..len]`.
+    ///
+    /// The bytes in `haystack[len..]` are treated as padding. They are never
+    /// part of a match, but the search may read them. This is useful when a
+    /// haystack is a prefix of a larger buffer, such as one allocated from an
+    /// arena: passing the whole buffer lets a vectorized search always read
+    /// full vectors, instead of handling the end of the haystack specially.
+    ///
+    /// This only makes a difference for short needles, and only when at
+    /// least 32 bytes of padding follow `len`. Otherwise, this is the same as
+    /// `self.find(&haystack[..len])`. The result never depends on the
+    /// padding.
+    ///
+    /// # Panics
+    ///
+    /// This panics when `len > haystack.len()`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let mut buf = [0u8; 64];
+    /// buf[..11].copy_from_slice(b"foo bar baz");
+    /// buf[11..14].copy_from_slice(b"baz");
+    /// let finder = Finder::new("baz");
+    /// assert_eq!(Some(8), finder.find_with_padding(&buf, 11));
+    /// // The copy of "baz" at 11 is in the padding, so it never matches.
+    /// assert_eq!(None, finder.find_with_padding(&buf, 10));
+    /// ```
+    pub fn find_with_padding(
+        &self,
+        haystack: &[u8],
+        len: usize,
+    ) -> Option<usize> {
+        assert!(len <= haystack.len(), "length exceeds haystack");
+        self.searcher.find_padded(
+            &mut self.searcher.prefilter_state(),
+            haystack,
+            len,
+        )
+    }
+
+    /// Returns an iterator over all occurrences of a substring in a haystack.
+    ///
+    /// Matches don't overlap, unless this finder was built with
+    /// [`FinderBuilder::overlapping`].
     ///
     /// # Complexity
     ///
     /// This routine is guaranteed to have worst case linear time complexity
     /// with respect to both the needle and the haystack. That is, this runs
-    /// in `O(needle.len() + haystack.len())` time.
+    /// in `O(needle.len() + haystack.len())` time. (Except when overlapping
+    /// matches are reported, as described for
+    /// [`FinderBuilder::overlapping`].)
     ///
     /// This routine is also guaranteed to have worst case constant space
     /// complexity.
@@ -595,236 +1316,3032 @@ impl<'n> FinderRev<'n> {
     /// Basic usage:
     ///
     /// ```
-    /// use memchr::memmem::FinderRev;
+    /// use memchr::memmem::Finder;
     ///
     /// let haystack = b"foo bar foo baz foo";
-    /// let finder = FinderRev::new(b"foo");
-    /// let mut it = finder.rfind_iter(haystack);
-    /// assert_eq!(Some(16), it.next());
-    /// assert_eq!(Some(8), it.next());
+    /// let finder = Finder::new(b"foo");
+    /// let mut it = finder.find_iter(haystack);
     /// assert_eq!(Some(0), it.next());
+    /// assert_eq!(Some(8), it.next());
+    /// assert_eq!(Some(16), it.next());
     /// assert_eq!(None, it.next());
     /// ```
     #[inline]
-    pub fn rfind_iter<'a, 'h>(
+    pub fn find_iter<'a, 'h>(
         &'a self,
         haystack: &'h [u8],
-    ) -> FindRevIter<'h, 'a> {
-        FindRevIter::new(haystack, self.as_ref())
+    ) -> FindIter<'h, 'a> {
+        FindIter::new(haystack, self.as_ref())
     }
 
-    /// Convert this finder into its owned variant, such that it no longer
-    /// borrows the needle.
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// needle in a haystack, reporting both the start and the length of each
+    /// match.
     ///
-    /// If this is already an owned finder, then this is a no-op. Otherwise,
-    /// this copies the needle.
+    /// This finds the same matches as [`Finder::find_iter`].
     ///
-    /// This is only available when the `std` feature is enabled.
-    #[cfg(feature = "std")]
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"foo bar foo";
+    /// let finder = Finder::new("foo");
+    /// let ranges: Vec<_> =
+    ///     finder.find_iter_match(haystack).map(|m| m.range()).collect();
+    /// assert_eq!(vec![0..3, 8..11], ranges);
+    /// ```
     #[inline]
-    pub fn into_owned(self) -> FinderRev<'static> {
-        FinderRev { searcher: self.searcher.into_owned() }
+    pub fn find_iter_match<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindMatchIter<'h, 'a> {
+        FindMatchIter { it: self.find_iter(haystack) }
     }
 
-    /// Convert this finder into its borrowed variant.
+    /// Returns an iterator over the end offsets of all non-overlapping
+    /// occurrences of this needle in a haystack.
     ///
-    /// This is primarily useful if your finder is owned and you'd like to
-    /// store its borrowed variant in some intermediate data structure.
+    /// This finds the same matches as [`Finder::find_iter`], but reports
+    /// the offset immediately following each match, i.e., `start +
+    /// needle.len()`, which is where parsing would resume after it. For an
+    /// empty needle, a match ends where it starts, so this reports every
+    /// offset from `0` to `haystack.len()` inclusive, just like `find_iter`.
     ///
-    /// Note that the lifetime parameter of the returned finder is tied to the
-    /// lifetime of `self`, and may be shorter than the `'n` lifetime of the
-    /// needle itself. Namely, a finder's needle can be either borrowed or
-    /// owned, so the lifetime of the needle returned must necessarily be the
-    /// shorter of the two.
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("aa");
+    /// let ends: Vec<usize> = finder.find_end_iter(b"aaaaa").collect();
+    /// assert_eq!(vec![2, 4], ends);
+    /// ```
     #[inline]
-    pub fn as_ref(&self) -> FinderRev<'_> {
-        FinderRev { searcher: self.searcher.as_ref() }
+    pub fn find_end_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindEndIter<'h, 'a> {
+        FindEndIter { it: self.find_iter(haystack) }
     }
 
-    /// Returns the needle that this finder searches for.
+    /// Returns an iterator over the starting offsets of all non-overlapping
+    /// occurrences of this needle in a haystack, with `base` added to each.
     ///
-    /// Note that the lifetime of the needle returned is tied to the lifetime
-    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
-    /// finder's needle can be either borrowed or owned, so the lifetime of the
-    /// needle returned must necessarily be the shorter of the two.
+    /// This finds the same matches as [`Finder::find_iter`]. It's meant for
+    /// haystacks that are a window into something bigger, such as a region
+    /// of a file starting at file offset `base`, so that matches come out as
+    /// offsets into the whole thing. Those are `u64` so that they can exceed
+    /// the address space, as they can for large files on 32-bit targets.
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if `base` plus the start of a match overflows a
+    /// `u64`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("foo");
+    /// let base = 5_000_000_000;
+    /// let offsets: Vec<u64> =
+    ///     finder.find_iter_based(b"foo bar foo", base).collect();
+    /// assert_eq!(vec![5_000_000_000, 5_000_000_008], offsets);
+    /// ```
     #[inline]
-    pub fn needle(&self) -> &[u8] {
-        self.searcher.needle()
+    pub fn find_iter_based<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+        base: u64,
+    ) -> FindIterBased<'h, 'a> {
+        FindIterBased { it: self.find_iter(haystack), base }
     }
-}
 
-/// A builder for constructing non-default forward or reverse memmem finders.
-///
-/// A builder is primarily useful for configuring a substring searcher.
-/// Currently, the only configuration exposed is the ability to disable
-/// heuristic prefilters used to speed up certain searches.
-#[derive(Clone, Debug, Default)]
-pub struct FinderBuilder {
-    config: SearcherConfig,
-}
+    /// Returns an iterator over the spans of a haystack covered by
+    /// occurrences of this needle, where occurrences that overlap or are
+    /// adjacent to one another are merged into a single span.
+    ///
+    /// Unlike [`Finder::find_iter`], this considers every occurrence of the
+    /// needle, including those that overlap, like
+    /// [`Finder::count_overlapping`]. The ranges reported are maximal,
+    /// non-empty (unless the needle is empty), in ascending order, and never
+    /// overlap or touch one another. This is what's needed to highlight every
+    /// occurrence of a needle.
+    ///
+    /// An empty needle matches at every position, and its empty matches are
+    /// reported as they are, since empty ranges at different positions don't
+    /// touch.
+    ///
+    /// # Complexity
+    ///
+    /// Each match causes a new search to start one byte after it, so in the
+    /// worst case, this runs in `O(needle.len() * haystack.len())` time.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("aba");
+    /// let ranges: Vec<_> =
+    ///     finder.find_merged_ranges(b"abababa xaba abaaba").collect();
+    /// assert_eq!(vec![0..7, 9..12, 13..19], ranges);
+    /// ```
+    #[inline]
+    pub fn find_merged_ranges<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindMergedRanges<'h, 'a> {
+        let finder = self.as_ref();
+        let prestate = finder.searcher.prefilter_state();
+        FindMergedRanges { haystack, prestate, finder, pos: 0 }
+    }
 
-impl FinderBuilder {
-    /// Create a new finder builder with default settings.
-    pub fn new() -> FinderBuilder {
-        FinderBuilder::default()
+    /// Returns the index of the first occurrence of this needle in the given
+    /// string.
+    ///
+    /// The match returned always starts and ends at a char boundary, so the
+    /// index can be used to slice `haystack` directly. When the needle is
+    /// valid UTF-8 and non-empty (as it always is for a finder built from a
+    /// `&str`), every match has this property, and this returns the same
+    /// index as `self.find(haystack.as_bytes())`. Otherwise, matches that
+    /// would split a char are skipped. So a needle that isn't valid UTF-8
+    /// never matches, and an empty needle only matches at char boundaries,
+    /// just like with [`str::find`].
+    ///
+    /// # Complexity
+    ///
+    /// When the needle is valid UTF-8 and non-empty, this has the same
+    /// complexity as `find`. Otherwise, each skipped match causes a new
+    /// search to start within it, so in the worst case, this runs in
+    /// `O(needle.len() * haystack.len())` time.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = "Löwe 老虎 Léopard";
+    /// let finder = Finder::new("老虎");
+    /// let i = finder.find_str(haystack).unwrap();
+    /// assert_eq!("老虎 Léopard", &haystack[i..]);
+    ///
+    /// // An empty needle never matches in the middle of a char.
+    /// assert_eq!(Some(2), Finder::new("").find_str_iter("öx").nth(1));
+    /// ```
+    #[inline]
+    pub fn find_str(&self, haystack: &str) -> Option<usize> {
+        self.find_str_iter(haystack).next()
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// needle in the given string.
+    ///
+    /// Every match reported starts and ends at a char boundary. This yields
+    /// the same matches as [`Finder::find_iter`] when the needle is valid
+    /// UTF-8 and non-empty. See [`Finder::find_str`] for details.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = "née, née, né";
+    /// let finder = Finder::new("née");
+    /// let matches: Vec<usize> = finder.find_str_iter(haystack).collect();
+    /// assert_eq!(vec![0, 6], matches);
+    /// ```
+    #[inline]
+    pub fn find_str_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h str,
+    ) -> FindStrIter<'h, 'a> {
+        let finder = self.as_ref();
+        let prestate = finder.searcher.prefilter_state();
+        FindStrIter { haystack, prestate, finder, pos: 0 }
+    }
+
+    /// Returns an iterator over the occurrences of a substring in a haystack
+    /// that are accepted by the given predicate.
+    ///
+    /// The predicate is called with the starting offset of each candidate
+    /// match. If it returns `true`, then the match is yielded and searching
+    /// resumes where [`Finder::find_iter`] would resume (at the end of the
+    /// match, unless this finder reports overlapping matches). If it
+    /// returns `false`, then searching resumes one byte after the start of
+    /// the rejected match, so that an overlapping match may still be found.
+    /// This is the difference between this and filtering the results of
+    /// `find_iter`, which by default never reports a match overlapping one
+    /// that was found before it.
+    ///
+    /// # Complexity
+    ///
+    /// Each rejected match causes a new search to start within it, so in the
+    /// worst case, this runs in `O(needle.len() * haystack.len())` time.
+    /// When no matches are rejected, this has the same complexity as
+    /// `find_iter`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"aaaa";
+    /// let finder = Finder::new(b"aa");
+    /// let matches: Vec<usize> =
+    ///     finder.find_iter_filter(haystack, |i| i != 0).collect();
+    /// assert_eq!(matches, vec![1]);
+    ///
+    /// // Compare this to filtering the results of find_iter, which never
+    /// // considers the match at offset 1.
+    /// let matches: Vec<usize> =
+    ///     finder.find_iter(haystack).filter(|&i| i != 0).collect();
+    /// assert_eq!(matches, vec![2]);
+    /// ```
+    #[inline]
+    pub fn find_iter_filter<'a, 'h, F: FnMut(usize) -> bool>(
+        &'a self,
+        haystack: &'h [u8],
+        pred: F,
+    ) -> FindIterFilter<'h, 'a, F> {
+        let finder = self.as_ref();
+        let prestate = finder.searcher.prefilter_state();
+        FindIterFilter { haystack, prestate, finder, pos: 0, pred }
+    }
+
+    /// Returns the index of the `n`th non-overlapping occurrence of this
+    /// needle in the given haystack, where `n` is zero-based.
+    ///
+    /// This is equivalent to `self.find_iter(haystack).nth(n)`, but documents
+    /// intent and avoids yielding each of the preceding matches. As with
+    /// [`FindIter`], the same prefilter state is used for the entire search.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"a,b,c,d";
+    /// let finder = Finder::new(",");
+    /// assert_eq!(Some(1), finder.find_nth(haystack, 0));
+    /// assert_eq!(Some(5), finder.find_nth(haystack, 2));
+    /// assert_eq!(None, finder.find_nth(haystack, 3));
+    /// ```
+    pub fn find_nth(&self, haystack: &[u8], n: usize) -> Option<usize> {
+        let mut prestate = self.searcher.prefilter_state();
+        let stride = self.searcher.stride();
+        let mut pos = 0;
+        for _ in 0..n {
+            let i = self.searcher.find(&mut prestate, &haystack[pos..])?;
+            pos += i + stride;
+            if pos > haystack.len() {
+                return None;
+            }
+        }
+        self.searcher.find(&mut prestate, &haystack[pos..]).map(|i| pos + i)
+    }
+
+    /// Calls `f` with the starting offset of each non-overlapping occurrence
+    /// of this needle in the given haystack, in order, until `f` returns
+    /// `false`.
+    ///
+    /// The matches reported are the same as those yielded by
+    /// [`Finder::find_iter`]. Since this drives the search loop itself
+    /// instead of suspending it between matches, it may be faster when
+    /// matches are frequent.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time, not counting the time
+    /// spent in `f`.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"foo bar foo baz foo";
+    /// let mut matches = vec![];
+    /// Finder::new("foo").for_each_match(haystack, |i| {
+    ///     matches.push(i);
+    ///     // Stop after the second match.
+    ///     matches.len() < 2
+    /// });
+    /// assert_eq!(matches, vec![0, 8]);
+    /// ```
+    #[inline]
+    pub fn for_each_match<F: FnMut(usize) -> bool>(
+        &self,
+        haystack: &[u8],
+        f: F,
+    ) {
+        self.for_each_match_with_stride(haystack, self.searcher.stride(), f)
+    }
+
+    /// Like `for_each_match`, but resumes each search `stride` bytes after
+    /// the start of the previous match, regardless of this finder's
+    /// configuration.
+    #[inline(always)]
+    fn for_each_match_with_stride<F: FnMut(usize) -> bool>(
+        &self,
+        haystack: &[u8],
+        stride: usize,
+        mut f: F,
+    ) {
+        // A one byte needle has a stride of 1 either way.
+        if let SearcherKind::OneByte(b) = self.searcher.kind {
+            return for_each_byte_match(b, haystack, f);
+        }
+        let mut prestate = self.searcher.prefilter_state();
+        let mut pos = 0;
+        while let Some(i) = self.searcher.find(&mut prestate, &haystack[pos..])
+        {
+            if !f(pos + i) {
+                return;
+            }
+            pos += i + stride;
+            if pos > haystack.len() {
+                return;
+            }
+        }
+    }
+
+    /// Returns the number of non-overlapping occurrences of this needle in
+    /// the given haystack.
+    ///
+    /// This is equivalent to `self.find_iter(haystack).count()`. In
+    /// particular, an empty needle matches at every position in the
+    /// haystack, including the end, and if this finder was built with
+    /// [`FinderBuilder::overlapping`], then overlapping occurrences are
+    /// counted too.
+    ///
+    /// See [`Finder::count_overlapping`] for counting every occurrence,
+    /// including those that overlap.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// assert_eq!(2, Finder::new("aa").count(b"aaaa"));
+    /// assert_eq!(3, Finder::new("foo").count(b"foo bar foo baz foo"));
+    /// ```
+    pub fn count(&self, haystack: &[u8]) -> usize {
+        let mut count = 0;
+        self.for_each_match(haystack, |_| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    /// Returns the number of occurrences of this needle in the given
+    /// haystack, including occurrences that overlap one another.
+    ///
+    /// That is, this counts every position in the haystack at which the
+    /// needle begins. This is never less than [`Finder::count`], and the two
+    /// differ only when the needle can overlap itself.
+    ///
+    /// # Complexity
+    ///
+    /// Each match causes a new search to start one byte after it, so in the
+    /// worst case, this runs in `O(needle.len() * haystack.len())` time.
+    ///
+    /// This routine is guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("aa");
+    /// assert_eq!(3, finder.count_overlapping(b"aaaa"));
+    /// assert_eq!(2, finder.count(b"aaaa"));
+    /// ```
+    pub fn count_overlapping(&self, haystack: &[u8]) -> usize {
+        let mut prestate = self.searcher.prefilter_state();
+        let mut count = 0;
+        let mut pos = 0;
+        while let Some(i) = self.searcher.find(&mut prestate, &haystack[pos..])
+        {
+            count += 1;
+            pos += i + 1;
+            if pos > haystack.len() {
+                break;
+            }
+        }
+        count
+    }
+
+    /// Returns a copy of the given haystack with every non-overlapping
+    /// occurrence of this needle replaced by `replacement`.
+    ///
+    /// The replacement may have any length. The matches replaced are the
+    /// same as those reported by [`Finder::find_iter`] (without
+    /// [`FinderBuilder::overlapping`]), so this behaves like
+    /// `str::replace`, but for bytes. In particular, with an empty needle,
+    /// the replacement is inserted before every byte and at the end.
+    ///
+    /// The returned vector is allocated with a capacity of `haystack.len()`,
+    /// which is exact when the replacement is the same length as the
+    /// needle.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to the needle, the haystack and the returned vector.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("foo");
+    /// assert_eq!(
+    ///     b"quux bar quux".to_vec(),
+    ///     finder.replace(b"foo bar foo", b"quux"),
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn replace(&self, haystack: &[u8], replacement: &[u8]) -> Vec<u8> {
+        self.replacen(haystack, replacement, core::usize::MAX)
+    }
+
+    /// Returns a copy of the given haystack with the first `count`
+    /// non-overlapping occurrences of this needle replaced by `replacement`.
+    ///
+    /// This is like [`Finder::replace`], except at most `count` matches are
+    /// replaced. Any later matches are copied unchanged. This behaves like
+    /// `str::replacen`, but for bytes.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to the needle, the haystack and the returned vector.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new(", ");
+    /// assert_eq!(
+    ///     b"a\nb\nc, d".to_vec(),
+    ///     finder.replacen(b"a, b, c, d", b"\n", 2),
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn replacen(
+        &self,
+        haystack: &[u8],
+        replacement: &[u8],
+        count: usize,
+    ) -> Vec<u8> {
+        let mut replaced = Vec::with_capacity(haystack.len());
+        // The end of the last match, i.e., the start of the unmatched span
+        // that hasn't been copied yet.
+        let mut last = 0;
+        let mut n = 0;
+        if count > 0 {
+            // Replaced matches must not overlap, whatever the configuration.
+            let stride = core::cmp::max(1, self.needle().len());
+            self.for_each_match_with_stride(haystack, stride, |i| {
+                replaced.extend_from_slice(&haystack[last..i]);
+                replaced.extend_from_slice(replacement);
+                last = i + self.needle().len();
+                n += 1;
+                n < count
+            });
+        }
+        replaced.extend_from_slice(&haystack[last..]);
+        replaced
+    }
+
+    /// Returns the smallest period of this finder's needle.
+    ///
+    /// The period of a needle is the smallest `p >= 1` such that
+    /// `needle[i] == needle[i + p]` for every `i` where both sides are in
+    /// bounds. Every non-empty needle has a period of at most its length. A
+    /// needle is commonly called periodic when its period is at most half of
+    /// its length. An empty needle has a period of `0`.
+    ///
+    /// # Complexity
+    ///
+    /// The period is computed on demand from the same critical factorization
+    /// that the Two-Way algorithm uses. For periodic needles, this takes
+    /// linear time in the length of the needle. Otherwise, the factorization
+    /// only yields a lower bound on the period, and finding the exact period
+    /// may take quadratic time in the length of the needle.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// assert_eq!(3, Finder::new("abcabcab").needle_period());
+    /// assert_eq!(1, Finder::new("zzz").needle_period());
+    /// assert_eq!(4, Finder::new("abcd").needle_period());
+    /// ```
+    pub fn needle_period(&self) -> usize {
+        twoway::period(self.needle())
+    }
+
+    /// Returns the index of the first occurrence of this needle in the
+    /// concatenation of `first` and `second`, without concatenating them.
+    ///
+    /// The index returned is an offset into the concatenation. That is, an
+    /// index less than `first.len()` is where a match starts in `first`,
+    /// while any other index `i` corresponds to `i - first.len()` in
+    /// `second`. Matches that begin in `first` and end in `second` are found
+    /// too.
+    ///
+    /// This is useful for searching data in a ring buffer, such as the pair
+    /// of slices returned by `VecDeque::as_slices`.
+    ///
+    /// # Complexity
+    ///
+    /// Searching `first` and `second` is guaranteed to have worst case linear
+    /// time complexity, as with `find`. But checking for a match spanning
+    /// the boundary between them compares the needle at each of the
+    /// `needle.len() - 1` positions where it could start, so the worst case
+    /// time complexity is `O(needle.len()^2 + first.len() + second.len())`.
+    ///
+    /// This routine is guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("foo");
+    /// assert_eq!(Some(1), finder.find_in_two(b"xfoo", b"bar"));
+    /// assert_eq!(Some(3), finder.find_in_two(b"barf", b"oo"));
+    /// assert_eq!(Some(5), finder.find_in_two(b"bar", b"zzfoo"));
+    /// assert_eq!(None, finder.find_in_two(b"barfo", b"x"));
+    /// ```
+    pub fn find_in_two(&self, first: &[u8], second: &[u8]) -> Option<usize> {
+        if let Some(i) = self.find(first) {
+            return Some(i);
+        }
+        // An empty needle either matches at the start of `first` or never
+        // matches at all, so from here on, the needle has at least one byte.
+        let needle = self.needle();
+        if needle.is_empty() {
+            return None;
+        }
+        // Try each split of the needle into a non-empty suffix of `first` and
+        // a non-empty prefix of `second`, starting with the split that puts
+        // the most bytes in `first` (i.e., the leftmost match).
+        let max_head = core::cmp::min(needle.len() - 1, first.len());
+        for head_len in (1..=max_head).rev() {
+            let (head, tail) = needle.split_at(head_len);
+            if util::is_suffix(first, head) && util::is_prefix(second, tail) {
+                return Some(first.len() - head_len);
+            }
+        }
+        self.find(second).map(|i| first.len() + i)
+    }
+
+    /// Returns the first position in the given haystack at which this
+    /// finder's prefilter reports a candidate match, without confirming that
+    /// the needle actually occurs there.
+    ///
+    /// The position returned may be a false positive, so it must be verified
+    /// by the caller. It is never a false negative though: if the needle
+    /// occurs in the haystack, then this returns a position no greater than
+    /// the one returned by [`Finder::find`]. So when this returns `None`,
+    /// the haystack definitely doesn't contain the needle.
+    ///
+    /// This is useful as a coarse pre-screen over large amounts of data,
+    /// where every candidate is subsequently validated with more context
+    /// than just the needle.
+    ///
+    /// If this finder doesn't have a prefilter (for example, it was disabled
+    /// or the needle is very short), then there is nothing to skip, and this
+    /// returns the same result as `find`. Similarly, if the prefilter judges
+    /// itself ineffective on this haystack, then it stops early and reports
+    /// the position it got to, which is likely to be a false positive.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new("foobar");
+    /// let haystack = b"quux foobaz foobar";
+    /// let candidate = finder.find_candidate(haystack).unwrap();
+    /// // A candidate is never past the first match, but it may be before it.
+    /// assert!(candidate <= 12);
+    /// assert_eq!(None, finder.find_candidate(b"quux"));
+    /// ```
+    pub fn find_candidate(&self, haystack: &[u8]) -> Option<usize> {
+        self.searcher.find_candidate(haystack)
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack, along with statistics about how well this finder's
+    /// prefilter performed.
+    ///
+    /// The result is always the same as [`Finder::find`], but the search is
+    /// carried out differently: each candidate reported by the prefilter is
+    /// checked by comparing it with the entire needle. If this finder doesn't
+    /// have a prefilter (for example, it was disabled or the needle is very
+    /// short), or if the prefilter is heuristically disabled during the
+    /// search, then the rest of the search is done as in `find` and isn't
+    /// counted. Note that for some needles, `find` may not use the prefilter
+    /// at all, but this still reports how the prefilter would have done.
+    ///
+    /// This is only available when the `instrumentation` feature is enabled.
+    ///
+    /// # Complexity
+    ///
+    /// Unlike `find`, this routine does not have worst case linear time
+    /// complexity, since every candidate is confirmed with a comparison
+    /// against the entire needle. It is meant only for analysis.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz foo bar";
+    /// let (found, stats) = Finder::new("foo").find_instrumented(haystack);
+    /// assert_eq!(Some(39), found);
+    /// assert_eq!(stats.confirmations(), stats.candidates());
+    /// assert_eq!(stats.rejected() + 1, stats.confirmations());
+    /// ```
+    #[cfg(feature = "instrumentation")]
+    pub fn find_instrumented(
+        &self,
+        haystack: &[u8],
+    ) -> (Option<usize>, SearchStats) {
+        self.searcher.find_instrumented(haystack)
+    }
+
+    /// Returns an iterator over the tokens in a haystack that are separated by
+    /// non-overlapping occurrences of this needle.
+    ///
+    /// Each item yielded is a pair of a token and the offset of the match
+    /// immediately following it. The last token yielded runs to the end of
+    /// the haystack and has no match offset. Consequently, this always
+    /// yields at least one token (which may be empty), and the number of
+    /// tokens is always one more than the number of matches.
+    ///
+    /// This is like splitting a haystack on a delimiter, except the position
+    /// of each delimiter is retained.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let finder = Finder::new(", ");
+    /// let mut it = finder.tokenize(b"foo, bar, baz");
+    /// assert_eq!(Some((&b"foo"[..], Some(3))), it.next());
+    /// assert_eq!(Some((&b"bar"[..], Some(8))), it.next());
+    /// assert_eq!(Some((&b"baz"[..], None)), it.next());
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[inline]
+    pub fn tokenize<'a, 'h>(&'a self, haystack: &'h [u8]) -> Tokens<'h, 'a> {
+        // The tokens are what's between matches, so they're always found
+        // without overlap.
+        let mut it = self.find_iter(haystack);
+        it.stride = core::cmp::max(1, self.needle().len());
+        Tokens::new(it)
+    }
+
+    /// Returns the index of the next occurrence of this needle in the given
+    /// haystack, starting the search at the position recorded in `cursor`.
+    ///
+    /// When a match is found, the cursor is advanced past it using the same
+    /// stride as [`FindIter`], so matches only overlap when this finder was
+    /// built with [`FinderBuilder::overlapping`]. When no match is found, the
+    /// cursor is left unchanged. This makes it possible to resume a search
+    /// later, possibly after the haystack has grown.
+    ///
+    /// Unlike [`FindIter`], a [`Cursor`] does not borrow the haystack, so it
+    /// can be stored in other data structures between searches. The downside
+    /// is that no prefilter state is carried between calls, so each call
+    /// begins with a fresh prefilter state.
+    ///
+    /// If the cursor's position is greater than the length of the haystack,
+    /// then this always returns `None`.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::{Cursor, Finder};
+    ///
+    /// let finder = Finder::new(b"foo");
+    /// let mut cursor = Cursor::new();
+    ///
+    /// let haystack = b"foo bar foo";
+    /// assert_eq!(Some(0), finder.find_from_cursor(haystack, &mut cursor));
+    /// assert_eq!(3, cursor.position());
+    /// assert_eq!(Some(8), finder.find_from_cursor(haystack, &mut cursor));
+    /// assert_eq!(None, finder.find_from_cursor(haystack, &mut cursor));
+    ///
+    /// // The haystack may be re-borrowed (or even grow) between searches.
+    /// let haystack = b"foo bar foo baz foo";
+    /// assert_eq!(Some(16), finder.find_from_cursor(haystack, &mut cursor));
+    /// ```
+    #[inline]
+    pub fn find_from_cursor(
+        &self,
+        haystack: &[u8],
+        cursor: &mut Cursor,
+    ) -> Option<usize> {
+        if cursor.pos > haystack.len() {
+            return None;
+        }
+        let mut prestate = self.searcher.prefilter_state();
+        let i = self.searcher.find(&mut prestate, &haystack[cursor.pos..])?;
+        let pos = cursor.pos + i;
+        cursor.pos = pos + self.searcher.stride();
+        Some(pos)
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack that starts at or after `at`.
+    ///
+    /// This returns `None` if `at > haystack.len()`. Otherwise, it's
+    /// equivalent to searching `&haystack[at..]` and adding `at` to the
+    /// result.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"foo bar foo";
+    /// let finder = Finder::new("foo");
+    /// assert_eq!(Some(0), finder.find_after(haystack, 0));
+    /// assert_eq!(Some(8), finder.find_after(haystack, 1));
+    /// assert_eq!(None, finder.find_after(haystack, 9));
+    /// ```
+    #[inline]
+    pub fn find_after(&self, haystack: &[u8], at: usize) -> Option<usize> {
+        let rest = haystack.get(at..)?;
+        self.find(rest).map(|i| at + i)
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack that is immediately followed by a byte in `next`.
+    ///
+    /// An occurrence that ends at the end of the haystack has no following
+    /// byte. It is accepted only when `at_end` is `true`.
+    ///
+    /// When an occurrence is rejected, searching resumes one byte after its
+    /// start, so an overlapping occurrence may still be found. The same
+    /// prefilter state is used for the entire search.
+    ///
+    /// # Complexity
+    ///
+    /// Each rejected occurrence causes a new search to start within it, so in
+    /// the worst case, this runs in `O(needle.len() * haystack.len())` time.
+    /// When no occurrences are rejected, this has the same complexity as
+    /// `find`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::{memmem::Finder, ByteSet};
+    ///
+    /// let digits = ByteSet::new(b"0123456789");
+    /// let haystack = b"foo foox foo1";
+    /// let finder = Finder::new("foo");
+    /// assert_eq!(Some(9), finder.find_followed_by(haystack, &digits, false));
+    /// assert_eq!(None, finder.find_followed_by(b"foo", &digits, false));
+    /// assert_eq!(Some(0), finder.find_followed_by(b"foo", &digits, true));
+    /// ```
+    pub fn find_followed_by(
+        &self,
+        haystack: &[u8],
+        next: &ByteSet,
+        at_end: bool,
+    ) -> Option<usize> {
+        let mut prestate = self.searcher.prefilter_state();
+        let nlen = self.needle().len();
+        let mut pos = 0;
+        while pos <= haystack.len() {
+            let i = self.searcher.find(&mut prestate, &haystack[pos..])?;
+            let start = pos + i;
+            let accept = match haystack.get(start + nlen) {
+                None => at_end,
+                Some(&b) => next.contains(b),
+            };
+            if accept {
+                return Some(start);
+            }
+            pos = start + 1;
+        }
+        None
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack whose starting offset `i` satisfies
+    /// `i % align == offset_mod`.
+    ///
+    /// This is useful for formats made of fixed size records, where a marker
+    /// is only meaningful at a record boundary. Occurrences at other offsets
+    /// are skipped: after one is found, searching resumes at the next
+    /// conforming offset after its start. When `align` is large, conforming
+    /// offsets are checked directly instead, so occurrences in between them
+    /// are never looked at.
+    ///
+    /// # Complexity
+    ///
+    /// Each skipped occurrence causes a new search to start within it, so in
+    /// the worst case, this runs in
+    /// `O(haystack.len() + needle.len() * haystack.len() / align)` time.
+    /// When no occurrences are skipped, this has the same complexity as
+    /// `find`.
+    ///
+    /// # Panics
+    ///
+    /// This panics when `offset_mod >= align`, which includes when `align`
+    /// is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"xxMKMKxxMKxx";
+    /// let finder = Finder::new("MK");
+    /// assert_eq!(Some(2), finder.find_aligned(haystack, 2, 0));
+    /// assert_eq!(Some(4), finder.find_aligned(haystack, 4, 0));
+    /// assert_eq!(Some(8), finder.find_aligned(haystack, 8, 0));
+    /// assert_eq!(None, finder.find_aligned(haystack, 2, 1));
+    /// ```
+    pub fn find_aligned(
+        &self,
+        haystack: &[u8],
+        align: usize,
+        offset_mod: usize,
+    ) -> Option<usize> {
+        assert!(
+            offset_mod < align,
+            "offset_mod must be less than align (got {} and {})",
+            offset_mod,
+            align,
+        );
+        let needle = self.needle();
+        // Checking each conforming offset costs at most `needle.len()` per
+        // `align` bytes, which keeps this linear as long as the needle is no
+        // longer than the alignment.
+        if !needle.is_empty()
+            && align >= ALIGNED_STEP_MIN
+            && align >= needle.len()
+        {
+            let mut start = offset_mod;
+            while start <= haystack.len() {
+                if util::is_prefix(&haystack[start..], needle) {
+                    return Some(start);
+                }
+                start = start.checked_add(align)?;
+            }
+            return None;
+        }
+
+        let mut prestate = self.searcher.prefilter_state();
+        let mut pos = offset_mod;
+        while pos <= haystack.len() {
+            let i = self.searcher.find(&mut prestate, &haystack[pos..])?;
+            let start = pos + i;
+            // Every offset we search from conforms, so `i` is a multiple of
+            // `align` exactly when `start` conforms.
+            let rem = i % align;
+            if rem == 0 {
+                return Some(start);
+            }
+            pos = start.checked_add(align - rem)?;
+        }
+        None
+    }
+
+    /// Replace this finder's needle with a copy of the given one, keeping
+    /// its configuration.
+    ///
+    /// This is like building a new owned finder with the same settings, but
+    /// if this finder already owns a needle, then its allocation is reused
+    /// for the new needle whenever the new needle fits in its capacity. This
+    /// saves an allocation per needle when cycling through many needles of
+    /// similar lengths. The facts computed about the needle (such as its critical factorization
+    /// and rare bytes) are always recomputed.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let mut finder = Finder::new("foo").into_owned();
+    /// assert_eq!(Some(0), finder.find(b"foo bar"));
+    /// finder.set_needle(b"bar");
+    /// assert_eq!(b"bar", finder.needle());
+    /// assert_eq!(Some(4), finder.find(b"foo bar"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_needle(&mut self, needle: &[u8]) {
+        let config = self.searcher.config;
+        let mut cow =
+            core::mem::replace(&mut self.searcher.needle, CowBytes::empty());
+        cow.set_owned(needle);
+        let ninfo = NeedleInfo::new(&cow);
+        self.searcher = Searcher::build(config, cow, ninfo, None);
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle.
+    ///
+    /// If this is already an owned finder, then this is a no-op. Otherwise,
+    /// this copies the needle.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn into_owned(self) -> Finder<'static> {
+        Finder { searcher: self.searcher.into_owned() }
+    }
+
+    /// Decompose this finder into its needle and the facts pre-computed about
+    /// that needle.
+    ///
+    /// The parts returned can be given to [`Finder::from_parts`] to
+    /// reconstruct this finder without computing anything about the needle.
+    /// Since every field of [`TwoWayParams`] is public, the parts can be
+    /// computed ahead of time (e.g., in a build script) and baked into a
+    /// program.
+    ///
+    /// Note that the configuration of this finder (such as its prefilter
+    /// setting) is not included.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let (needle, params) = Finder::new("foo").into_parts();
+    /// assert_eq!(b"foo", &*needle);
+    ///
+    /// let finder = Finder::from_parts(&needle, params);
+    /// assert_eq!(Some(4), finder.find(b"baz foo quux"));
+    /// ```
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn into_parts(self) -> (Vec<u8>, TwoWayParams) {
+        let params = self.searcher.params();
+        (self.needle().to_vec(), params)
+    }
+
+    /// Create a new finder for the given needle from parts previously
+    /// returned by [`Finder::into_parts`], using the default configuration.
+    ///
+    /// Unlike [`Finder::new`], this does not compute anything about the
+    /// needle. (Beyond, for example, deciding which vectorized routines are
+    /// available on the current CPU.)
+    ///
+    /// # Panics
+    ///
+    /// This panics if any of the positions in `params` are out of bounds for
+    /// the given needle. If `params` were not returned by `into_parts` for
+    /// the same needle, then the results of searching are unspecified, but
+    /// memory safety is never violated.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::{Finder, TwoWayParams, TwoWayShift};
+    ///
+    /// // These parts might be generated by a build script.
+    /// let params = TwoWayParams {
+    ///     critical_pos: 1,
+    ///     shift: TwoWayShift::Large { shift: 2 },
+    ///     byteset: 0x0000_8040_0000_0000,
+    ///     rare1i: 0,
+    ///     rare2i: 1,
+    ///     hash: 0x0000_02e5,
+    ///     hash_2pow: 4,
+    /// };
+    /// let finder = Finder::from_parts("foo", params);
+    /// assert_eq!(Some(4), finder.find(b"baz foo quux"));
+    /// ```
+    #[inline]
+    pub fn from_parts<B: ?Sized + AsRef<[u8]>>(
+        needle: &'n B,
+        params: TwoWayParams,
+    ) -> Finder<'n> {
+        let config = SearcherConfig::default();
+        Finder {
+            searcher: Searcher::from_params(config, needle.as_ref(), &params),
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    ///
+    /// This is primarily useful if your finder is owned and you'd like to
+    /// store its borrowed variant in some intermediate data structure.
+    ///
+    /// Note that the lifetime parameter of the returned finder is tied to the
+    /// lifetime of `self`, and may be shorter than the `'n` lifetime of the
+    /// needle itself. Namely, a finder's needle can be either borrowed or
+    /// owned, so the lifetime of the needle returned must necessarily be the
+    /// shorter of the two.
+    #[inline]
+    pub fn as_ref(&self) -> Finder<'_> {
+        Finder { searcher: self.searcher.as_ref() }
+    }
+
+    /// Returns the needle that this finder searches for.
+    ///
+    /// Note that the lifetime of the needle returned is tied to the lifetime
+    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
+    /// finder's needle can be either borrowed or owned, so the lifetime of the
+    /// needle returned must necessarily be the shorter of the two.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.searcher.needle()
+    }
+}
+
+/// A single substring reverse searcher fixed to a particular needle.
+///
+/// The purpose of this type is to permit callers to construct a substring
+/// searcher that can be used to search haystacks without the overhead of
+/// constructing the searcher in the first place. This is a somewhat niche
+/// concern when it's necessary to re-use the same needle to search multiple
+/// different haystacks with as little overhead as possible. In general,
+/// using [`rfind`] is good enough, but `FinderRev` is useful when you can
+/// meaningfully observe searcher construction time in a profile.
+///
+/// When the `std` feature is enabled, then this type has an `into_owned`
+/// version which permits building a `FinderRev` that is not connected to
+/// the lifetime of its needle.
+#[derive(Clone)]
+pub struct FinderRev<'n> {
+    searcher: SearcherRev<'n>,
+}
+
+/// This shows the needle of the finder, but omits any facts computed about
+/// the needle, since they are only interesting when debugging this crate.
+///
+/// Note that reverse finders always use the default prefilter setting, so
+/// it isn't shown.
+impl<'n> core::fmt::Debug for FinderRev<'n> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("FinderRev")
+            .field("needle", &util::DebugBytes(self.needle()))
+            .finish()
+    }
+}
+
+/// The default reverse finder has an empty needle, so it matches at every
+/// position. This is the same as [`FinderRev::empty`].
+impl<'n> Default for FinderRev<'n> {
+    #[inline]
+    fn default() -> FinderRev<'n> {
+        FinderRev::empty()
+    }
+}
+
+impl<'n> FinderRev<'n> {
+    /// Create a new reverse finder for the empty needle.
+    ///
+    /// An empty needle matches at every position, so this is the same as
+    /// `FinderRev::new("")`, except that it can be used in a constant
+    /// expression.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// const EMPTY: FinderRev<'static> = FinderRev::empty();
+    /// assert_eq!(Some(3), EMPTY.rfind(b"foo"));
+    /// ```
+    #[inline]
+    pub const fn empty() -> FinderRev<'static> {
+        FinderRev { searcher: SearcherRev::empty() }
+    }
+
+    /// Create a new reverse finder for the given needle.
+    #[inline]
+    pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> FinderRev<'n> {
+        FinderBuilder::new().build_reverse(needle)
+    }
+
+    /// Returns the index of the last occurrence of this needle in the given
+    /// haystack.
+    ///
+    /// The haystack may be any type that can be cheaply converted into a
+    /// `&[u8]`. This includes, but is not limited to, `&str` and `&[u8]`.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack = b"foo bar baz";
+    /// assert_eq!(Some(0), FinderRev::new("foo").rfind(haystack));
+    /// assert_eq!(Some(4), FinderRev::new("bar").rfind(haystack));
+    /// assert_eq!(None, FinderRev::new("quux").rfind(haystack));
+    /// ```
+    pub fn rfind<B: AsRef<[u8]>>(&self, haystack: B) -> Option<usize> {
+        self.searcher
+            .rfind(&mut self.searcher.prefilter_state(), haystack.as_ref())
+    }
+
+    /// Returns the index of the last occurrence of this needle in the given
+    /// string.
+    ///
+    /// The match returned always starts and ends at a char boundary. When
+    /// the needle is valid UTF-8 (as it always is for a finder built from a
+    /// `&str`), every match has this property, and this returns the same
+    /// index as `self.rfind(haystack)`. Otherwise, matches that would split a
+    /// char are skipped, so a needle that isn't valid UTF-8 never matches.
+    ///
+    /// # Complexity
+    ///
+    /// When the needle is valid UTF-8, this has the same complexity as
+    /// `rfind`. Otherwise, each skipped match causes a new search to start
+    /// within it, so in the worst case, this runs in
+    /// `O(needle.len() * haystack.len())` time.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack = "née, née, né";
+    /// let i = FinderRev::new("né").rfind_str(haystack).unwrap();
+    /// assert_eq!("né", &haystack[i..]);
+    /// ```
+    pub fn rfind_str(&self, haystack: &str) -> Option<usize> {
+        let bytes = haystack.as_bytes();
+        let nlen = self.needle().len();
+        let mut prestate = self.searcher.prefilter_state();
+        let mut end = bytes.len();
+        loop {
+            let i = self.searcher.rfind(&mut prestate, &bytes[..end])?;
+            if is_str_match(haystack, i, nlen) {
+                return Some(i);
+            }
+            // An empty needle always matches at `end`, which is a char
+            // boundary, so `nlen >= 1` here. Searching up to just before the
+            // end of this match finds the next match starting before it.
+            end = i + nlen - 1;
+        }
+    }
+
+    /// Returns the index of the last occurrence of this needle in the given
+    /// haystack that starts strictly before `at`.
+    ///
+    /// A match starting before `at` is reported even if it ends after `at`.
+    /// If `at > haystack.len()`, then this is equivalent to `rfind`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack = b"foo bar foo";
+    /// let finder = FinderRev::new("foo");
+    /// assert_eq!(Some(8), finder.find_before(haystack, 9));
+    /// assert_eq!(Some(0), finder.find_before(haystack, 8));
+    /// assert_eq!(None, finder.find_before(haystack, 0));
+    /// ```
+    #[inline]
+    pub fn find_before(&self, haystack: &[u8], at: usize) -> Option<usize> {
+        if at == 0 {
+            return None;
+        }
+        // Every match starting before `at` ends within this prefix.
+        let end = core::cmp::min(
+            (at - 1).saturating_add(self.needle().len()),
+            haystack.len(),
+        );
+        self.rfind(&haystack[..end])
+    }
+
+    /// Returns a reverse iterator over all occurrences of a substring in a
+    /// haystack.
+    ///
+    /// Matches don't overlap, unless this finder was built with
+    /// [`FinderBuilder::overlapping`].
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time. (Except when overlapping
+    /// matches are reported, as described for
+    /// [`FinderBuilder::overlapping`].)
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack = b"foo bar foo baz foo";
+    /// let finder = FinderRev::new(b"foo");
+    /// let mut it = finder.rfind_iter(haystack);
+    /// assert_eq!(Some(16), it.next());
+    /// assert_eq!(Some(8), it.next());
+    /// assert_eq!(Some(0), it.next());
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[inline]
+    pub fn rfind_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindRevIter<'h, 'a> {
+        FindRevIter::new(haystack, self.as_ref())
+    }
+
+    /// Returns a reverse iterator over all non-overlapping occurrences of
+    /// this needle in a haystack, reporting both the start and the length of
+    /// each match.
+    ///
+    /// This finds the same matches as [`FinderRev::rfind_iter`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack = b"foo bar foo";
+    /// let finder = FinderRev::new("foo");
+    /// let ends: Vec<usize> =
+    ///     finder.rfind_iter_match(haystack).map(|m| m.end()).collect();
+    /// assert_eq!(vec![11, 3], ends);
+    /// ```
+    #[inline]
+    pub fn rfind_iter_match<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindRevMatchIter<'h, 'a> {
+        FindRevMatchIter { it: self.rfind_iter(haystack) }
+    }
+
+    /// Returns a reverse iterator over all occurrences of a substring in a
+    /// haystack, using the given prefilter state.
+    ///
+    /// This is like [`FinderRev::rfind_iter`], except the state tracking the
+    /// effectiveness of the prefilter is borrowed from the caller instead of
+    /// being created fresh. Passing the same state to each search when
+    /// searching many similar haystacks lets the searcher keep what it learned
+    /// about the prefilter. e.g., If the prefilter was found to be
+    /// ineffective on one haystack, it won't be tried again on the next.
+    ///
+    /// The matches reported are always the same as those reported by
+    /// `rfind_iter`.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine is also guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::{FinderRev, PrefilterState};
+    ///
+    /// let finder = FinderRev::new(b"ERROR");
+    /// let mut state = PrefilterState::new();
+    /// let mut found = vec![];
+    /// for log in &[&b"ok\nERROR a\nok\nERROR b\n"[..], b"ok\nok\n"] {
+    ///     found.push(finder.rfind_iter_with_state(log, &mut state).count());
+    /// }
+    /// assert_eq!(vec![2, 0], found);
+    /// ```
+    #[inline]
+    pub fn rfind_iter_with_state<'a, 'h, 's>(
+        &'a self,
+        haystack: &'h [u8],
+        state: &'s mut PrefilterState,
+    ) -> FindRevIterWithState<'h, 'a, 's> {
+        FindRevIterWithState {
+            haystack,
+            prestate: state,
+            finder: self.as_ref(),
+            pos: Some(haystack.len()),
+        }
+    }
+
+    /// Returns an iterator over all occurrences of a substring in a haystack,
+    /// in ascending order.
+    ///
+    /// The matches reported are exactly those reported by
+    /// [`FinderRev::rfind_iter`], just in the opposite order. Note that this
+    /// is not always the same set of matches reported by a forward
+    /// [`Finder`], since non-overlapping matches are chosen starting from the
+    /// end of the haystack. e.g., Searching for `aa` in `aaa` reports a match
+    /// at `1`, while a forward search reports a match at `0`.
+    ///
+    /// Since a reverse finder can only find matches starting from the end of
+    /// the haystack, all matches are found and buffered before this returns.
+    /// If you only need matches in ascending order, then a forward [`Finder`]
+    /// is a better choice.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Complexity
+    ///
+    /// This routine is guaranteed to have worst case linear time complexity
+    /// with respect to both the needle and the haystack. That is, this runs
+    /// in `O(needle.len() + haystack.len())` time.
+    ///
+    /// This routine uses space proportional to the number of matches.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack = b"foo bar foo baz foo";
+    /// let finder = FinderRev::new(b"foo");
+    /// let matches: Vec<usize> = finder.find_iter_fwd(haystack).collect();
+    /// assert_eq!(vec![0, 8, 16], matches);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn find_iter_fwd(&self, haystack: &[u8]) -> FindRevFwdIter {
+        let mut matches: Vec<usize> = self.rfind_iter(haystack).collect();
+        matches.reverse();
+        FindRevFwdIter { it: matches.into_iter() }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle.
+    ///
+    /// If this is already an owned finder, then this is a no-op. Otherwise,
+    /// this copies the needle.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn into_owned(self) -> FinderRev<'static> {
+        FinderRev { searcher: self.searcher.into_owned() }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    ///
+    /// This is primarily useful if your finder is owned and you'd like to
+    /// store its borrowed variant in some intermediate data structure.
+    ///
+    /// Note that the lifetime parameter of the returned finder is tied to the
+    /// lifetime of `self`, and may be shorter than the `'n` lifetime of the
+    /// needle itself. Namely, a finder's needle can be either borrowed or
+    /// owned, so the lifetime of the needle returned must necessarily be the
+    /// shorter of the two.
+    #[inline]
+    pub fn as_ref(&self) -> FinderRev<'_> {
+        FinderRev { searcher: self.searcher.as_ref() }
+    }
+
+    /// Returns the needle that this finder searches for.
+    ///
+    /// Note that the lifetime of the needle returned is tied to the lifetime
+    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
+    /// finder's needle can be either borrowed or owned, so the lifetime of the
+    /// needle returned must necessarily be the shorter of the two.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.searcher.needle()
+    }
+}
+
+/// A single substring searcher, for both directions, fixed to a particular
+/// needle.
+///
+/// This is like having both a [`Finder`] and a [`FinderRev`] for the same
+/// needle, except the needle is only stored once, and facts computed about
+/// the needle that don't depend on the direction of search are only computed
+/// once.
+///
+/// Note that Two-Way, which is used for longer needles, still computes a
+/// separate critical factorization for each direction. While a critical
+/// factorization is the same in either direction, the Two-Way algorithm also
+/// requires that the part of the needle to the left of it (in the direction
+/// of search) be shorter than the needle's period. A factorization chosen
+/// for one direction generally doesn't satisfy this in the other.
+///
+/// When the `std` feature is enabled, then this type has an `into_owned`
+/// version which permits building a `BiFinder` that is not connected to
+/// the lifetime of its needle.
+#[derive(Clone)]
+pub struct BiFinder<'n> {
+    /// The forward searcher, which also owns (or borrows) the needle.
+    searcher: Searcher<'n>,
+    /// The parts of a reverse searcher, not including its needle. A reverse
+    /// searcher is built from these and the forward searcher's needle on
+    /// demand, which is cheap.
+    rninfo: NeedleInfo,
+    rprefn: Option<PrefilterFn>,
+    rkind: SearcherRevKind,
+}
+
+/// This shows the needle and configuration of the finder, but omits any
+/// facts computed about the needle.
+impl<'n> core::fmt::Debug for BiFinder<'n> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("BiFinder")
+            .field("needle", &util::DebugBytes(self.needle()))
+            .field("prefilter", &self.searcher.config.prefilter)
+            .finish()
+    }
+}
+
+impl<'n> BiFinder<'n> {
+    /// Create a new bidirectional finder for the given needle.
+    #[inline]
+    pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> BiFinder<'n> {
+        FinderBuilder::new().build_bidirectional(needle)
+    }
+
+    /// Create a bidirectional finder from a forward searcher, reusing as
+    /// much as possible of what was computed for it.
+    fn from_searcher(searcher: Searcher<'n>) -> BiFinder<'n> {
+        let rev = {
+            let needle = searcher.needle();
+            let ninfo = NeedleInfo {
+                // Rare bytes are the same in either direction.
+                rarebytes: searcher.ninfo.rarebytes,
+                nhash: NeedleHash::reverse(needle),
+            };
+            let rev = SearcherRev::build(searcher.config, needle, ninfo);
+            (rev.ninfo, rev.prefn, rev.kind)
+        };
+        BiFinder { searcher, rninfo: rev.0, rprefn: rev.1, rkind: rev.2 }
+    }
+
+    /// Return a reverse searcher that borrows this finder's needle.
+    fn rev(&self) -> SearcherRev<'_> {
+        SearcherRev {
+            needle: CowBytes::new(self.needle()),
+            ninfo: self.rninfo,
+            prefn: self.rprefn,
+            kind: self.rkind,
+            overlapping: self.searcher.config.overlapping,
+        }
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack.
+    ///
+    /// This is the same as [`Finder::find`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::BiFinder;
+    ///
+    /// let finder = BiFinder::new("foo");
+    /// assert_eq!(Some(0), finder.find(b"foo bar foo"));
+    /// assert_eq!(Some(8), finder.rfind(b"foo bar foo"));
+    /// ```
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        self.searcher.find(&mut self.searcher.prefilter_state(), haystack)
+    }
+
+    /// Returns the index of the last occurrence of this needle in the given
+    /// haystack.
+    ///
+    /// This is the same as [`FinderRev::rfind`].
+    pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        let rev = self.rev();
+        rev.rfind(&mut rev.prefilter_state(), haystack)
+    }
+
+    /// Returns the index of the first occurrence of this needle that starts
+    /// at or after `at`.
+    ///
+    /// This is the same as [`Finder::find_after`].
+    #[inline]
+    pub fn find_after(&self, haystack: &[u8], at: usize) -> Option<usize> {
+        Finder { searcher: self.searcher.as_ref() }.find_after(haystack, at)
+    }
+
+    /// Returns the index of the last occurrence of this needle that starts
+    /// strictly before `at`.
+    ///
+    /// This is the same as [`FinderRev::find_before`].
+    #[inline]
+    pub fn find_before(&self, haystack: &[u8], at: usize) -> Option<usize> {
+        FinderRev { searcher: self.rev() }.find_before(haystack, at)
+    }
+
+    /// Returns the index of the occurrence of this needle that starts
+    /// closest to `at`, in either direction.
+    ///
+    /// This considers the matches reported by [`BiFinder::find_after`] and
+    /// [`BiFinder::find_before`]. When both are the same distance from `at`,
+    /// the earlier one is returned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::BiFinder;
+    ///
+    /// let haystack = b"foo bar foo";
+    /// let finder = BiFinder::new("foo");
+    /// assert_eq!(Some(0), finder.find_nearest(haystack, 3));
+    /// assert_eq!(Some(8), finder.find_nearest(haystack, 5));
+    /// // Both matches are 4 bytes away.
+    /// assert_eq!(Some(0), finder.find_nearest(haystack, 4));
+    /// ```
+    pub fn find_nearest(&self, haystack: &[u8], at: usize) -> Option<usize> {
+        match (self.find_before(haystack, at), self.find_after(haystack, at)) {
+            (Some(before), Some(after)) => {
+                if at - before <= after - at {
+                    Some(before)
+                } else {
+                    Some(after)
+                }
+            }
+            (before, None) => before,
+            (None, after) => after,
+        }
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// needle in a haystack.
+    ///
+    /// This is the same as [`Finder::find_iter`].
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindIter<'h, 'a> {
+        FindIter::new(haystack, Finder { searcher: self.searcher.as_ref() })
+    }
+
+    /// Returns a reverse iterator over all non-overlapping occurrences of
+    /// this needle in a haystack.
+    ///
+    /// This is the same as [`FinderRev::rfind_iter`].
+    #[inline]
+    pub fn rfind_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindRevIter<'h, 'a> {
+        FindRevIter::new(haystack, FinderRev { searcher: self.rev() })
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle.
+    ///
+    /// If this is already an owned finder, then this is a no-op. Otherwise,
+    /// this copies the needle.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn into_owned(self) -> BiFinder<'static> {
+        BiFinder {
+            searcher: self.searcher.into_owned(),
+            rninfo: self.rninfo,
+            rprefn: self.rprefn,
+            rkind: self.rkind,
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    ///
+    /// This is primarily useful if your finder is owned and you'd like to
+    /// store its borrowed variant in some intermediate data structure.
+    ///
+    /// Note that the lifetime parameter of the returned finder is tied to the
+    /// lifetime of `self`, and may be shorter than the `'n` lifetime of the
+    /// needle itself. Namely, a finder's needle can be either borrowed or
+    /// owned, so the lifetime of the needle returned must necessarily be the
+    /// shorter of the two.
+    #[inline]
+    pub fn as_ref(&self) -> BiFinder<'_> {
+        BiFinder {
+            searcher: self.searcher.as_ref(),
+            rninfo: self.rninfo,
+            rprefn: self.rprefn,
+            rkind: self.rkind,
+        }
+    }
+
+    /// Returns the needle that this finder searches for.
+    ///
+    /// Note that the lifetime of the needle returned is tied to the lifetime
+    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
+    /// finder's needle can be either borrowed or owned, so the lifetime of the
+    /// needle returned must necessarily be the shorter of the two.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.searcher.needle()
+    }
+}
+
+/// The substring search algorithm used by a forward finder.
+///
+/// By default, the algorithm is chosen automatically based on the needle,
+/// and this generally gives the best performance. The other strategies exist
+/// so that their performance can be compared with the default on a
+/// particular corpus.
+///
+/// Needles with fewer than two bytes are always searched for with `memchr`
+/// (or trivially, for an empty needle), regardless of the strategy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Strategy {
+    /// Automatically select the algorithm. Currently, this is a vectorized
+    /// routine for short needles when one is available, and Two-Way, usually
+    /// accelerated by a prefilter, otherwise.
+    Auto,
+    /// Always use Boyer-Moore-Horspool, which skips ahead based on the
+    /// haystack byte lined up with the end of the needle. This never uses a
+    /// prefilter.
+    ///
+    /// This may be faster than the default for long needles whose bytes are
+    /// rare in the haystack, even when they aren't rare in general. But
+    /// unlike the default, its worst case time complexity is
+    /// `O(needle.len() * haystack.len())`, so it should only be used on
+    /// trusted haystacks.
+    Horspool,
+}
+
+impl Default for Strategy {
+    fn default() -> Strategy {
+        Strategy::Auto
+    }
+}
+
+/// A builder for constructing non-default forward or reverse memmem finders.
+///
+/// A builder is primarily useful for configuring a substring searcher. For
+/// example, it can disable heuristic prefilters used to speed up certain
+/// searches, or permit faster routines without a linear worst case for
+/// longer needles.
+#[derive(Clone, Debug, Default)]
+pub struct FinderBuilder {
+    config: SearcherConfig,
+}
+
+impl FinderBuilder {
+    /// Create a new finder builder with default settings.
+    pub fn new() -> FinderBuilder {
+        FinderBuilder::default()
+    }
+
+    /// Build a forward finder using the given needle from the current
+    /// settings.
+    pub fn build_forward<'n, B: ?Sized + AsRef<[u8]>>(
+        &self,
+        needle: &'n B,
+    ) -> Finder<'n> {
+        Finder { searcher: Searcher::new(self.config, needle.as_ref()) }
+    }
+
+    /// Build an owned forward finder from the bytes yielded by the given
+    /// iterator, using the current settings.
+    ///
+    /// This is useful when the needle is produced incrementally, since the
+    /// bytes are collected directly into the finder's owned needle. It is
+    /// equivalent to collecting them into a `Vec<u8>`, building a finder
+    /// that borrows it with [`FinderBuilder::build_forward`] and then calling
+    /// [`Finder::into_owned`], but without copying the needle a second time.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// // A needle decoded from pairs of hex digits.
+    /// let hex = b"666f6f";
+    /// let needle = hex.chunks(2).map(|pair| {
+    ///     let pair = std::str::from_utf8(pair).unwrap();
+    ///     u8::from_str_radix(pair, 16).unwrap()
+    /// });
+    /// let finder = FinderBuilder::new().build_forward_from_iter(needle);
+    /// assert_eq!(b"foo", finder.needle());
+    /// assert_eq!(Some(4), finder.find(b"bar foo"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn build_forward_from_iter<I: IntoIterator<Item = u8>>(
+        &self,
+        needle: I,
+    ) -> Finder<'static> {
+        let needle: Vec<u8> = needle.into_iter().collect();
+        let ninfo = NeedleInfo::new(&needle);
+        let cow = CowBytes::new_owned(needle);
+        Finder { searcher: Searcher::build(self.config, cow, ninfo, None) }
+    }
+
+    /// Build a forward finder from a needle that may be either borrowed or
+    /// owned, using the current settings.
+    ///
+    /// The finder adopts the needle as is: a borrowed needle stays borrowed,
+    /// and an owned needle is moved into the finder without copying it. This
+    /// avoids the copy made by building a finder that borrows an owned
+    /// needle and then calling [`Finder::into_owned`].
+    ///
+    /// This is only available when the `std` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// fn needle(upper: bool) -> Cow<'static, [u8]> {
+    ///     if upper {
+    ///         Cow::Owned(b"foo".to_ascii_uppercase())
+    ///     } else {
+    ///         Cow::Borrowed(b"foo")
+    ///     }
+    /// }
+    ///
+    /// let finder = FinderBuilder::new().build_forward_cow(needle(true));
+    /// assert_eq!(Some(4), finder.find(b"bar FOO"));
+    /// let finder = FinderBuilder::new().build_forward_cow(needle(false));
+    /// assert_eq!(Some(4), finder.find(b"bar foo"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn build_forward_cow<'n>(
+        &self,
+        needle: std::borrow::Cow<'n, [u8]>,
+    ) -> Finder<'n> {
+        let cow = CowBytes::from_cow(needle);
+        let ninfo = NeedleInfo::new(&cow);
+        Finder { searcher: Searcher::build(self.config, cow, ninfo, None) }
+    }
+
+    /// Build a reverse finder using the given needle from the current
+    /// settings.
+    pub fn build_reverse<'n, B: ?Sized + AsRef<[u8]>>(
+        &self,
+        needle: &'n B,
+    ) -> FinderRev<'n> {
+        FinderRev { searcher: SearcherRev::new(self.config, needle.as_ref()) }
+    }
+
+    /// Build a bidirectional finder using the given needle from the current
+    /// settings.
+    ///
+    /// Note that the prefetch distance only applies to forward searches. All
+    /// other settings apply in both directions.
+    pub fn build_bidirectional<'n, B: ?Sized + AsRef<[u8]>>(
+        &self,
+        needle: &'n B,
+    ) -> BiFinder<'n> {
+        BiFinder::from_searcher(Searcher::new(self.config, needle.as_ref()))
+    }
+
+    /// Build a forward finder for a needle with wildcard positions, using
+    /// the current settings.
+    ///
+    /// A position `i` at which `mask[i] == 0` matches any byte. Every other
+    /// position must match `needle[i]` exactly. The prefilter only looks for
+    /// bytes at positions that aren't wildcards. An adaptive prefilter isn't
+    /// supported for masked needles, and is treated as
+    /// [`Prefilter::Auto`].
+    ///
+    /// # Panics
+    ///
+    /// This panics if `needle` and `mask` have different lengths.
+    pub fn build_masked<'n, N, M>(
+        &self,
+        needle: &'n N,
+        mask: &'n M,
+    ) -> MaskedFinder<'n>
+    where
+        N: ?Sized + AsRef<[u8]>,
+        M: ?Sized + AsRef<[u8]>,
+    {
+        MaskedFinder::new(self.config, needle.as_ref(), mask.as_ref())
+    }
+
+    /// Configure the prefilter setting for the finder.
+    ///
+    /// See the documentation for [`Prefilter`] for more discussion on why
+    /// you might want to configure this.
+    pub fn prefilter(&mut self, prefilter: Prefilter) -> &mut FinderBuilder {
+        self.config.prefilter = prefilter;
+        self
+    }
+
+    /// Configure the substring search algorithm used by forward finders.
+    ///
+    /// See the documentation for [`Strategy`] for the trade offs. The default
+    /// is [`Strategy::Auto`]. This has no effect on reverse or masked
+    /// finders.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::{FinderBuilder, Strategy};
+    ///
+    /// let finder = FinderBuilder::new()
+    ///     .strategy(Strategy::Horspool)
+    ///     .build_forward("needle");
+    /// assert_eq!(Some(9), finder.find(b"haystack needle"));
+    /// ```
+    pub fn strategy(&mut self, strategy: Strategy) -> &mut FinderBuilder {
+        self.config.strategy = strategy;
+        self
+    }
+
+    /// Use the `Auto` prefilter, but judge whether it's effective after at
+    /// most `max_warmup_bytes` bytes of the haystack.
+    ///
+    /// A prefilter is normally given a fixed number of runs to warm up before
+    /// it's judged on how many bytes it skips on average, and disabled if it
+    /// skips too few. On short haystacks with many false positive candidates,
+    /// that warmup can make up most of the search. With a budget, the
+    /// prefilter is also judged as soon as it has moved past
+    /// `max_warmup_bytes` bytes, counting each candidate it reported as one
+    /// byte, so that an ineffective prefilter is disabled sooner.
+    ///
+    /// The default budget is unlimited, which preserves the fixed warmup.
+    /// The budget also applies if the prefilter is later set to
+    /// [`Prefilter::Adaptive`], and it is not used by reverse searchers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// let finder = FinderBuilder::new()
+    ///     .prefilter_auto_with_budget(256)
+    ///     .build_forward("foo");
+    /// assert_eq!(Some(4), finder.find(b"bar foo"));
+    /// ```
+    pub fn prefilter_auto_with_budget(
+        &mut self,
+        max_warmup_bytes: usize,
+    ) -> &mut FinderBuilder {
+        self.config.prefilter = Prefilter::Auto;
+        self.config.max_warmup_bytes = max_warmup_bytes;
+        self
+    }
+
+    /// Set the longest needle for which a vectorized search routine that
+    /// confirms its own candidate matches may be used.
+    ///
+    /// This routine has very low latency, but its worst case time complexity
+    /// is `O(needle.len() * haystack.len())`. For this reason, it is only
+    /// used for short needles by default, and longer needles use Two-Way,
+    /// which guarantees `O(needle.len() + haystack.len())` time. Raising
+    /// this limit may make searches for longer needles faster on typical
+    /// inputs, but can make them much slower on adversarial inputs. It should
+    /// only be raised when the haystacks searched are trusted.
+    ///
+    /// The default is `32`. Values larger than `255` are treated as `255`.
+    /// Setting this to a value less than `2` disables the routine entirely.
+    ///
+    /// This applies to reverse searchers too, which have their own variant
+    /// of the routine. This setting has no effect on targets where the
+    /// routine isn't available.
+    pub fn max_simd_needle_len(&mut self, len: usize) -> &mut FinderBuilder {
+        self.config.max_simd_needle_len = len;
+        self
+    }
+
+    /// Set the most times a needle may repeat itself for the vectorized
+    /// search routine for short needles to be used.
+    ///
+    /// A needle repeats itself `needle.len() / period` times, where `period`
+    /// is its smallest period (see [`Finder::needle_period`]). For example,
+    /// `abcabc` repeats twice, and `aaaa` repeats four times. When a needle
+    /// repeats more than `repetitions` times, Two-Way is used instead, even
+    /// if the needle is no longer than
+    /// [`FinderBuilder::max_simd_needle_len`].
+    ///
+    /// The vectorized routine confirms each candidate match by comparing it
+    /// with the entire needle, and highly repetitive needles are the easiest
+    /// for an adversarial haystack to produce many candidates for. So when
+    /// needles are untrusted, setting this (e.g., to `2`) guarantees that the
+    /// most repetitive needles are always searched for in linear time, while
+    /// most other short needles still benefit from the vectorized routine.
+    /// To guarantee linear time for every needle, disable the routine with
+    /// `max_simd_needle_len` instead.
+    ///
+    /// Computing a needle's period takes time quadratic in the length of
+    /// the needle in the worst case. It is only computed when this is set,
+    /// and only for needles short enough for the vectorized routine.
+    ///
+    /// The default is `usize::MAX`, which means repetition is never checked.
+    ///
+    /// This applies to reverse searchers too. This setting has no effect on
+    /// targets where the routine isn't available.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// let finder = FinderBuilder::new()
+    ///     .max_simd_needle_repetitions(2)
+    ///     .build_forward("aaaaaaaa");
+    /// assert_eq!(Some(5), finder.find(b"aaab aaaaaaaa"));
+    /// ```
+    pub fn max_simd_needle_repetitions(
+        &mut self,
+        repetitions: usize,
+    ) -> &mut FinderBuilder {
+        self.config.max_simd_needle_repetitions = repetitions;
+        self
+    }
+
+    /// Set how far ahead of the current search position, in bytes, to issue
+    /// software prefetches for the haystack.
+    ///
+    /// This may help throughput when scanning very large haystacks that are
+    /// not in cache, such as a cold memory mapped file, by hiding some of the
+    /// latency of reading from memory. On haystacks that are already in
+    /// cache, it only adds work. Whether it helps at all depends heavily on
+    /// the CPU, since the hardware prefetcher already handles sequential
+    /// scans well, so it should be benchmarked before being enabled.
+    ///
+    /// The default is `0`, which disables prefetching. A few kilobytes (e.g.,
+    /// `4096`) is a reasonable place to start.
+    ///
+    /// This currently only applies to the vectorized routine used for short
+    /// needles (see [`FinderBuilder::max_simd_needle_len`]). It has no effect
+    /// on targets where that routine isn't available, and it is not used by
+    /// reverse searchers.
+    pub fn prefetch_distance(&mut self, bytes: usize) -> &mut FinderBuilder {
+        self.config.prefetch_distance = bytes;
+        self
+    }
+
+    /// Set whether an empty needle matches at every position of a haystack.
+    ///
+    /// By default, an empty needle matches at every position, including the
+    /// end of the haystack, as it does for `str::find`. So a forward search
+    /// reports `0` and a reverse search reports `haystack.len()`, and the
+    /// iterators report every position from `0` to `haystack.len()`
+    /// inclusive. (They make progress by stepping over each match, and an
+    /// empty match is stepped over by moving ahead one byte.)
+    ///
+    /// When this is disabled, searching for an empty needle never reports a
+    /// match, and iterators over its matches are empty. This applies to
+    /// forward, reverse, bidirectional and masked finders alike. It has no
+    /// effect on non-empty needles.
+    ///
+    /// The default is `true`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// let finder = FinderBuilder::new().build_forward("");
+    /// assert_eq!(4, finder.find_iter(b"abc").count());
+    ///
+    /// let finder =
+    ///     FinderBuilder::new().empty_needle_matches(false).build_forward("");
+    /// assert_eq!(None, finder.find(b"abc"));
+    /// assert_eq!(0, finder.find_iter(b"abc").count());
+    /// ```
+    pub fn empty_needle_matches(&mut self, yes: bool) -> &mut FinderBuilder {
+        self.config.empty_needle_matches = yes;
+        self
+    }
+
+    /// Whether iterating over the matches of a needle reports overlapping
+    /// matches.
+    ///
+    /// By default, iterators step over each match, so that matches never
+    /// overlap. When this is enabled, they step one byte past the start of
+    /// each match instead (or, in reverse, one byte before its end), so that
+    /// every occurrence is reported. The iterator types stay the same, so
+    /// code that is generic over them can pick either behavior when the
+    /// finder is built.
+    ///
+    /// This applies to `find_iter` and `rfind_iter` on every finder, to the
+    /// iterators built on them, and to the methods documented as equivalent
+    /// to them (`find_nth`, `for_each_match` and `count`). Methods that need
+    /// matches that don't overlap, like `tokenize` and `replace`, ignore it.
+    ///
+    /// An empty needle matches at every position either way, so this has no
+    /// effect on it.
+    ///
+    /// Each match causes a new search to start within it, so with this
+    /// enabled, iterating over every match takes
+    /// `O(needle.len() * haystack.len())` time in the worst case.
+    ///
+    /// The default is `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// let finder = FinderBuilder::new().overlapping(true).build_forward("aa");
+    /// let matches: Vec<usize> = finder.find_iter(b"aaaa").collect();
+    /// assert_eq!(vec![0, 1, 2], matches);
+    ///
+    /// let finder = FinderBuilder::new().overlapping(true).build_reverse("aa");
+    /// let matches: Vec<usize> = finder.rfind_iter(b"aaaa").collect();
+    /// assert_eq!(vec![2, 1, 0], matches);
+    /// ```
+    pub fn overlapping(&mut self, yes: bool) -> &mut FinderBuilder {
+        self.config.overlapping = yes;
+        self
+    }
+}
+
+/// The internal implementation of a forward substring searcher.
+///
+/// The reality is that this is a "meta" searcher. Namely, depending on a
+/// variety of parameters (CPU support, target, needle size, haystack size and
+/// even dynamic properties such as prefilter effectiveness), the actual
+/// algorithm employed to do substring search may change.
+#[derive(Clone, Debug)]
+struct Searcher<'n> {
+    /// The actual needle we're searching for.
+    ///
+    /// A CowBytes is like a Cow<[u8]>, except in no_std environments, it is
+    /// specialized to a single variant (the borrowed form).
+    needle: CowBytes<'n>,
+    /// A collection of facts computed on the needle that are useful for more
+    /// than one substring search algorithm.
+    ninfo: NeedleInfo,
+    /// The configuration used to build this searcher.
+    config: SearcherConfig,
+    /// A prefilter function, if it was deemed appropriate.
+    ///
+    /// Some substring search implementations (like Two-Way) benefit greatly
+    /// if we can quickly find candidate starting positions for a match.
+    prefn: Option<PrefilterFn>,
+    /// The actual substring implementation in use.
+    kind: SearcherKind,
+}
+
+/// A collection of facts computed about a search needle.
+///
+/// We group these things together because it's useful to be able to hand them
+/// to prefilters or substring algorithms that want them.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct NeedleInfo {
+    /// The offsets of "rare" bytes detected in the needle.
+    ///
+    /// This is meant to be a heuristic in order to maximize the effectiveness
+    /// of vectorized code. Namely, vectorized code tends to focus on only
+    /// one or two bytes. If we pick bytes from the needle that occur
+    /// infrequently, then more time will be spent in the vectorized code and
+    /// will likely make the overall search (much) faster.
+    ///
+    /// Of course, this is only a heuristic based on a background frequency
+    /// distribution of bytes. But it tends to work very well in practice.
+    pub(crate) rarebytes: RareNeedleBytes,
+    /// A Rabin-Karp hash of the needle.
+    ///
+    /// This is store here instead of in a more specific Rabin-Karp search
+    /// since Rabin-Karp may be used even if another SearchKind corresponds
+    /// to some other search implementation. e.g., If measurements suggest RK
+    /// is faster in some cases or if a search implementation can't handle
+    /// particularly small haystack. (Moreover, we cannot use RK *generally*,
+    /// since its worst case time is multiplicative. Instead, we only use it
+    /// some small haystacks, where "small" is a constant.)
+    ///
+    /// When Rabin-Karp is compiled out, this is only kept so that it can be
+    /// exposed by `Finder::into_parts`.
+    #[cfg_attr(
+        all(feature = "no-rabinkarp", not(feature = "std")),
+        allow(dead_code)
+    )]
+    pub(crate) nhash: NeedleHash,
+}
+
+/// Configuration for substring search.
+#[derive(Clone, Copy, Debug)]
+struct SearcherConfig {
+    /// This permits changing the behavior of the prefilter, since it can have
+    /// a variable impact on performance.
+    prefilter: Prefilter,
+    /// The forward search algorithm to use for needles with two or more
+    /// bytes.
+    strategy: Strategy,
+    /// The longest needle for which the "generic SIMD" searcher may be used.
+    /// Its worst case is multiplicative, so this trades that guarantee away
+    /// for better latency on longer needles.
+    max_simd_needle_len: usize,
+    /// The most times a needle may repeat itself for the "generic SIMD"
+    /// searcher to be used. Repetitive needles are the easiest to produce
+    /// many candidates for, so this bounds its multiplicative worst case.
+    max_simd_needle_repetitions: usize,
+    /// How far ahead, in bytes, the "generic SIMD" searcher prefetches the
+    /// haystack. Zero disables prefetching.
+    prefetch_distance: usize,
+    /// Whether an empty needle matches at every position. When disabled, it
+    /// never matches.
+    empty_needle_matches: bool,
+    /// The number of bytes after which a prefilter is judged on its average
+    /// skip, even if it's still warming up.
+    max_warmup_bytes: usize,
+    /// Whether iterating over matches steps one byte past the start of each
+    /// match, instead of past its end.
+    overlapping: bool,
+}
+
+impl Default for SearcherConfig {
+    fn default() -> SearcherConfig {
+        SearcherConfig::new()
+    }
+}
+
+impl SearcherConfig {
+    /// The default configuration. This is a `const fn` so that empty
+    /// searchers can be built in constant expressions.
+    const fn new() -> SearcherConfig {
+        SearcherConfig {
+            // This must agree with `Prefilter::default`.
+            prefilter: Prefilter::Auto,
+            strategy: Strategy::Auto,
+            #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+            max_simd_needle_len: genericsimd::MAX_NEEDLE_LEN,
+            // There is no vectorized routine to configure on other targets,
+            // so this is never used.
+            #[cfg(not(all(target_arch = "x86_64", memchr_runtime_simd)))]
+            max_simd_needle_len: 0,
+            max_simd_needle_repetitions: core::usize::MAX,
+            prefetch_distance: 0,
+            empty_needle_matches: true,
+            max_warmup_bytes: core::usize::MAX,
+            overlapping: false,
+        }
+    }
+
+    /// Returns the longest needle for which the "generic SIMD" searcher may
+    /// be used when searching for the given needle.
+    ///
+    /// This is `max_simd_needle_len`, unless the needle is short enough for
+    /// the searcher but repeats itself too many times, in which case this is
+    /// `0` so that the searcher is never used.
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    fn simd_needle_len(&self, needle: &[u8]) -> usize {
+        if self.max_simd_needle_repetitions == core::usize::MAX
+            || needle.len() < 2
+            || needle.len() > self.max_simd_needle_len
+        {
+            return self.max_simd_needle_len;
+        }
+        let period = twoway::period(needle);
+        if needle.len() / period > self.max_simd_needle_repetitions {
+            0
+        } else {
+            self.max_simd_needle_len
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum SearcherKind {
+    /// A special case for empty needles. An empty needle always matches, even
+    /// in an empty haystack.
+    Empty,
+    /// An empty needle that was configured to never match.
+    Never,
+    /// This is used whenever the needle is a single byte. In this case, we
+    /// always use memchr.
+    OneByte(u8),
+    /// Two-Way is the generic work horse and is what provides our additive
+    /// linear time guarantee. In general, it's used when the needle is bigger
+    /// than 8 bytes or so.
+    TwoWay(twoway::Forward),
+    /// Boyer-Moore-Horspool, which is only used when it's requested via
+    /// `FinderBuilder::strategy`. Its skip table is built on each search.
+    Horspool,
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    GenericSIMD128(x86::sse::Forward),
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    GenericSIMD256(x86::avx::Forward),
+}
+
+impl<'n> Searcher<'n> {
+    fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
+        let ninfo = NeedleInfo::new(needle);
+        Searcher::build(config, CowBytes::new(needle), ninfo, None)
+    }
+
+    /// Build the same searcher as `Searcher::new` does for an empty needle
+    /// and the default configuration, but in a constant expression.
+    const fn empty() -> Searcher<'static> {
+        Searcher {
+            needle: CowBytes::empty(),
+            ninfo: NeedleInfo::empty(),
+            config: SearcherConfig::new(),
+            prefn: None,
+            kind: SearcherKind::Empty,
+        }
+    }
+
+    /// Create a searcher from parts previously returned by `params`. No
+    /// critical factorization is computed.
+    ///
+    /// This panics if any of the parts are out of bounds for the needle.
+    fn from_params(
+        config: SearcherConfig,
+        needle: &'n [u8],
+        params: &TwoWayParams,
+    ) -> Searcher<'n> {
+        // Tiny needles don't use Two-Way or rare bytes, so there's nothing
+        // to reconstruct.
+        if needle.len() <= 1 {
+            return Searcher::new(config, needle);
+        }
+        assert!(
+            (params.rare1i as usize) < needle.len()
+                && (params.rare2i as usize) < needle.len(),
+            "rare byte offsets ({}, {}) are out of bounds for needle of \
+             length {}",
+            params.rare1i,
+            params.rare2i,
+            needle.len(),
+        );
+        let ninfo = NeedleInfo {
+            rarebytes: RareNeedleBytes::new(params.rare1i, params.rare2i),
+            nhash: NeedleHash::from_parts(params.hash, params.hash_2pow),
+        };
+        let tw = twoway::Forward::from_params(needle, params);
+        Searcher::build(config, CowBytes::new(needle), ninfo, Some(tw))
+    }
+
+    /// Build a searcher from facts already computed about the needle, which
+    /// may be borrowed or owned. If `tw` is `None` and Two-Way is selected,
+    /// then its critical factorization is computed.
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    fn build(
+        config: SearcherConfig,
+        cow: CowBytes<'n>,
+        ninfo: NeedleInfo,
+        tw: Option<twoway::Forward>,
+    ) -> Searcher<'n> {
+        use self::SearcherKind::*;
+
+        let needle = cow.as_slice();
+        let prefn =
+            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
+        let simd_len = config.simd_needle_len(needle);
+        let kind = if needle.is_empty() {
+            if config.empty_needle_matches {
+                Empty
+            } else {
+                Never
+            }
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else if config.strategy == Strategy::Horspool {
+            Horspool
+        } else if let Some(fwd) = x86::avx::Forward::new(
+            &ninfo,
+            needle,
+            simd_len,
+            config.prefetch_distance,
+        ) {
+            GenericSIMD256(fwd)
+        } else if let Some(fwd) = x86::sse::Forward::new(
+            &ninfo,
+            needle,
+            simd_len,
+            config.prefetch_distance,
+        ) {
+            GenericSIMD128(fwd)
+        } else {
+            TwoWay(tw.unwrap_or_else(|| twoway::Forward::new(needle)))
+        };
+        Searcher { needle: cow, ninfo, config, prefn, kind }
+    }
+
+    /// Build a searcher from facts already computed about the needle, which
+    /// may be borrowed or owned. If `tw` is `None` and Two-Way is selected,
+    /// then its critical factorization is computed.
+    #[cfg(not(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)))]
+    fn build(
+        config: SearcherConfig,
+        cow: CowBytes<'n>,
+        ninfo: NeedleInfo,
+        tw: Option<twoway::Forward>,
+    ) -> Searcher<'n> {
+        use self::SearcherKind::*;
+
+        let needle = cow.as_slice();
+        let prefn =
+            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
+        let kind = if needle.is_empty() {
+            if config.empty_needle_matches {
+                Empty
+            } else {
+                Never
+            }
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else if config.strategy == Strategy::Horspool {
+            Horspool
+        } else {
+            TwoWay(tw.unwrap_or_else(|| twoway::Forward::new(needle)))
+        };
+        Searcher { needle: cow, ninfo, config, prefn, kind }
+    }
+
+    /// Return all of the pre-computed parts of this searcher that depend only
+    /// on its needle.
+    ///
+    /// If this searcher doesn't use Two-Way, then its critical factorization
+    /// is computed.
+    #[cfg(feature = "std")]
+    fn params(&self) -> TwoWayParams {
+        let (rare1i, rare2i) = self.ninfo.rarebytes.as_rare_u8();
+        let (hash, hash_2pow) = self.ninfo.nhash.as_parts();
+        let mut params = TwoWayParams {
+            critical_pos: 0,
+            shift: TwoWayShift::Large { shift: 0 },
+            byteset: 0,
+            rare1i,
+            rare2i,
+            hash,
+            hash_2pow,
+        };
+        match self.kind {
+            SearcherKind::TwoWay(ref tw) => tw.write_params(&mut params),
+            _ => twoway::Forward::new(self.needle()).write_params(&mut params),
+        }
+        params
+    }
+
+    /// Return a fresh prefilter state that can be used with this searcher.
+    /// A prefilter state is used to track the effectiveness of a searcher's
+    /// prefilter for speeding up searches. Therefore, the prefilter state
+    /// should generally be reused on subsequent searches (such as in an
+    /// iterator). For searches on a different haystack, then a new prefilter
+    /// state should be used.
+    ///
+    /// This always initializes a valid (but possibly inert) prefilter state
+    /// even if this searcher does not have a prefilter enabled.
+    fn prefilter_state(&self) -> PrefilterState {
+        if self.prefn.is_none() {
+            PrefilterState::inert()
+        } else if self.config.prefilter.is_adaptive() {
+            PrefilterState::adaptive()
+                .with_max_warmup_bytes(self.config.max_warmup_bytes)
+        } else {
+            PrefilterState::new()
+                .with_max_warmup_bytes(self.config.max_warmup_bytes)
+        }
+    }
+
+    fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+
+    /// How far past the start of a match iterating over matches resumes.
+    fn stride(&self) -> usize {
+        if self.config.overlapping {
+            1
+        } else {
+            core::cmp::max(1, self.needle().len())
+        }
+    }
+
+    fn as_ref(&self) -> Searcher<'_> {
+        use self::SearcherKind::*;
+
+        let kind = match self.kind {
+            Empty => Empty,
+            Never => Never,
+            OneByte(b) => OneByte(b),
+            TwoWay(tw) => TwoWay(tw),
+            Horspool => Horspool,
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD128(gs) => GenericSIMD128(gs),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD256(gs) => GenericSIMD256(gs),
+        };
+        Searcher {
+            needle: CowBytes::new(self.needle()),
+            ninfo: self.ninfo,
+            config: self.config,
+            prefn: self.prefn,
+            kind,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn into_owned(self) -> Searcher<'static> {
+        use self::SearcherKind::*;
+
+        let kind = match self.kind {
+            Empty => Empty,
+            Never => Never,
+            OneByte(b) => OneByte(b),
+            TwoWay(tw) => TwoWay(tw),
+            Horspool => Horspool,
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD128(gs) => GenericSIMD128(gs),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD256(gs) => GenericSIMD256(gs),
+        };
+        Searcher {
+            needle: self.needle.into_owned(),
+            ninfo: self.ninfo,
+            config: self.config,
+            prefn: self.prefn,
+            kind,
+        }
+    }
+
+    /// Implements forward substring search by selecting the implementation
+    /// chosen at construction and executing it on the given haystack with the
+    /// prefilter's current state of effectiveness.
+    #[inline(always)]
+    fn find(
+        &self,
+        state: &mut PrefilterState,
+        haystack: &[u8],
+    ) -> Option<usize> {
+        use self::SearcherKind::*;
+
+        let needle = self.needle();
+        if haystack.len() < needle.len() {
+            return None;
+        }
+        match self.kind {
+            Empty => Some(0),
+            Never => None,
+            OneByte(b) => crate::memchr(b, haystack),
+            TwoWay(ref tw) => {
+                // For very short haystacks (e.g., where the prefilter probably
+                // can't run), it's faster to just run RK.
+                #[cfg(not(feature = "no-rabinkarp"))]
+                {
+                    if rabinkarp::is_fast(haystack, needle) {
+                        return rabinkarp::find_with(
+                            &self.ninfo.nhash,
+                            haystack,
+                            needle,
+                        );
+                    }
+                }
+                if haystack.len() >= RARE_PREPASS_MIN_HAYSTACK_LEN
+                    && !self.config.prefilter.is_none()
+                {
+                    let start = self.rare_byte_start(haystack)?;
+                    return self
+                        .find_tw(tw, state, &haystack[start..], needle)
+                        .map(|i| start + i);
+                }
+                self.find_tw(tw, state, haystack, needle)
+            }
+            Horspool => horspool::find(haystack, needle),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD128(ref gs) => {
+                // The SIMD matcher can't handle particularly short haystacks,
+                // so we fall back to RK in these cases.
+                if haystack.len() < gs.min_haystack_len() {
+                    self.find_short(haystack, needle)
+                } else {
+                    gs.find(haystack, needle)
+                }
+            }
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD256(ref gs) => {
+                // The SIMD matcher can't handle particularly short haystacks,
+                // so we fall back to RK in these cases.
+                if haystack.len() < gs.min_haystack_len() {
+                    self.find_short(haystack, needle)
+                } else {
+                    gs.find(haystack, needle)
+                }
+            }
+        }
+    }
+
+    /// Like `find`, but searches `haystack[..len]` while treating the rest
+    /// of `haystack` as padding. When the padding is big enough, the generic
+    /// SIMD searchers read into it instead of handling the end of the
+    /// haystack specially.
+    ///
+    /// Callers must ensure that `len <= haystack.len()`.
+    #[inline(always)]
+    fn find_padded(
+        &self,
+        state: &mut PrefilterState,
+        haystack: &[u8],
+        len: usize,
+    ) -> Option<usize> {
+        #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+        {
+            use self::SearcherKind::*;
+
+            let padding = haystack.len() - len;
+            match self.kind {
+                GenericSIMD128(ref gs) if padding >= gs.padding_len() => {
+                    return gs.find_padded(haystack, len, self.needle());
+                }
+                GenericSIMD256(ref gs) if padding >= gs.padding_len() => {
+                    return gs.find_padded(haystack, len, self.needle());
+                }
+                _ => {}
+            }
+        }
+        self.find(state, &haystack[..len])
+    }
+
+    /// Returns the smallest offset at which a match could start in the
+    /// given haystack, based on the first occurrence of the needle's rarest
+    /// byte. If that byte doesn't occur at all, then there is no match and
+    /// this returns `None`.
+    ///
+    /// This is a single `memchr` call, so it's cheap even when it proves
+    /// there is no match in a huge haystack. When the byte does occur, the
+    /// scan only covers bytes that a search would have skipped over anyway.
+    ///
+    /// Callers must ensure that `haystack.len() >= self.needle().len()`.
+    fn rare_byte_start(&self, haystack: &[u8]) -> Option<usize> {
+        let needle = self.needle();
+        let (rare1i, _) = self.ninfo.rarebytes.as_rare_usize();
+        // A match starting at `i` has the rare byte at `i + rare1i`, so
+        // searching from `rare1i` gives us a starting position directly.
+        crate::memchr(needle[rare1i], &haystack[rare1i..])
+    }
+
+    /// Search a haystack that is too short for the generic SIMD searcher.
+    #[cfg(all(
+        not(miri),
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        not(feature = "no-rabinkarp"),
+    ))]
+    #[inline(always)]
+    fn find_short(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        rabinkarp::find_with(&self.ninfo.nhash, haystack, needle)
+    }
+
+    /// Search a haystack that is too short for the generic SIMD searcher.
+    ///
+    /// Without Rabin-Karp, this just tries the needle at every position.
+    /// The haystack is never more than a vector longer than the needle, so
+    /// this is cheap regardless.
+    #[cfg(all(
+        not(miri),
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        feature = "no-rabinkarp",
+    ))]
+    fn find_short(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        (0..=haystack.len() - needle.len())
+            .find(|&i| util::is_prefix(&haystack[i..], needle))
+    }
+
+    /// Calls Two-Way on the given haystack/needle.
+    ///
+    /// This is marked as unlineable since it seems to have a better overall
+    /// effect on benchmarks. However, this is one of those cases where
+    /// inlining it results an improvement in other benchmarks too, so I
+    /// suspect we just don't have enough data yet to make the right call here.
+    ///
+    /// I suspect the main problem is that this function contains two different
+    /// inlined copies of Two-Way: one with and one without prefilters enabled.
+    #[inline(never)]
+    fn find_tw(
+        &self,
+        tw: &twoway::Forward,
+        state: &mut PrefilterState,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        if let Some(prefn) = self.prefn {
+            // We used to look at the length of a haystack here. That is, if
+            // it was too small, then don't bother with the prefilter. But two
+            // things changed: the prefilter falls back to memchr for small
+            // haystacks, and, above, Rabin-Karp is employed for tiny haystacks
+            // anyway.
+            if state.is_effective() {
+                let mut pre = Pre { state, prefn, ninfo: &self.ninfo };
+                return tw.find(Some(&mut pre), haystack, needle);
+            }
+        }
+        tw.find(None, haystack, needle)
+    }
+
+    /// Returns the first candidate reported by the prefilter without
+    /// confirming it. See `Finder::find_candidate` for details.
+    fn find_candidate(&self, haystack: &[u8]) -> Option<usize> {
+        let needle = self.needle();
+        if haystack.len() < needle.len() {
+            return None;
+        }
+        let prefn = match self.prefn {
+            None => return self.find(&mut self.prefilter_state(), haystack),
+            Some(prefn) => prefn,
+        };
+        // If the prefilter gives up because it's judged ineffective, then it
+        // reports the position it got to, which is still a valid candidate.
+        let mut state = self.prefilter_state();
+        let i = prefn.call(&mut state, &self.ninfo, haystack, needle)?;
+        // Candidates only increase, so if this one is too close to the end
+        // of the haystack to be a match, then so is every other.
+        if haystack.len() - i < needle.len() {
+            return None;
+        }
+        Some(i)
+    }
+
+    /// Implements forward substring search with instrumentation. See
+    /// `Finder::find_instrumented` for details.
+    #[cfg(feature = "instrumentation")]
+    fn find_instrumented(
+        &self,
+        haystack: &[u8],
+    ) -> (Option<usize>, SearchStats) {
+        let mut stats = SearchStats::default();
+        let mut state = self.prefilter_state();
+        let prefn = match self.prefn {
+            None => return (self.find(&mut state, haystack), stats),
+            Some(prefn) => prefn,
+        };
+        let needle = self.needle();
+        let mut pos = 0;
+        while haystack.len() - pos >= needle.len() {
+            if !state.is_effective() {
+                // Once the prefilter is disabled, the search proceeds as
+                // usual.
+                let found =
+                    self.find(&mut state, &haystack[pos..]).map(|i| pos + i);
+                return (found, stats);
+            }
+            let candidate = match prefn.call(
+                &mut state,
+                &self.ninfo,
+                &haystack[pos..],
+                needle,
+            ) {
+                None => {
+                    stats.bytes_scanned += haystack.len() - pos;
+                    return (None, stats);
+                }
+                Some(i) => pos + i,
+            };
+            stats.candidates += 1;
+            stats.bytes_scanned += candidate - pos;
+            stats.confirmations += 1;
+            if util::is_prefix(&haystack[candidate..], needle) {
+                return (Some(candidate), stats);
+            }
+            stats.rejected += 1;
+            pos = candidate + 1;
+        }
+        (None, stats)
     }
+}
 
-    /// Build a forward finder using the given needle from the current
-    /// settings.
-    pub fn build_forward<'n, B: ?Sized + AsRef<[u8]>>(
-        &self,
-        needle: &'n B,
-    ) -> Finder<'n> {
-        Finder { searcher: Searcher::new(self.config, needle.as_ref()) }
+impl NeedleInfo {
+    pub(crate) fn new(needle: &[u8]) -> NeedleInfo {
+        NeedleInfo {
+            rarebytes: RareNeedleBytes::forward(needle),
+            nhash: NeedleHash::forward(needle),
+        }
     }
 
-    /// Build a reverse finder using the given needle from the current
-    /// settings.
-    pub fn build_reverse<'n, B: ?Sized + AsRef<[u8]>>(
-        &self,
-        needle: &'n B,
-    ) -> FinderRev<'n> {
-        FinderRev { searcher: SearcherRev::new(needle.as_ref()) }
+    /// The needle info for an empty needle. This is what both `new` and
+    /// `reverse` compute for an empty needle.
+    const fn empty() -> NeedleInfo {
+        NeedleInfo {
+            rarebytes: RareNeedleBytes::new(0, 0),
+            nhash: NeedleHash::from_parts(0, 1),
+        }
     }
 
-    /// Configure the prefilter setting for the finder.
-    ///
-    /// See the documentation for [`Prefilter`] for more discussion on why
-    /// you might want to configure this.
-    pub fn prefilter(&mut self, prefilter: Prefilter) -> &mut FinderBuilder {
-        self.config.prefilter = prefilter;
-        self
+    /// Like `new`, but computes a Rabin-Karp hash suitable for reverse
+    /// searching. The rare byte offsets are the same in either direction.
+    pub(crate) fn reverse(needle: &[u8]) -> NeedleInfo {
+        NeedleInfo {
+            rarebytes: RareNeedleBytes::forward(needle),
+            nhash: NeedleHash::reverse(needle),
+        }
     }
 }
 
-/// The internal implementation of a forward substring searcher.
+/// The internal implementation of a reverse substring searcher.
 ///
-/// The reality is that this is a "meta" searcher. Namely, depending on a
-/// variety of parameters (CPU support, target, needle size, haystack size and
-/// even dynamic properties such as prefilter effectiveness), the actual
-/// algorithm employed to do substring search may change.
+/// See the forward searcher docs for more details. Currently, the reverse
+/// searcher is considerably simpler than the forward searcher. It only uses
+/// Two-Way (and Rabin-Karp for tiny haystacks), and its prefilter is always
+/// the one based on `memrchr`.
 #[derive(Clone, Debug)]
-struct Searcher<'n> {
+struct SearcherRev<'n> {
     /// The actual needle we're searching for.
-    ///
-    /// A CowBytes is like a Cow<[u8]>, except in no_std environments, it is
-    /// specialized to a single variant (the borrowed form).
     needle: CowBytes<'n>,
     /// A collection of facts computed on the needle that are useful for more
-    /// than one substring search algorithm.
+    /// than one substring search algorithm. The Rabin-Karp hash in here is
+    /// computed for reverse searching.
     ninfo: NeedleInfo,
     /// A prefilter function, if it was deemed appropriate.
-    ///
-    /// Some substring search implementations (like Two-Way) benefit greatly
-    /// if we can quickly find candidate starting positions for a match.
     prefn: Option<PrefilterFn>,
     /// The actual substring implementation in use.
-    kind: SearcherKind,
+    kind: SearcherRevKind,
+    /// Whether iterating over matches steps one byte before the end of each
+    /// match, instead of before its start.
+    overlapping: bool,
 }
 
-/// A collection of facts computed about a search needle.
-///
-/// We group these things together because it's useful to be able to hand them
-/// to prefilters or substring algorithms that want them.
 #[derive(Clone, Copy, Debug)]
-pub(crate) struct NeedleInfo {
-    /// The offsets of "rare" bytes detected in the needle.
-    ///
-    /// This is meant to be a heuristic in order to maximize the effectiveness
-    /// of vectorized code. Namely, vectorized code tends to focus on only
-    /// one or two bytes. If we pick bytes from the needle that occur
-    /// infrequently, then more time will be spent in the vectorized code and
-    /// will likely make the overall search (much) faster.
-    ///
-    /// Of course, this is only a heuristic based on a background frequency
-    /// distribution of bytes. But it tends to work very well in practice.
-    pub(crate) rarebytes: RareNeedleBytes,
-    /// A Rabin-Karp hash of the needle.
-    ///
-    /// This is store here instead of in a more specific Rabin-Karp search
-    /// since Rabin-Karp may be used even if another SearchKind corresponds
-    /// to some other search implementation. e.g., If measurements suggest RK
-    /// is faster in some cases or if a search implementation can't handle
-    /// particularly small haystack. (Moreover, we cannot use RK *generally*,
-    /// since its worst case time is multiplicative. Instead, we only use it
-    /// some small haystacks, where "small" is a constant.)
-    pub(crate) nhash: NeedleHash,
-}
-
-/// Configuration for substring search.
-#[derive(Clone, Copy, Debug, Default)]
-struct SearcherConfig {
-    /// This permits changing the behavior of the prefilter, since it can have
-    /// a variable impact on performance.
-    prefilter: Prefilter,
-}
-
-#[derive(Clone, Debug)]
-enum SearcherKind {
+enum SearcherRevKind {
     /// A special case for empty needles. An empty needle always matches, even
     /// in an empty haystack.
     Empty,
+    /// An empty needle that was configured to never match.
+    Never,
     /// This is used whenever the needle is a single byte. In this case, we
     /// always use memchr.
     OneByte(u8),
     /// Two-Way is the generic work horse and is what provides our additive
     /// linear time guarantee. In general, it's used when the needle is bigger
     /// than 8 bytes or so.
-    TwoWay(twoway::Forward),
+    TwoWay(twoway::Reverse),
     #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
-    GenericSIMD128(x86::sse::Forward),
+    GenericSIMD128(x86::sse::Reverse),
     #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
-    GenericSIMD256(x86::avx::Forward),
+    GenericSIMD256(x86::avx::Reverse),
 }
 
-impl<'n> Searcher<'n> {
+impl<'n> SearcherRev<'n> {
+    fn new(config: SearcherConfig, needle: &'n [u8]) -> SearcherRev<'n> {
+        SearcherRev::build(config, needle, NeedleInfo::reverse(needle))
+    }
+
+    /// Build the same searcher as `SearcherRev::new` does for an empty
+    /// needle and the default configuration, but in a constant expression.
+    const fn empty() -> SearcherRev<'static> {
+        SearcherRev {
+            needle: CowBytes::empty(),
+            ninfo: NeedleInfo::empty(),
+            prefn: None,
+            kind: SearcherRevKind::Empty,
+            overlapping: false,
+        }
+    }
+
+    /// Build a reverse searcher from facts already computed about the needle.
+    /// The Rabin-Karp hash in `ninfo` must be computed for reverse searching.
+    ///
+    /// The prefetch distance in `config` isn't used, since reverse searches
+    /// never prefetch.
     #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
-    fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
-        use self::SearcherKind::*;
+    fn build(
+        config: SearcherConfig,
+        needle: &'n [u8],
+        ninfo: NeedleInfo,
+    ) -> SearcherRev<'n> {
+        use self::SearcherRevKind::*;
 
-        let ninfo = NeedleInfo::new(needle);
         let prefn =
-            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
-        let kind = if needle.len() == 0 {
-            Empty
+            prefilter::reverse(&config.prefilter, &ninfo.rarebytes, needle);
+        let simd_len = config.simd_needle_len(needle);
+        let kind = if needle.is_empty() {
+            if config.empty_needle_matches {
+                Empty
+            } else {
+                Never
+            }
         } else if needle.len() == 1 {
             OneByte(needle[0])
-        } else if let Some(fwd) = x86::avx::Forward::new(&ninfo, needle) {
-            GenericSIMD256(fwd)
-        } else if let Some(fwd) = x86::sse::Forward::new(&ninfo, needle) {
-            GenericSIMD128(fwd)
+        } else if let Some(rev) =
+            x86::avx::Reverse::new(&ninfo, needle, simd_len)
+        {
+            GenericSIMD256(rev)
+        } else if let Some(rev) =
+            x86::sse::Reverse::new(&ninfo, needle, simd_len)
+        {
+            GenericSIMD128(rev)
         } else {
-            TwoWay(twoway::Forward::new(needle))
+            TwoWay(twoway::Reverse::new(needle))
         };
-        Searcher { needle: CowBytes::new(needle), ninfo, prefn, kind }
+        SearcherRev {
+            needle: CowBytes::new(needle),
+            ninfo,
+            prefn,
+            kind,
+            overlapping: config.overlapping,
+        }
     }
 
+    /// Build a reverse searcher from facts already computed about the needle.
+    /// The Rabin-Karp hash in `ninfo` must be computed for reverse searching.
+    ///
+    /// Only the prefilter, empty needle and overlapping settings of `config`
+    /// are used. The other settings only configure vectorized routines that
+    /// aren't available here.
     #[cfg(not(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)))]
-    fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
-        use self::SearcherKind::*;
+    fn build(
+        config: SearcherConfig,
+        needle: &'n [u8],
+        ninfo: NeedleInfo,
+    ) -> SearcherRev<'n> {
+        use self::SearcherRevKind::*;
 
-        let ninfo = NeedleInfo::new(needle);
         let prefn =
-            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
-        let kind = if needle.len() == 0 {
-            Empty
+            prefilter::reverse(&config.prefilter, &ninfo.rarebytes, needle);
+        let kind = if needle.is_empty() {
+            if config.empty_needle_matches {
+                Empty
+            } else {
+                Never
+            }
         } else if needle.len() == 1 {
             OneByte(needle[0])
         } else {
-            TwoWay(twoway::Forward::new(needle))
+            TwoWay(twoway::Reverse::new(needle))
         };
-        Searcher { needle: CowBytes::new(needle), ninfo, prefn, kind }
+        SearcherRev {
+            needle: CowBytes::new(needle),
+            ninfo,
+            prefn,
+            kind,
+            overlapping: config.overlapping,
+        }
     }
 
     /// Return a fresh prefilter state that can be used with this searcher.
-    /// A prefilter state is used to track the effectiveness of a searcher's
-    /// prefilter for speeding up searches. Therefore, the prefilter state
-    /// should generally be reused on subsequent searches (such as in an
-    /// iterator). For searches on a different haystack, then a new prefilter
-    /// state should be used.
     ///
-    /// This always initializes a valid (but possibly inert) prefilter state
-    /// even if this searcher does not have a prefilter enabled.
+    /// See `Searcher::prefilter_state` for more details.
     fn prefilter_state(&self) -> PrefilterState {
         if self.prefn.is_none() {
             PrefilterState::inert()
@@ -837,11 +4354,12 @@ impl<'n> Searcher<'n> {
         self.needle.as_slice()
     }
 
-    fn as_ref(&self) -> Searcher<'_> {
-        use self::SearcherKind::*;
+    fn as_ref(&self) -> SearcherRev<'_> {
+        use self::SearcherRevKind::*;
 
         let kind = match self.kind {
             Empty => Empty,
+            Never => Never,
             OneByte(b) => OneByte(b),
             TwoWay(tw) => TwoWay(tw),
             #[cfg(all(
@@ -857,20 +4375,22 @@ impl<'n> Searcher<'n> {
             ))]
             GenericSIMD256(gs) => GenericSIMD256(gs),
         };
-        Searcher {
+        SearcherRev {
             needle: CowBytes::new(self.needle()),
             ninfo: self.ninfo,
             prefn: self.prefn,
             kind,
+            overlapping: self.overlapping,
         }
     }
 
     #[cfg(feature = "std")]
-    fn into_owned(self) -> Searcher<'static> {
-        use self::SearcherKind::*;
+    fn into_owned(self) -> SearcherRev<'static> {
+        use self::SearcherRevKind::*;
 
         let kind = match self.kind {
             Empty => Empty,
+            Never => Never,
             OneByte(b) => OneByte(b),
             TwoWay(tw) => TwoWay(tw),
             #[cfg(all(
@@ -886,40 +4406,48 @@ impl<'n> Searcher<'n> {
             ))]
             GenericSIMD256(gs) => GenericSIMD256(gs),
         };
-        Searcher {
+        SearcherRev {
             needle: self.needle.into_owned(),
             ninfo: self.ninfo,
             prefn: self.prefn,
             kind,
+            overlapping: self.overlapping,
         }
     }
 
-    /// Implements forward substring search by selecting the implementation
+    /// Implements reverse substring search by selecting the implementation
     /// chosen at construction and executing it on the given haystack with the
     /// prefilter's current state of effectiveness.
     #[inline(always)]
-    fn find(
+    fn rfind(
         &self,
         state: &mut PrefilterState,
         haystack: &[u8],
     ) -> Option<usize> {
-        use self::SearcherKind::*;
+        use self::SearcherRevKind::*;
 
         let needle = self.needle();
         if haystack.len() < needle.len() {
             return None;
         }
         match self.kind {
-            Empty => Some(0),
-            OneByte(b) => crate::memchr(b, haystack),
+            Empty => Some(haystack.len()),
+            Never => None,
+            OneByte(b) => crate::memrchr(b, haystack),
             TwoWay(ref tw) => {
                 // For very short haystacks (e.g., where the prefilter probably
                 // can't run), it's faster to just run RK.
-                if rabinkarp::is_fast(haystack, needle) {
-                    rabinkarp::find_with(&self.ninfo.nhash, haystack, needle)
-                } else {
-                    self.find_tw(tw, state, haystack, needle)
+                #[cfg(not(feature = "no-rabinkarp"))]
+                {
+                    if rabinkarp::is_fast(haystack, needle) {
+                        return rabinkarp::rfind_with(
+                            &self.ninfo.nhash,
+                            haystack,
+                            needle,
+                        );
+                    }
                 }
+                self.rfind_tw(tw, state, haystack, needle)
             }
             #[cfg(all(
                 not(miri),
@@ -930,9 +4458,9 @@ impl<'n> Searcher<'n> {
                 // The SIMD matcher can't handle particularly short haystacks,
                 // so we fall back to RK in these cases.
                 if haystack.len() < gs.min_haystack_len() {
-                    rabinkarp::find_with(&self.ninfo.nhash, haystack, needle)
+                    self.rfind_short(haystack, needle)
                 } else {
-                    gs.find(haystack, needle)
+                    gs.rfind(haystack, needle)
                 }
             }
             #[cfg(all(
@@ -944,177 +4472,434 @@ impl<'n> Searcher<'n> {
                 // The SIMD matcher can't handle particularly short haystacks,
                 // so we fall back to RK in these cases.
                 if haystack.len() < gs.min_haystack_len() {
-                    rabinkarp::find_with(&self.ninfo.nhash, haystack, needle)
+                    self.rfind_short(haystack, needle)
                 } else {
-                    gs.find(haystack, needle)
+                    gs.rfind(haystack, needle)
                 }
             }
         }
     }
 
-    /// Calls Two-Way on the given haystack/needle.
+    /// Search a haystack that is too short for the generic SIMD searcher.
+    #[cfg(all(
+        not(miri),
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        not(feature = "no-rabinkarp"),
+    ))]
+    #[inline(always)]
+    fn rfind_short(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        rabinkarp::rfind_with(&self.ninfo.nhash, haystack, needle)
+    }
+
+    /// Search a haystack that is too short for the generic SIMD searcher.
     ///
-    /// This is marked as unlineable since it seems to have a better overall
-    /// effect on benchmarks. However, this is one of those cases where
-    /// inlining it results an improvement in other benchmarks too, so I
-    /// suspect we just don't have enough data yet to make the right call here.
+    /// See `Searcher::find_short` for why this is fine without Rabin-Karp.
+    #[cfg(all(
+        not(miri),
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        feature = "no-rabinkarp",
+    ))]
+    fn rfind_short(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        (0..=haystack.len() - needle.len())
+            .rev()
+            .find(|&i| util::is_prefix(&haystack[i..], needle))
+    }
+
+    /// Calls Two-Way in reverse on the given haystack/needle.
     ///
-    /// I suspect the main problem is that this function contains two different
-    /// inlined copies of Two-Way: one with and one without prefilters enabled.
+    /// This is marked as unlineable for the same reasons as its forward
+    /// counterpart, `Searcher::find_tw`.
     #[inline(never)]
-    fn find_tw(
+    fn rfind_tw(
         &self,
-        tw: &twoway::Forward,
+        tw: &twoway::Reverse,
         state: &mut PrefilterState,
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
         if let Some(prefn) = self.prefn {
-            // We used to look at the length of a haystack here. That is, if
-            // it was too small, then don't bother with the prefilter. But two
-            // things changed: the prefilter falls back to memchr for small
-            // haystacks, and, above, Rabin-Karp is employed for tiny haystacks
-            // anyway.
             if state.is_effective() {
                 let mut pre = Pre { state, prefn, ninfo: &self.ninfo };
-                return tw.find(Some(&mut pre), haystack, needle);
+                return tw.rfind(Some(&mut pre), haystack, needle);
             }
         }
-        tw.find(None, haystack, needle)
+        tw.rfind(None, haystack, needle)
     }
 }
 
-impl NeedleInfo {
-    pub(crate) fn new(needle: &[u8]) -> NeedleInfo {
-        NeedleInfo {
-            rarebytes: RareNeedleBytes::forward(needle),
-            nhash: NeedleHash::forward(needle),
+/// This module defines some generic quickcheck properties useful for testing
+/// any substring search algorithm. It also runs those properties for the
+/// top-level public API memmem routines. (The properties are also used to
+/// test various substring search implementations more granularly elsewhere as
+/// well.)
+#[cfg(all(test, feature = "std", not(miri)))]
+mod proptests {
+    use super::testutil::{naive_find, naive_rfind};
+
+    // N.B. This defines the quickcheck tests using the properties defined
+    // below. Because of macro-visibility weirdness, the actual macro is
+    // defined at the top of this file.
+    define_memmem_quickcheck_tests!(super::find, super::rfind);
+
+    quickcheck::quickcheck! {
+        fn qc_find_str_matches_std(haystack: String, needle: String) -> bool {
+            let fwd = super::Finder::new(&needle);
+            let rev = super::FinderRev::new(&needle);
+            let got: Vec<usize> = fwd.find_str_iter(&haystack).collect();
+            let expected: Vec<usize> =
+                haystack.match_indices(needle.as_str()).map(|(i, _)| i).collect();
+            got == expected
+                && fwd.find_str(&haystack) == haystack.find(needle.as_str())
+                && rev.rfind_str(&haystack) == haystack.rfind(needle.as_str())
         }
-    }
-}
 
-/// The internal implementation of a reverse substring searcher.
-///
-/// See the forward searcher docs for more details. Currently, the reverse
-/// searcher is considerably simpler since it lacks prefilter support. This
-/// was done because it adds a lot of code, and more surface area to test. And
-/// in particular, it's not clear whether a prefilter on reverse searching is
-/// worth it. (If you have a compelling use case, please file an issue!)
-#[derive(Clone, Debug)]
-struct SearcherRev<'n> {
-    /// The actual needle we're searching for.
-    needle: CowBytes<'n>,
-    /// A Rabin-Karp hash of the needle.
-    nhash: NeedleHash,
-    /// The actual substring implementation in use.
-    kind: SearcherRevKind,
-}
+        fn qc_find_merged_ranges(haystack: Vec<u8>, needle: Vec<u8>) -> bool {
+            // Mark every byte covered by a match, and read off the runs of
+            // covered bytes. Non-empty needles only, since empty matches
+            // don't cover anything.
+            if needle.is_empty() {
+                return true;
+            }
+            let mut covered = vec![false; haystack.len()];
+            for (i, w) in haystack.windows(needle.len()).enumerate() {
+                if w == &needle[..] {
+                    for c in &mut covered[i..i + needle.len()] {
+                        *c = true;
+                    }
+                }
+            }
+            let mut expected = vec![];
+            let mut i = 0;
+            while i < covered.len() {
+                if covered[i] {
+                    let start = i;
+                    while i < covered.len() && covered[i] {
+                        i += 1;
+                    }
+                    expected.push(start..i);
+                }
+                i += 1;
+            }
+            let finder = super::Finder::new(&needle);
+            let got: Vec<_> = finder.find_merged_ranges(&haystack).collect();
+            got == expected
+        }
 
-#[derive(Clone, Debug)]
-enum SearcherRevKind {
-    /// A special case for empty needles. An empty needle always matches, even
-    /// in an empty haystack.
-    Empty,
-    /// This is used whenever the needle is a single byte. In this case, we
-    /// always use memchr.
-    OneByte(u8),
-    /// Two-Way is the generic work horse and is what provides our additive
-    /// linear time guarantee. In general, it's used when the needle is bigger
-    /// than 8 bytes or so.
-    TwoWay(twoway::Reverse),
-}
+        fn qc_find_candidate_never_misses(
+            haystack: Vec<u8>,
+            needle: Vec<u8>
+        ) -> bool {
+            let finder = super::Finder::new(&needle);
+            match (finder.find_candidate(&haystack), finder.find(&haystack)) {
+                (Some(c), Some(m)) => c <= m,
+                (None, Some(_)) => false,
+                (Some(c), None) => haystack.len() - c >= needle.len(),
+                (None, None) => true,
+            }
+        }
+
+        fn qc_find_aligned(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            align: u8,
+            offset_mod: u8
+        ) -> bool {
+            // Use a tiny alphabet so that there are plenty of matches at
+            // offsets that don't conform.
+            let haystack: Vec<u8> = haystack.iter().map(|b| b % 2).collect();
+            let needle: Vec<u8> = needle.iter().map(|b| b % 2).collect();
+            let align = 1 + align as usize;
+            let offset_mod = offset_mod as usize % align;
+            let expected = (0..=haystack.len()).find(|&i| {
+                i % align == offset_mod && haystack[i..].starts_with(&needle)
+            });
+            let finder = super::Finder::new(&needle);
+            finder.find_aligned(&haystack, align, offset_mod) == expected
+        }
+
+        fn qc_cursor_matches_find_iter(
+            haystack: Vec<u8>,
+            needle: Vec<u8>
+        ) -> bool {
+            let finder = super::Finder::new(&needle);
+            let mut cursor = super::Cursor::new();
+            let mut got = vec![];
+            while let Some(i) =
+                finder.find_from_cursor(&haystack, &mut cursor)
+            {
+                got.push(i);
+            }
+            got == finder.find_iter(&haystack).collect::<Vec<usize>>()
+        }
+
+        fn qc_for_each_match(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            limit: u8
+        ) -> bool {
+            // The callback always sees at least one match before it can stop.
+            let limit = limit as usize + 1;
+            let finder = super::Finder::new(&needle);
+            let expected: Vec<usize> =
+                finder.find_iter(&haystack).take(limit).collect();
+            let mut got = vec![];
+            finder.for_each_match(&haystack, |i| {
+                got.push(i);
+                got.len() < limit
+            });
+            got == expected
+        }
+
+        fn qc_count(haystack: Vec<u8>, needle: Vec<u8>) -> bool {
+            // Use a tiny alphabet so that overlapping matches are likely.
+            let haystack: Vec<u8> = haystack.iter().map(|&b| b % 2).collect();
+            let needle: Vec<u8> = needle.iter().take(3).map(|&b| b % 2).collect();
+            let overlapping = (0..=haystack.len())
+                .filter(|&i| haystack[i..].starts_with(&needle))
+                .count();
+
+            let finder = super::Finder::new(&needle);
+            let count = finder.count(&haystack);
+            count == finder.find_iter(&haystack).count()
+                && count <= overlapping
+                && finder.count_overlapping(&haystack) == overlapping
+        }
+
+        fn qc_overlapping_iters(haystack: Vec<u8>, needle: Vec<u8>) -> bool {
+            let haystack: Vec<u8> = haystack.iter().map(|&b| b % 2).collect();
+            let needle: Vec<u8> = needle.iter().take(3).map(|&b| b % 2).collect();
+            let mut expected: Vec<usize> = (0..=haystack.len())
+                .filter(|&i| haystack[i..].starts_with(&needle))
+                .collect();
+
+            let mut builder = super::FinderBuilder::new();
+            builder.overlapping(true);
+            let fwd: Vec<usize> =
+                builder.build_forward(&needle).find_iter(&haystack).collect();
+            let rev: Vec<usize> =
+                builder.build_reverse(&needle).rfind_iter(&haystack).collect();
+            let ok = fwd == expected;
+            expected.reverse();
+            ok && rev == expected
+        }
+
+        fn qc_rfind_iter_with_state(
+            haystacks: Vec<Vec<u8>>,
+            needle: Vec<u8>
+        ) -> bool {
+            let finder = super::FinderRev::new(&needle);
+            let mut state = super::PrefilterState::new();
+            let mut inert = super::PrefilterState::inert();
+            haystacks.iter().all(|h| {
+                let expected: Vec<usize> = finder.rfind_iter(h).collect();
+                finder.rfind_iter_with_state(h, &mut state).eq(expected.clone())
+                    && finder.rfind_iter_with_state(h, &mut inert).eq(expected)
+            })
+        }
+
+        fn qc_replace_matches_str(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            replacement: Vec<u8>,
+            count: u8
+        ) -> bool {
+            // Use a tiny alphabet so that matches are likely, and so that
+            // everything is valid UTF-8.
+            let ab = |bytes: &[u8]| -> String {
+                bytes.iter().map(|&b| (b'a' + b % 2) as char).collect()
+            };
+            let (haystack, replacement) = (ab(&haystack), ab(&replacement));
+            let needle = ab(&needle[..core::cmp::min(3, needle.len())]);
+            let count = count as usize % 4;
+
+            let finder = super::Finder::new(&needle);
+            let h = haystack.as_bytes();
+            finder.replace(h, replacement.as_bytes())
+                == haystack.replace(&needle, &replacement).into_bytes()
+                && finder.replacen(h, replacement.as_bytes(), count)
+                    == haystack.replacen(&needle, &replacement, count).into_bytes()
+        }
+
+        fn qc_find_nth(haystack: Vec<u8>, needle: Vec<u8>, n: u8) -> bool {
+            let finder = super::Finder::new(&needle);
+            let n = n as usize % 8;
+            finder.find_nth(&haystack, n) == finder.find_iter(&haystack).nth(n)
+        }
 
-impl<'n> SearcherRev<'n> {
-    fn new(needle: &'n [u8]) -> SearcherRev<'n> {
-        use self::SearcherRevKind::*;
+        fn qc_find_iter_fwd(haystack: Vec<u8>, needle: Vec<u8>) -> bool {
+            let finder = super::FinderRev::new(&needle);
+            let mut expected: Vec<usize> =
+                finder.rfind_iter(&haystack).collect();
+            expected.reverse();
+            let it = finder.find_iter_fwd(&haystack);
+            it.len() == expected.len() && it.collect::<Vec<usize>>() == expected
+        }
 
-        let kind = if needle.len() == 0 {
-            Empty
-        } else if needle.len() == 1 {
-            OneByte(needle[0])
-        } else {
-            TwoWay(twoway::Reverse::new(needle))
-        };
-        SearcherRev {
-            needle: CowBytes::new(needle),
-            nhash: NeedleHash::reverse(needle),
-            kind,
+        fn qc_find_in_two(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            split: usize
+        ) -> bool {
+            let split = if haystack.is_empty() {
+                0
+            } else {
+                split % (haystack.len() + 1)
+            };
+            let (first, second) = haystack.split_at(split);
+            let finder = super::Finder::new(&needle);
+            finder.find_in_two(first, second) == finder.find(&haystack)
         }
-    }
 
-    fn needle(&self) -> &[u8] {
-        self.needle.as_slice()
-    }
+        fn qc_find_iter_filter(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            reject: Vec<usize>
+        ) -> bool {
+            // A naive version that tries the needle at every position.
+            let mut expected = vec![];
+            let mut pos = 0;
+            while pos + needle.len() <= haystack.len() {
+                if haystack[pos..].starts_with(&needle)
+                    && !reject.contains(&pos)
+                {
+                    expected.push(pos);
+                    pos += core::cmp::max(1, needle.len());
+                } else {
+                    pos += 1;
+                }
+            }
+            let got: Vec<usize> = super::Finder::new(&needle)
+                .find_iter_filter(&haystack, |i| !reject.contains(&i))
+                .collect();
+            got == expected
+        }
 
-    fn as_ref(&self) -> SearcherRev<'_> {
-        use self::SearcherRevKind::*;
+        fn qc_bifinder_matches_finders(
+            haystack: Vec<u8>,
+            needle: Vec<u8>
+        ) -> bool {
+            let bi = super::BiFinder::new(&needle);
+            let fwd = super::Finder::new(&needle);
+            let rev = super::FinderRev::new(&needle);
+            bi.find(&haystack) == fwd.find(&haystack)
+                && bi.rfind(&haystack) == rev.rfind(&haystack)
+                && bi.find_iter(&haystack).eq(fwd.find_iter(&haystack))
+                && bi.rfind_iter(&haystack).eq(rev.rfind_iter(&haystack))
+        }
 
-        let kind = match self.kind {
-            Empty => Empty,
-            OneByte(b) => OneByte(b),
-            TwoWay(tw) => TwoWay(tw),
-        };
-        SearcherRev {
-            needle: CowBytes::new(self.needle()),
-            nhash: self.nhash,
-            kind,
+        fn qc_find_after_before_nearest(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            at: usize
+        ) -> bool {
+            // Use a tiny alphabet so that matches are likely.
+            let haystack: Vec<u8> = haystack.iter().map(|&b| b % 2).collect();
+            let needle: Vec<u8> = needle.iter().take(3).map(|&b| b % 2).collect();
+            let at = at % (haystack.len() + 2);
+            let starts: Vec<usize> = (0..=haystack.len())
+                .filter(|&i| haystack[i..].starts_with(&needle))
+                .collect();
+            let after = starts.iter().cloned().find(|&i| i >= at);
+            let before = starts.iter().cloned().rev().find(|&i| i < at);
+            let nearest = starts
+                .iter()
+                .cloned()
+                .min_by_key(|&i| if i < at { at - i } else { i - at });
+
+            let bi = super::BiFinder::new(&needle);
+            super::Finder::new(&needle).find_after(&haystack, at) == after
+                && super::FinderRev::new(&needle).find_before(&haystack, at)
+                    == before
+                && bi.find_after(&haystack, at) == after
+                && bi.find_before(&haystack, at) == before
+                && bi.find_nearest(&haystack, at) == nearest
         }
-    }
 
-    #[cfg(feature = "std")]
-    fn into_owned(self) -> SearcherRev<'static> {
-        use self::SearcherRevKind::*;
+        fn qc_max_simd_needle_len(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            len: u8
+        ) -> bool {
+            let expected = super::Finder::new(&needle).find(&haystack);
+            let finder = super::FinderBuilder::new()
+                .max_simd_needle_len(len as usize)
+                .build_forward(&needle);
+            finder.find(&haystack) == expected
+        }
 
-        let kind = match self.kind {
-            Empty => Empty,
-            OneByte(b) => OneByte(b),
-            TwoWay(tw) => TwoWay(tw),
-        };
-        SearcherRev {
-            needle: self.needle.into_owned(),
-            nhash: self.nhash,
-            kind,
+        fn qc_max_simd_needle_repetitions(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            repetitions: u8
+        ) -> bool {
+            // Use a tiny alphabet so that repetitive needles are common.
+            let haystack: Vec<u8> = haystack.iter().map(|b| b % 2).collect();
+            let needle: Vec<u8> = needle.iter().map(|b| b % 2).collect();
+            let expected_fwd = super::Finder::new(&needle).find(&haystack);
+            let expected_rev = super::FinderRev::new(&needle).rfind(&haystack);
+            let mut builder = super::FinderBuilder::new();
+            builder.max_simd_needle_repetitions(repetitions as usize);
+            builder.build_forward(&needle).find(&haystack) == expected_fwd
+                && builder.build_reverse(&needle).rfind(&haystack)
+                    == expected_rev
         }
-    }
 
-    /// Implements reverse substring search by selecting the implementation
-    /// chosen at construction and executing it on the given haystack with the
-    /// prefilter's current state of effectiveness.
-    #[inline(always)]
-    fn rfind(&self, haystack: &[u8]) -> Option<usize> {
-        use self::SearcherRevKind::*;
+        fn qc_prefetch_distance(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            distance: u16
+        ) -> bool {
+            let expected = super::Finder::new(&needle).find(&haystack);
+            let finder = super::FinderBuilder::new()
+                .prefetch_distance(distance as usize)
+                .build_forward(&needle);
+            finder.find(&haystack) == expected
+        }
 
-        let needle = self.needle();
-        if haystack.len() < needle.len() {
-            return None;
+        fn qc_find_with_padding(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            padding: Vec<u8>
+        ) -> bool {
+            // Repeat the needle in the padding, so that a match in it would
+            // be found if it weren't excluded.
+            let mut buf = haystack.clone();
+            buf.extend_from_slice(&needle);
+            buf.extend_from_slice(&padding);
+            buf.extend_from_slice(&[0; 32]);
+            let finder = super::Finder::new(&needle);
+            finder.find_with_padding(&buf, haystack.len())
+                == finder.find(&haystack)
         }
-        match self.kind {
-            Empty => Some(haystack.len()),
-            OneByte(b) => crate::memrchr(b, haystack),
-            TwoWay(ref tw) => {
-                // For very short haystacks (e.g., where the prefilter probably
-                // can't run), it's faster to just run RK.
-                if rabinkarp::is_fast(haystack, needle) {
-                    rabinkarp::rfind_with(&self.nhash, haystack, needle)
-                } else {
-                    tw.rfind(haystack, needle)
+
+        fn qc_parts_roundtrip(haystack: Vec<u8>, needle: Vec<u8>) -> bool {
+            let finder = super::Finder::new(&needle);
+            let (n, params) = finder.clone().into_parts();
+            let rebuilt = super::Finder::from_parts(&n, params);
+            let (_, params2) = rebuilt.clone().into_parts();
+            n == needle
+                && params == params2
+                && rebuilt.find(&haystack) == finder.find(&haystack)
+        }
+
+        fn qc_tokenize_roundtrips(haystack: Vec<u8>, needle: Vec<u8>) -> bool {
+            let finder = super::Finder::new(&needle);
+            let mut rebuilt = vec![];
+            let mut matches = vec![];
+            for (token, m) in finder.tokenize(&haystack) {
+                rebuilt.extend_from_slice(token);
+                if let Some(i) = m {
+                    assert_eq!(rebuilt.len(), i);
+                    rebuilt.extend_from_slice(&needle);
+                    matches.push(i);
                 }
             }
+            rebuilt == haystack
+                && matches == finder.find_iter(&haystack).collect::<Vec<_>>()
         }
     }
-}
-
-/// This module defines some generic quickcheck properties useful for testing
-/// any substring search algorithm. It also runs those properties for the
-/// top-level public API memmem routines. (The properties are also used to
-/// test various substring search implementations more granularly elsewhere as
-/// well.)
-#[cfg(all(test, feature = "std", not(miri)))]
-mod proptests {
-    // N.B. This defines the quickcheck tests using the properties defined
-    // below. Because of macro-visibility weirdness, the actual macro is
-    // defined at the top of this file.
-    define_memmem_quickcheck_tests!(super::find, super::rfind);
 
     /// Check that every prefix of the given byte string is a substring.
     pub(crate) fn prefix_is_substring(
@@ -1170,44 +4955,779 @@ mod proptests {
             naive_find(haystack, needle) == search(haystack, needle)
         }
     }
+}
 
-    /// Naively search forwards for the given needle in the given haystack.
-    fn naive_find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
-        if needle.is_empty() {
-            return Some(0);
-        } else if haystack.len() < needle.len() {
-            return None;
+/// This module defines some hand-written "simple" substring tests. It
+/// also provides routines for easily running them on any substring search
+/// implementation.
+#[cfg(test)]
+mod testsimples {
+    define_memmem_simple_tests!(super::find, super::rfind);
+
+    #[test]
+    fn iters_are_fused() {
+        use core::iter::FusedIterator;
+
+        fn assert_fused<I: FusedIterator<Item = usize>>(mut it: I) {
+            for _ in it.by_ref() {}
+            assert_eq!(None, it.next());
+            assert_eq!(None, it.next());
         }
-        for i in 0..(haystack.len() - needle.len() + 1) {
-            if needle == &haystack[i..i + needle.len()] {
-                return Some(i);
-            }
+
+        for &needle in &["", "a", "ab", "abc"] {
+            assert_fused(super::find_iter(b"abcabcab", needle));
+            assert_fused(super::rfind_iter(b"abcabcab", needle));
         }
-        None
     }
 
-    /// Naively search in reverse for the given needle in the given haystack.
-    fn naive_rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
-        if needle.is_empty() {
-            return Some(haystack.len());
-        } else if haystack.len() < needle.len() {
-            return None;
+    #[cfg(all(
+        not(miri),
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        feature = "std"
+    ))]
+    #[test]
+    fn search_every_isa() {
+        use super::{x86::force, Finder, FinderRev};
+
+        for &isa in force::ALL {
+            force::with_max_isa(isa, || {
+                testsimples::run_search_tests_fwd(|h, n| {
+                    Finder::new(n).find(h)
+                });
+                testsimples::run_search_tests_rev(|h, n| {
+                    FinderRev::new(n).rfind(h)
+                });
+                testsimples::run_search_tests_fwd(|h, n| {
+                    let mut buf = h.to_vec();
+                    buf.extend_from_slice(n);
+                    buf.resize(buf.len() + 32, b'z');
+                    Finder::new(n).find_with_padding(&buf, h.len())
+                });
+            });
         }
-        for i in (0..(haystack.len() - needle.len() + 1)).rev() {
-            if needle == &haystack[i..i + needle.len()] {
-                return Some(i);
+    }
+
+    #[cfg(all(
+        not(miri),
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        feature = "std"
+    ))]
+    #[test]
+    fn reverse_every_isa_long() {
+        use super::{
+            testutil::naive_rfind, x86::force, FinderRev, SearcherRevKind,
+        };
+
+        let needles: &[&[u8]] = &[b"zq", b"zqx", b"0123456789abcdefzq"];
+        for &isa in force::ALL {
+            force::with_max_isa(isa, || {
+                for &needle in needles {
+                    let finder = FinderRev::new(needle);
+                    if let SearcherRevKind::TwoWay(_) = finder.searcher.kind {
+                        assert_eq!(force::Isa::Fallback, isa);
+                    }
+                    for len in needle.len()..100 {
+                        for pos in 0..=len - needle.len() {
+                            // A copy of the needle at `pos`, with a copy of
+                            // its prefix at the end of the haystack and
+                            // another copy of it at the start.
+                            let mut h = vec![b'.'; len];
+                            h[len - 2..].copy_from_slice(&needle[..2]);
+                            if pos >= needle.len() {
+                                h[..needle.len()].copy_from_slice(needle);
+                            }
+                            h[pos..pos + needle.len()].copy_from_slice(needle);
+                            assert_eq!(
+                                naive_rfind(&h, needle),
+                                finder.rfind(&h),
+                                "isa: {:?}, needle: {:?}, haystack: {:?}",
+                                isa,
+                                needle,
+                                h,
+                            );
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn rarest_byte_matches_prefilter_choice() {
+        use super::{rarebytes::RareNeedleBytes, rarest_byte};
+
+        for &needle in &["ab", "za", "foobar", "\x7f\x7fzz", "aaaa"] {
+            let needle = needle.as_bytes();
+            let rare1i = RareNeedleBytes::forward(needle).as_rare_usize().0;
+            assert_eq!(
+                Some((rare1i, needle[rare1i])),
+                rarest_byte(needle),
+                "needle: {:?}",
+                needle,
+            );
+        }
+        assert_eq!(None, rarest_byte(b""));
+    }
+
+    #[test]
+    fn best_needle() {
+        use super::best_needle;
+
+        // 'Z' is rarer than anything in "foo".
+        assert_eq!(Some(&b"fooZ"[..]), best_needle(&[b"foo", b"fooZ"]));
+        // Same rarest byte, so the longer needle wins.
+        assert_eq!(Some(&b"zab"[..]), best_needle(&[b"za", b"zab", b"zac"]));
+        // Empty needles are a last resort.
+        assert_eq!(Some(&b"e"[..]), best_needle(&[b"", b"e"]));
+        assert_eq!(Some(&b""[..]), best_needle(&[b"", b""]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_forward_from_iter() {
+        use super::{Finder, FinderBuilder};
+
+        testsimples::run_search_tests_fwd(|h, n| {
+            let finder = FinderBuilder::new()
+                .build_forward_from_iter(n.iter().cloned());
+            assert_eq!(n, finder.needle());
+            finder.find(h)
+        });
+        // The finder must not borrow from anything, including a temporary
+        // needle.
+        let finder: Finder<'static> =
+            FinderBuilder::new().build_forward_from_iter(b"bar".to_vec());
+        assert_eq!(Some(4), finder.find(b"foo bar"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn for_each_match_one_byte() {
+        use super::Finder;
+
+        // Dense and sparse matches spanning several blocks, and stopping at
+        // every match.
+        let haystack: Vec<u8> = (0..300usize)
+            .map(|i| if i % 3 == 0 || i > 200 { b'a' } else { b'b' })
+            .collect();
+        let expected: Vec<usize> = super::find_iter(&haystack, "a").collect();
+        for stop in 0..=expected.len() {
+            let mut found = vec![];
+            Finder::new("a").for_each_match(&haystack, |i| {
+                found.push(i);
+                found.len() <= stop
+            });
+            let want = core::cmp::min(stop + 1, expected.len());
+            assert_eq!(&expected[..want], &found[..]);
+        }
+        assert_eq!(expected.len(), Finder::new("a").count(&haystack));
+        assert_eq!(0, Finder::new("c").count(&haystack));
+    }
+
+    #[test]
+    fn reverse_prefilter_config() {
+        use super::{FinderBuilder, Prefilter};
+
+        // The rare bytes of this needle are rare enough for the reverse
+        // prefilter to be used by default.
+        let needle = "zqzq";
+        let finder = FinderBuilder::new().build_reverse(needle);
+        assert!(finder.searcher.prefn.is_some());
+        let finder = FinderBuilder::new().build_bidirectional(needle);
+        assert!(finder.rprefn.is_some());
+
+        let mut builder = FinderBuilder::new();
+        builder.prefilter(Prefilter::None);
+        let finder = builder.build_reverse(needle);
+        assert!(finder.searcher.prefn.is_none());
+        assert!(!finder.searcher.prefilter_state().is_effective());
+        assert_eq!(Some(4), finder.rfind("abzqzqzq"));
+        let finder = builder.build_bidirectional(needle);
+        assert!(finder.rprefn.is_none());
+        assert_eq!(Some(4), finder.rfind(b"abzqzqzq"));
+    }
+
+    #[test]
+    fn empty_needle_never_matches() {
+        use super::FinderBuilder;
+
+        let mut builder = FinderBuilder::new();
+        builder.empty_needle_matches(false);
+        let finder = builder.build_forward("");
+        assert_eq!(None, finder.find(b""));
+        assert_eq!(None, finder.find(b"abc"));
+        assert_eq!(None, finder.find_iter(b"abc").next());
+        assert_eq!(None, finder.find_in_two(b"ab", b"c"));
+        assert_eq!(0, finder.count(b"abc"));
+        assert_eq!(0, finder.count_overlapping(b"abc"));
+        assert_eq!(None, finder.find_nth(b"abc", 0));
+
+        let finder = builder.build_reverse("");
+        assert_eq!(None, finder.rfind(b"abc"));
+        assert_eq!(None, finder.rfind_iter(b"abc").next());
+        let finder = builder.build_bidirectional("");
+        assert_eq!(None, finder.find(b"abc"));
+        assert_eq!(None, finder.rfind(b"abc"));
+        assert_eq!(None, finder.find_nearest(b"abc", 1));
+        let finder = builder.build_masked("", "");
+        assert_eq!(None, finder.find_iter(b"abc").next());
+
+        // Non-empty needles are unaffected.
+        assert_eq!(Some(1), builder.build_forward("b").find(b"abc"));
+        assert_eq!(Some(1), builder.build_reverse("bc").rfind(b"abc"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn iter_match() {
+        use super::{Finder, FinderRev};
+
+        let haystack = b"abcabcab";
+        let finder = Finder::new("bc");
+        let matches: Vec<_> = finder.find_iter_match(haystack).collect();
+        let starts: Vec<usize> = matches.iter().map(|m| m.start()).collect();
+        assert_eq!(vec![1, 4], starts);
+        for m in &matches {
+            assert_eq!(2, m.len());
+            assert_eq!(b"bc", &haystack[m.range()]);
+        }
+
+        let finder = FinderRev::new("ab");
+        let starts: Vec<usize> =
+            finder.rfind_iter_match(haystack).map(|m| m.start()).collect();
+        assert_eq!(vec![6, 3, 0], starts);
+
+        // Empty matches are reported at every position.
+        let finder = Finder::new("");
+        let matches: Vec<_> = finder.find_iter_match(b"ab").collect();
+        assert_eq!(3, matches.len());
+        assert!(matches.iter().all(|m| m.is_empty() && m.range().is_empty()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn empty_finders() {
+        use super::{Finder, FinderRev};
+
+        const EMPTY: Finder<'static> = Finder::empty();
+        const EMPTY_REV: FinderRev<'static> = FinderRev::empty();
+        for &haystack in &["", "a", "abc"] {
+            let h = haystack.as_bytes();
+            let fwd = Finder::new("");
+            let rev = FinderRev::new("");
+            assert_eq!(fwd.find(h), EMPTY.find(h));
+            assert_eq!(fwd.find(h), Finder::default().find(h));
+            assert_eq!(
+                fwd.find_iter(h).collect::<Vec<_>>(),
+                EMPTY.find_iter(h).collect::<Vec<_>>(),
+            );
+            assert_eq!(rev.rfind(h), EMPTY_REV.rfind(h));
+            assert_eq!(rev.rfind(h), FinderRev::default().rfind(h));
+        }
+        assert_eq!(format!("{:?}", Finder::new("")), format!("{:?}", EMPTY));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn find_str_skips_split_chars() {
+        use super::{Finder, FinderRev};
+
+        // "é" is C3 A9, so each of these needles matches inside it. A needle
+        // that isn't valid UTF-8 can never match a whole substring of a
+        // string, so every one of its matches is skipped.
+        let haystack = "éaé";
+        for &needle in &[&b"\xC3"[..], b"\xA9a", b"\xA9"] {
+            assert!(Finder::new(needle).find(haystack.as_bytes()).is_some());
+            assert_eq!(None, Finder::new(needle).find_str(haystack));
+            assert_eq!(None, FinderRev::new(needle).rfind_str(haystack));
+        }
+
+        // An empty needle only matches between chars.
+        let empty: Vec<usize> = Finder::new("").find_str_iter("öx").collect();
+        assert_eq!(vec![0, 2, 3], empty);
+        assert_eq!(Some(3), FinderRev::new("").rfind_str("öx"));
+        assert_eq!(Some(0), FinderRev::new("").rfind_str(""));
+    }
+
+    #[test]
+    fn find_followed_by() {
+        use super::Finder;
+        use crate::ByteSet;
+
+        let digits = ByteSet::new(b"0123456789");
+        let finder = Finder::new("aa");
+        // The match at 0 is followed by 'a', but the overlapping one at 1 is
+        // followed by a digit.
+        assert_eq!(Some(1), finder.find_followed_by(b"aaa1", &digits, false));
+        assert_eq!(None, finder.find_followed_by(b"aaa", &digits, false));
+        assert_eq!(Some(1), finder.find_followed_by(b"aaa", &digits, true));
+        assert_eq!(None, finder.find_followed_by(b"", &digits, true));
+
+        let finder = Finder::new("");
+        assert_eq!(Some(1), finder.find_followed_by(b"a1", &digits, false));
+        assert_eq!(Some(2), finder.find_followed_by(b"ab", &digits, true));
+        assert_eq!(None, finder.find_followed_by(b"ab", &digits, false));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn find_aligned() {
+        use super::Finder;
+
+        let finder = Finder::new("ab");
+        let haystack = b"xabab.ab";
+        assert_eq!(Some(1), finder.find_aligned(haystack, 1, 0));
+        assert_eq!(Some(3), finder.find_aligned(haystack, 5, 3));
+        assert_eq!(Some(6), finder.find_aligned(haystack, 4, 2));
+        assert_eq!(None, finder.find_aligned(haystack, 4, 0));
+        assert_eq!(None, finder.find_aligned(b"", 1, 0));
+
+        // Large alignments check each conforming offset directly.
+        let mut haystack = vec![b'a'; 200];
+        haystack[64..66].copy_from_slice(b"ab");
+        haystack[130..132].copy_from_slice(b"ab");
+        haystack[192..194].copy_from_slice(b"ab");
+        assert_eq!(Some(64), finder.find_aligned(&haystack, 64, 0));
+        assert_eq!(Some(130), finder.find_aligned(&haystack, 64, 2));
+        assert_eq!(None, finder.find_aligned(&haystack, 64, 1));
+        assert_eq!(Some(192), finder.find_aligned(&haystack, 96, 0));
+        assert_eq!(None, finder.find_aligned(&haystack, 1000, 199));
+
+        let finder = Finder::new("");
+        assert_eq!(Some(2), finder.find_aligned(b"abc", 4, 2));
+        assert_eq!(None, finder.find_aligned(b"abc", 8, 4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn find_aligned_bad_offset() {
+        super::Finder::new("a").find_aligned(b"a", 2, 2);
+    }
+
+    #[test]
+    fn strategy_horspool() {
+        use super::{FinderBuilder, Strategy};
+
+        let mut builder = FinderBuilder::new();
+        builder.strategy(Strategy::Horspool);
+        run_search_tests_fwd(|h, n| builder.build_forward(n).find(h));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn overlapping() {
+        use super::FinderBuilder;
+
+        let mut builder = FinderBuilder::new();
+        builder.overlapping(true);
+        let fwd = |needle: &str, haystack: &str| {
+            builder
+                .build_forward(needle)
+                .find_iter(haystack.as_bytes())
+                .collect::<Vec<usize>>()
+        };
+        let rev = |needle: &str, haystack: &str| {
+            builder
+                .build_reverse(needle)
+                .rfind_iter(haystack.as_bytes())
+                .collect::<Vec<usize>>()
+        };
+        assert_eq!(vec![0, 2, 4], fwd("aba", "abababa"));
+        assert_eq!(vec![4, 2, 0], rev("aba", "abababa"));
+        assert_eq!(vec![0, 1, 2], fwd("a", "aaa"));
+        assert_eq!(vec![2, 1, 0], rev("a", "aaa"));
+        // An empty needle reports every position, just as it does without
+        // overlapping matches.
+        assert_eq!(vec![0, 1, 2], fwd("", "ab"));
+        assert_eq!(vec![2, 1, 0], rev("", "ab"));
+
+        let finder = builder.build_forward("aa");
+        assert_eq!(3, finder.count(b"aaaa"));
+        assert_eq!(Some(2), finder.find_nth(b"aaaa", 2));
+        // These never report overlapping matches.
+        assert_eq!(b"xxx".to_vec(), finder.replace(b"aaaaaa", b"x"));
+        let tokens: Vec<_> = finder.tokenize(b"aaa").collect();
+        assert_eq!(vec![(&b""[..], Some(0)), (&b"a"[..], None)], tokens);
+
+        let bi = builder.build_bidirectional("aa");
+        assert_eq!(vec![0, 1, 2], bi.find_iter(b"aaaa").collect::<Vec<_>>());
+        assert_eq!(vec![2, 1, 0], bi.rfind_iter(b"aaaa").collect::<Vec<_>>());
+
+        let masked = builder.build_masked("a?a", &[0xFF, 0, 0xFF]);
+        assert_eq!(vec![0, 2], masked.find_iter(b"abaca").collect::<Vec<_>>());
+
+        let finder = builder.build_forward("aa");
+        let matches: Vec<usize> =
+            finder.find_iter_filter(b"aaaa", |_| true).collect();
+        assert_eq!(vec![0, 1, 2], matches);
+        let mut cursor = super::Cursor::new();
+        assert_eq!(Some(0), finder.find_from_cursor(b"aaaa", &mut cursor));
+        assert_eq!(1, cursor.position());
+        assert_eq!(Some(1), finder.find_from_cursor(b"aaaa", &mut cursor));
+        assert_eq!(Some(2), finder.find_from_cursor(b"aaaa", &mut cursor));
+        assert_eq!(None, finder.find_from_cursor(b"aaaa", &mut cursor));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_forward_cow() {
+        use super::FinderBuilder;
+        use std::borrow::Cow;
+
+        let needle = b"foo".to_vec();
+        let ptr = needle.as_ptr();
+        let finder =
+            FinderBuilder::new().build_forward_cow(Cow::Owned(needle));
+        // An owned needle without spare capacity is moved in as is.
+        assert_eq!(ptr, finder.needle().as_ptr());
+        assert_eq!(Some(4), finder.find(b"bar foo"));
+
+        let needle = b"bar";
+        let finder =
+            FinderBuilder::new().build_forward_cow(Cow::Borrowed(&needle[..]));
+        assert_eq!(needle.as_ptr(), finder.needle().as_ptr());
+        assert_eq!(Some(0), finder.find(b"bar foo"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn set_needle() {
+        use super::FinderBuilder;
+
+        let mut finder = FinderBuilder::new()
+            .empty_needle_matches(false)
+            .build_forward("foo")
+            .into_owned();
+        let ptr = finder.needle().as_ptr();
+        // A needle that fits in the current allocation reuses it, whether
+        // or not it has the same length.
+        finder.set_needle(b"bar");
+        assert_eq!(ptr, finder.needle().as_ptr());
+        assert_eq!(Some(4), finder.find(b"foo bar"));
+        // The searcher also changes to suit the needle.
+        finder.set_needle(b"z");
+        assert_eq!(ptr, finder.needle().as_ptr());
+        assert_eq!(Some(2), finder.find(b"xyz"));
+        finder.set_needle(b"ab");
+        assert_eq!(ptr, finder.needle().as_ptr());
+        assert_eq!(Some(1), finder.find(b"_ab"));
+        finder.set_needle(b"0123456789abcdefghijklmnopqrstuvwxyz");
+        assert_eq!(
+            Some(1),
+            finder.find(b"_0123456789abcdefghijklmnopqrstuvwxyz")
+        );
+        // The configuration is kept.
+        finder.set_needle(b"");
+        assert_eq!(None, finder.find(b"abc"));
+
+        // A borrowed finder becomes an owned one.
+        let needle = b"abc".to_vec();
+        let mut finder = super::Finder::new(&needle);
+        finder.set_needle(b"abc");
+        assert_ne!(needle.as_ptr(), finder.needle().as_ptr());
+        assert_eq!(Some(1), finder.find(b"_abc"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn find_iter_based() {
+        use super::Finder;
+
+        let offsets = |needle: &str, haystack: &str, base: u64| {
+            Finder::new(needle)
+                .find_iter_based(haystack.as_bytes(), base)
+                .collect::<Vec<u64>>()
+        };
+        assert_eq!(vec![10, 18], offsets("foo", "foo bar foo", 10));
+        assert_eq!(vec![7, 8, 9], offsets("", "ab", 7));
+        assert_eq!(
+            vec![core::u64::MAX - 1],
+            offsets("b", "ab", core::u64::MAX - 2),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn find_iter_based_overflow() {
+        let finder = super::Finder::new("b");
+        finder.find_iter_based(b"ab", core::u64::MAX).for_each(drop);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn find_end_iter() {
+        use super::Finder;
+
+        let ends = |needle: &str, haystack: &str| {
+            Finder::new(needle)
+                .find_end_iter(haystack.as_bytes())
+                .collect::<Vec<usize>>()
+        };
+        assert_eq!(vec![3, 7], ends("foo", "foo foo"));
+        // Matches don't overlap, so each search resumes at the last end.
+        assert_eq!(vec![2, 4], ends("aa", "aaaaa"));
+        assert_eq!(vec![0, 1, 2], ends("", "ab"));
+        assert_eq!(vec![0], ends("", ""));
+        assert_eq!(Vec::<usize>::new(), ends("ab", "ba"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn find_merged_ranges() {
+        use super::Finder;
+
+        let ranges = |needle: &str, haystack: &str| {
+            Finder::new(needle)
+                .find_merged_ranges(haystack.as_bytes())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![0..4], ranges("aa", "aaaa"));
+        assert_eq!(vec![0..4, 5..7], ranges("ab", "abab ab"));
+        assert_eq!(vec![1..3, 4..6], ranges("ab", "xabxab"));
+        assert_eq!(Vec::<core::ops::Range<usize>>::new(), ranges("ab", "ba"));
+        assert_eq!(vec![0..0, 1..1, 2..2], ranges("", "ab"));
+    }
+
+    #[test]
+    fn iter_remaining() {
+        let mut it = super::find_iter(b"abcabcab", "bc");
+        assert_eq!(b"abcabcab", it.remaining());
+        assert_eq!(Some(1), it.next());
+        assert_eq!(b"abcab", it.remaining());
+        assert_eq!(Some(4), it.next());
+        assert_eq!(b"ab", it.remaining());
+        assert_eq!(None, it.next());
+        assert_eq!(b"ab", it.remaining());
+
+        let mut it = super::rfind_iter(b"abcabcab", "bc");
+        assert_eq!(b"abcabcab", it.remaining());
+        assert_eq!(Some(4), it.next());
+        assert_eq!(b"abca", it.remaining());
+        assert_eq!(Some(1), it.next());
+        assert_eq!(b"a", it.remaining());
+        assert_eq!(None, it.next());
+        assert_eq!(b"a", it.remaining());
+
+        // An empty needle matches at both ends, after which nothing is left.
+        let mut it = super::find_iter(b"ab", "");
+        assert_eq!(3, it.by_ref().count());
+        assert_eq!(b"", it.remaining());
+        let mut it = super::rfind_iter(b"ab", "");
+        assert_eq!(3, it.by_ref().count());
+        assert_eq!(b"", it.remaining());
+    }
+
+    #[test]
+    fn find_nz() {
+        let haystack = b"foo bar foo";
+        for &needle in &["", "foo", "bar", "o", "quux"] {
+            let (h, n) = (&haystack[..], needle.as_bytes());
+            let plus_one = |i: usize| i + 1;
+            assert_eq!(
+                super::find(h, n).map(plus_one),
+                super::find_nz(h, n).map(|i| i.get()),
+            );
+            assert_eq!(
+                super::rfind(h, n).map(plus_one),
+                super::rfind_nz(h, n).map(|i| i.get()),
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn rare_byte_prepass() {
+        use super::{Finder, FinderBuilder, Prefilter};
+
+        // Long enough to use Two-Way, with 'Z' as its rarest byte.
+        let needle = b"abcdefghijklmnopqrstuvwxyzabcdefghijklmZ";
+        let finders = [
+            Finder::new(needle),
+            FinderBuilder::new()
+                .prefilter(Prefilter::None)
+                .build_forward(needle),
+        ];
+        for finder in finders.iter() {
+            let mut haystack =
+                vec![b'a'; 8 * super::RARE_PREPASS_MIN_HAYSTACK_LEN];
+            assert_eq!(None, finder.find(&haystack));
+
+            // The rare byte occurs, but not as part of a match.
+            let len = haystack.len();
+            haystack[len / 2] = b'Z';
+            assert_eq!(None, finder.find(&haystack));
+
+            // Matches both before and after that occurrence.
+            for &start in &[0, 1, len / 4, len - needle.len()] {
+                let mut haystack = haystack.clone();
+                haystack[start..start + needle.len()].copy_from_slice(needle);
+                assert_eq!(Some(start), finder.find(&haystack));
             }
         }
-        None
     }
-}
 
-/// This module defines some hand-written "simple" substring tests. It
-/// also provides routines for easily running them on any substring search
-/// implementation.
-#[cfg(test)]
-mod testsimples {
-    define_memmem_simple_tests!(super::find, super::rfind);
+    #[test]
+    fn find_in_two_every_split() {
+        let haystack = b"xxabcabdyy";
+        for &(needle, expected) in &[
+            ("abd", Some(5)),
+            ("ab", Some(2)),
+            ("bdy", Some(6)),
+            ("xxabcabdyy", Some(0)),
+            ("", Some(0)),
+            ("abx", None),
+        ] {
+            let finder = super::Finder::new(needle);
+            for split in 0..=haystack.len() {
+                let (first, second) = haystack.split_at(split);
+                assert_eq!(
+                    expected,
+                    finder.find_in_two(first, second),
+                    "needle: {:?}, split: {}",
+                    needle,
+                    split,
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn find_candidate() {
+        // This needle is too long for the generic SIMD searcher, so it uses
+        // Two-Way with the prefilter, which only looks at two rare bytes.
+        let needle = "zq this needle is much too long to be vectorized xq";
+        let finder = super::Finder::new(needle);
+        let mut haystack = vec![b' '; 200];
+        haystack[100..100 + needle.len()].copy_from_slice(needle.as_bytes());
+        let found = finder.find(&haystack);
+        assert_eq!(Some(100), found);
+        let candidate = finder.find_candidate(&haystack).unwrap();
+        assert!(candidate <= 100);
+        // Without the needle, there may be candidates but never a match.
+        let mut decoy = haystack.clone();
+        decoy[120] = b'!';
+        assert_eq!(None, finder.find(&decoy));
+        assert_eq!(Some(candidate), finder.find_candidate(&decoy));
+        assert_eq!(None, finder.find_candidate(&haystack[..needle.len() - 1]));
+
+        let finder = super::FinderBuilder::new()
+            .prefilter(super::Prefilter::None)
+            .build_forward(needle);
+        assert_eq!(found, finder.find_candidate(&haystack));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn debug_shows_needle() {
+        use super::{Finder, FinderBuilder, FinderRev, Prefilter};
+
+        assert_eq!(
+            r#"Finder { needle: "foo", prefilter: Auto }"#,
+            format!("{:?}", Finder::new("foo")),
+        );
+        let finder = FinderBuilder::new()
+            .prefilter(Prefilter::None)
+            .build_forward(b"a'\"\\\n\x00\xFF");
+        assert_eq!(
+            r#"Finder { needle: "a'\"\\\n\x00\xff", prefilter: None }"#,
+            format!("{:?}", finder),
+        );
+        assert_eq!(
+            r#"FinderRev { needle: "foo" }"#,
+            format!("{:?}", FinderRev::new("foo")),
+        );
+    }
+
+    #[cfg(feature = "instrumentation")]
+    #[test]
+    fn find_instrumented() {
+        use super::{Finder, FinderBuilder, Prefilter};
+
+        let tests: &[(&str, &str)] = &[
+            ("", ""),
+            ("", "abc"),
+            ("a", "bcd"),
+            ("foo", "foo"),
+            ("foo", "fo"),
+            ("foobar", "fofoofoobfoobafoobar"),
+            ("Sherlock", "Sherloc Sherlo Sherlock"),
+            ("zzz", "zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz"),
+        ];
+        for &(needle, haystack) in tests {
+            let (needle, haystack) = (needle.as_bytes(), haystack.as_bytes());
+            let finder = Finder::new(needle);
+            let (found, stats) = finder.find_instrumented(haystack);
+            assert_eq!(finder.find(haystack), found, "needle: {:?}", needle);
+            assert!(stats.candidates() >= stats.confirmations());
+            assert!(stats.confirmations() >= stats.rejected());
+            assert!(stats.bytes_scanned() <= haystack.len());
+
+            let finder = FinderBuilder::new()
+                .prefilter(Prefilter::None)
+                .build_forward(needle);
+            let (found, stats) = finder.find_instrumented(haystack);
+            assert_eq!(finder.find(haystack), found, "needle: {:?}", needle);
+            assert_eq!(super::SearchStats::default(), stats);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn max_simd_needle_len() {
+        use super::FinderBuilder;
+
+        let needle: Vec<u8> = (0..100u8).map(|b| b'a' + b % 26).collect();
+        let mut haystack = vec![b'z'; 300];
+        haystack.extend_from_slice(&needle);
+        haystack.extend(vec![b'y'; 300]);
+        for &len in &[0, 1, 2, 8, 32, 64, 99, 100, 255, 1000] {
+            let finder = FinderBuilder::new()
+                .max_simd_needle_len(len)
+                .build_forward(&needle);
+            assert_eq!(Some(300), finder.find(&haystack), "len: {}", len);
+            assert_eq!(None, finder.find(&haystack[..399]), "len: {}", len);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    #[test]
+    fn max_simd_needle_repetitions() {
+        use super::{FinderBuilder, SearcherKind, SearcherRevKind};
+
+        let mut builder = FinderBuilder::new();
+        builder.max_simd_needle_repetitions(2);
+        // Only needles that repeat more than twice use Two-Way.
+        for &(needle, two_way) in &[
+            ("aaaaaaaa", true),
+            ("abababab", true),
+            ("abcabcab", false),
+            ("abcdabcd", false),
+            ("zq", false),
+        ] {
+            let fwd = builder.build_forward(needle);
+            let rev = builder.build_reverse(needle);
+            let fwd_two_way = match fwd.searcher.kind {
+                SearcherKind::TwoWay(_) => true,
+                _ => false,
+            };
+            let rev_two_way = match rev.searcher.kind {
+                SearcherRevKind::TwoWay(_) => true,
+                _ => false,
+            };
+            // Without SSE2 or AVX2, every needle uses Two-Way.
+            if super::x86::is_sse2_enabled() {
+                assert_eq!(two_way, fwd_two_way, "needle: {:?}", needle);
+                assert_eq!(two_way, rev_two_way, "needle: {:?}", needle);
+            }
+            let haystack = format!("{}{}", "xy".repeat(40), needle);
+            assert_eq!(Some(80), fwd.find(haystack.as_bytes()));
+            assert_eq!(Some(80), rev.rfind(haystack.as_bytes()));
+        }
+    }
 
     /// Each test is a (needle, haystack, expected_fwd, expected_rev) tuple.
     type SearchTest =
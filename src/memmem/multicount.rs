@@ -0,0 +1,184 @@
+/*
+This module implements counting the occurrences of several needles at once,
+in a single pass over the haystack.
+
+The approach is about as simple as it gets. For each needle, we pick its
+rarest byte (the same one its prefilter would use). We then search the
+haystack for any of those bytes, and each time one is found, we check every
+needle whose rare byte it is for a match at the corresponding position. Since
+each needle is always anchored at the same offset relative to its rare byte,
+candidates for any one needle are visited in order, which is what lets us
+count non-overlapping matches with a single pass.
+
+This is meant as a stepping stone toward proper multi-pattern search. In
+particular, there is nothing clever done when many needles share the same
+rare byte: each of them is checked in turn.
+*/
+
+use crate::memmem::{rarebytes::RareNeedleBytes, util};
+
+/// A counter of non-overlapping occurrences of several needles.
+///
+/// This counts the occurrences of every needle in a single pass over a
+/// haystack, which is typically much faster than searching the haystack once
+/// for each needle. The count reported for each needle is the same as what
+/// `Finder::new(needle).find_iter(haystack).count()` would report.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Complexity
+///
+/// Unlike [`Finder`](crate::memmem::Finder), this does not guarantee linear
+/// time. Every occurrence of a needle's rare byte causes that needle to be
+/// compared with the haystack, so in the worst case, this runs in time
+/// proportional to the length of the haystack multiplied by the total length
+/// of all needles.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::MultiCounter;
+///
+/// let counter = MultiCounter::new(["foo", "bar", "quux"]);
+/// let counts = counter.count(b"foo bar foo baz foo");
+/// assert_eq!(counts, vec![3, 1, 0]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultiCounter {
+    /// Every needle, in the order given.
+    needles: Vec<Needle>,
+    /// The distinct rare bytes of all non-empty needles. This is the set of
+    /// bytes searched for in the haystack.
+    rare: Vec<u8>,
+    /// The indices of the needles whose rare byte is the one given by the
+    /// index into this table. This always has 256 entries.
+    by_rare: Vec<Vec<usize>>,
+}
+
+#[derive(Clone, Debug)]
+struct Needle {
+    bytes: Vec<u8>,
+    /// The offset of the rare byte in this needle. This is meaningless for
+    /// an empty needle.
+    rarei: usize,
+}
+
+impl MultiCounter {
+    /// Create a new counter for the given needles.
+    ///
+    /// The counts reported by [`MultiCounter::count`] are in the same order
+    /// as the needles given here. Duplicate needles are permitted, and are
+    /// each counted independently.
+    pub fn new<I, B>(needles: I) -> MultiCounter
+    where
+        I: IntoIterator<Item = B>,
+        B: AsRef<[u8]>,
+    {
+        let mut counter = MultiCounter {
+            needles: vec![],
+            rare: vec![],
+            by_rare: vec![vec![]; 256],
+        };
+        for (i, needle) in needles.into_iter().enumerate() {
+            let bytes = needle.as_ref().to_vec();
+            let rarei = RareNeedleBytes::forward(&bytes).as_rare_usize().0;
+            if let Some(&rare) = bytes.get(rarei) {
+                let ids = &mut counter.by_rare[rare as usize];
+                if ids.is_empty() {
+                    counter.rare.push(rare);
+                }
+                ids.push(i);
+            }
+            counter.needles.push(Needle { bytes, rarei });
+        }
+        counter
+    }
+
+    /// Returns the number of non-overlapping occurrences of each needle in
+    /// the given haystack.
+    ///
+    /// The counts are returned in the same order that the needles were given
+    /// to [`MultiCounter::new`]. An empty needle matches at every position in
+    /// the haystack, including the end, just like `Finder::find_iter`.
+    pub fn count(&self, haystack: &[u8]) -> Vec<usize> {
+        let mut counts = vec![0; self.needles.len()];
+        // The smallest offset at which the next match of each needle may
+        // start, so that matches of the same needle don't overlap.
+        let mut next = vec![0; self.needles.len()];
+        for (i, needle) in self.needles.iter().enumerate() {
+            if needle.bytes.is_empty() {
+                counts[i] = haystack.len() + 1;
+            }
+        }
+
+        let mut pos = 0;
+        while let Some(i) = crate::find_byte_in(&haystack[pos..], &self.rare) {
+            let rarepos = pos + i;
+            for &id in &self.by_rare[haystack[rarepos] as usize] {
+                let needle = &self.needles[id];
+                let start = match rarepos.checked_sub(needle.rarei) {
+                    None => continue,
+                    Some(start) => start,
+                };
+                if start >= next[id]
+                    && util::is_prefix(&haystack[start..], &needle.bytes)
+                {
+                    counts[id] += 1;
+                    next[id] = start + needle.bytes.len();
+                }
+            }
+            pos = rarepos + 1;
+        }
+        counts
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::MultiCounter;
+    use crate::memmem::find_iter;
+
+    #[test]
+    fn overlapping_needles() {
+        let counter = MultiCounter::new(["aa", "a", "aaa", "", "b"].iter());
+        assert_eq!(counter.count(b"aaaaa"), vec![2, 5, 1, 6, 0]);
+        assert_eq!(counter.count(b""), vec![0, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn shared_rare_byte() {
+        // All of these needles should pick 'z' as their rare byte, at
+        // different offsets.
+        let counter =
+            MultiCounter::new(["za", "az", "zz", "aza", "za"].iter());
+        assert_eq!(counter.count(b"azazzaza"), vec![3, 3, 1, 2, 3]);
+    }
+
+    quickcheck! {
+        fn qc_matches_find_iter(
+            haystack: Vec<u8>,
+            needles: Vec<(u8, u8)>
+        ) -> bool {
+            // Derive needles from the haystack so that matches are likely.
+            let needles: Vec<&[u8]> = needles
+                .iter()
+                .map(|&(start, len)| {
+                    let start =
+                        core::cmp::min(start as usize, haystack.len());
+                    let end = core::cmp::min(
+                        start + (len as usize % 8),
+                        haystack.len(),
+                    );
+                    &haystack[start..end]
+                })
+                .collect();
+            let expected: Vec<usize> = needles
+                .iter()
+                .map(|needle| find_iter(&haystack, needle).count())
+                .collect();
+            MultiCounter::new(&needles).count(&haystack) == expected
+        }
+    }
+}
@@ -0,0 +1,328 @@
+/*
+This module implements searching for the leftmost occurrence of any of
+several needles.
+
+It works much like the multi-needle counter in the `multicount` module. For
+each needle, we pick its rarest byte (the same one its prefilter would use),
+and search the haystack for any of those bytes. Each time one is found, every
+needle whose rare byte it is gets checked for a match at the corresponding
+position.
+
+The wrinkle is that we want the leftmost match, but candidates are visited in
+the order of their rare bytes, not their starting positions. A needle whose
+rare byte is near its end may start before a match that was already found for
+a needle whose rare byte is near its start. So once a match is found, we keep
+looking at rare bytes until none of them could belong to a match starting at
+or before it.
+
+As with the counter, this is meant for a handful of short needles. Nothing
+clever is done when many needles share the same rare byte, and there is no
+automaton. For many needles, use the `aho-corasick` crate instead.
+*/
+
+use crate::memmem::{rarebytes::RareNeedleBytes, util};
+
+/// A searcher for the leftmost occurrence of any of several needles.
+///
+/// When several needles match at the same position, the one given first to
+/// [`MultiFinder::new`] is reported. (This is sometimes called "leftmost
+/// first" match semantics.)
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Complexity
+///
+/// Unlike [`Finder`](crate::memmem::Finder), this does not guarantee linear
+/// time. Every occurrence of a needle's rare byte causes that needle to be
+/// compared with the haystack, so in the worst case, this runs in time
+/// proportional to the length of the haystack multiplied by the total length
+/// of all needles.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::MultiFinder;
+///
+/// let finder = MultiFinder::new(["quux", "bar", "foo"].iter());
+/// assert_eq!(Some((4, 1)), finder.find(b"baz bar foo"));
+///
+/// let matches: Vec<(usize, usize)> =
+///     finder.find_iter(b"foo bar quux").collect();
+/// assert_eq!(vec![(0, 2), (4, 1), (8, 0)], matches);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultiFinder {
+    /// Every needle, in the order given.
+    needles: Vec<Needle>,
+    /// The distinct rare bytes of all non-empty needles. This is the set of
+    /// bytes searched for in the haystack.
+    rare: Vec<u8>,
+    /// The indices of the needles whose rare byte is the one given by the
+    /// index into this table. This always has 256 entries.
+    by_rare: Vec<Vec<usize>>,
+    /// The largest offset of a rare byte in any needle. A rare byte found at
+    /// `i` can only belong to a match starting at or after `i - max_rarei`.
+    max_rarei: usize,
+    /// The index of the first empty needle, if there is one. An empty needle
+    /// matches at every position.
+    empty: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct Needle {
+    bytes: Vec<u8>,
+    /// The offset of the rare byte in this needle. This is meaningless for
+    /// an empty needle.
+    rarei: usize,
+}
+
+impl MultiFinder {
+    /// Create a new searcher for the given needles.
+    ///
+    /// Matches are reported along with the index of the needle that matched,
+    /// in the order given here.
+    pub fn new<I, B>(needles: I) -> MultiFinder
+    where
+        I: IntoIterator<Item = B>,
+        B: AsRef<[u8]>,
+    {
+        let mut finder = MultiFinder {
+            needles: vec![],
+            rare: vec![],
+            by_rare: vec![vec![]; 256],
+            max_rarei: 0,
+            empty: None,
+        };
+        for (i, needle) in needles.into_iter().enumerate() {
+            let bytes = needle.as_ref().to_vec();
+            let rarei = RareNeedleBytes::forward(&bytes).as_rare_usize().0;
+            match bytes.get(rarei) {
+                None => {
+                    if finder.empty.is_none() {
+                        finder.empty = Some(i);
+                    }
+                }
+                Some(&rare) => {
+                    let ids = &mut finder.by_rare[rare as usize];
+                    if ids.is_empty() {
+                        finder.rare.push(rare);
+                    }
+                    ids.push(i);
+                    finder.max_rarei = core::cmp::max(finder.max_rarei, rarei);
+                }
+            }
+            finder.needles.push(Needle { bytes, rarei });
+        }
+        finder
+    }
+
+    /// Returns the leftmost occurrence of any needle in the given haystack.
+    ///
+    /// The match is returned as a pair of the offset at which it starts and
+    /// the index of the needle that matched.
+    pub fn find(&self, haystack: &[u8]) -> Option<(usize, usize)> {
+        self.find_at(haystack, 0)
+    }
+
+    /// Returns an iterator over all non-overlapping leftmost occurrences of
+    /// any needle in the given haystack.
+    ///
+    /// Each item is a pair of the offset at which a match starts and the
+    /// index of the needle that matched. Searching resumes at the end of each
+    /// match.
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> MultiFindIter<'h, 'a> {
+        MultiFindIter { haystack, finder: self, pos: 0 }
+    }
+
+    /// Returns the number of needles in this searcher.
+    pub fn len(&self) -> usize {
+        self.needles.len()
+    }
+
+    /// Returns true if this searcher has no needles. Such a searcher never
+    /// reports a match.
+    pub fn is_empty(&self) -> bool {
+        self.needles.is_empty()
+    }
+
+    /// Returns the needle at the given index, as given to
+    /// [`MultiFinder::new`].
+    ///
+    /// # Panics
+    ///
+    /// This panics when `index >= self.len()`.
+    pub fn needle(&self, index: usize) -> &[u8] {
+        &self.needles[index].bytes
+    }
+
+    /// Returns the leftmost match starting at or after `pos`.
+    fn find_at(&self, haystack: &[u8], pos: usize) -> Option<(usize, usize)> {
+        if pos > haystack.len() {
+            return None;
+        }
+        if let Some(empty) = self.empty {
+            // An empty needle matches at `pos`, so only a needle given before
+            // it that also matches at `pos` can take precedence.
+            let id = self.needles[..empty]
+                .iter()
+                .position(|n| util::is_prefix(&haystack[pos..], &n.bytes))
+                .unwrap_or(empty);
+            return Some((pos, id));
+        }
+
+        let mut best: Option<(usize, usize)> = None;
+        let mut at = pos;
+        while let Some(i) = crate::find_byte_in(&haystack[at..], &self.rare) {
+            let rarepos = at + i;
+            if let Some((start, _)) = best {
+                // Every candidate from here on starts after the best match.
+                if rarepos > start + self.max_rarei {
+                    break;
+                }
+            }
+            for &id in &self.by_rare[haystack[rarepos] as usize] {
+                let needle = &self.needles[id];
+                let start = match rarepos.checked_sub(needle.rarei) {
+                    Some(start) if start >= pos => start,
+                    _ => continue,
+                };
+                let is_better = match best {
+                    None => true,
+                    Some((bstart, bid)) => {
+                        start < bstart || (start == bstart && id < bid)
+                    }
+                };
+                if is_better
+                    && util::is_prefix(&haystack[start..], &needle.bytes)
+                {
+                    best = Some((start, id));
+                }
+            }
+            at = rarepos + 1;
+        }
+        best
+    }
+}
+
+/// An iterator over non-overlapping matches of a [`MultiFinder`].
+///
+/// Each item is a pair of the offset at which a match starts and the index
+/// of the needle that matched.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// searcher.
+#[derive(Debug)]
+pub struct MultiFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    finder: &'n MultiFinder,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for MultiFindIter<'h, 'n> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        match self.finder.find_at(self.haystack, self.pos) {
+            None => {
+                self.pos = self.haystack.len() + 1;
+                None
+            }
+            Some((start, id)) => {
+                // An empty needle matches everywhere, so make sure we make
+                // progress.
+                let len = self.finder.needle(id).len();
+                self.pos = start + core::cmp::max(1, len);
+                Some((start, id))
+            }
+        }
+    }
+}
+
+impl<'h, 'n> core::iter::FusedIterator for MultiFindIter<'h, 'n> {}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::MultiFinder;
+
+    /// Naively find the leftmost-first match starting at or after `pos`.
+    fn naive_find_at(
+        haystack: &[u8],
+        needles: &[&[u8]],
+        pos: usize,
+    ) -> Option<(usize, usize)> {
+        (pos..=haystack.len()).find_map(|start| {
+            needles
+                .iter()
+                .position(|n| haystack[start..].starts_with(n))
+                .map(|id| (start, id))
+        })
+    }
+
+    fn naive_find_iter(
+        haystack: &[u8],
+        needles: &[&[u8]],
+    ) -> Vec<(usize, usize)> {
+        let mut found = vec![];
+        let mut pos = 0;
+        while let Some((start, id)) = naive_find_at(haystack, needles, pos) {
+            found.push((start, id));
+            pos = start + core::cmp::max(1, needles[id].len());
+        }
+        found
+    }
+
+    #[test]
+    fn leftmost_first() {
+        // 'z' is the rare byte of both needles, but the second one starts
+        // earlier even though its 'z' is seen later.
+        let finder = MultiFinder::new(["za", "abcz"].iter());
+        assert_eq!(Some((1, 1)), finder.find(b"xabczaa"));
+
+        // Ties go to the needle given first.
+        let finder = MultiFinder::new(["ab", "abc", "a"].iter());
+        assert_eq!(Some((1, 0)), finder.find(b"xabc"));
+        let finder = MultiFinder::new(["abc", "ab", "a"].iter());
+        assert_eq!(Some((1, 0)), finder.find(b"xabc"));
+    }
+
+    #[test]
+    fn empty_needles() {
+        let finder = MultiFinder::new(["ab", ""].iter());
+        let found: Vec<(usize, usize)> = finder.find_iter(b"xab").collect();
+        assert_eq!(vec![(0, 1), (1, 0), (3, 1)], found);
+
+        let finder = MultiFinder::new(Vec::<&str>::new());
+        assert!(finder.is_empty());
+        assert_eq!(None, finder.find(b"abc"));
+    }
+
+    quickcheck! {
+        fn qc_matches_naive(
+            haystack: Vec<u8>,
+            needles: Vec<(u8, u8)>
+        ) -> bool {
+            // Derive needles from the haystack so that matches are likely.
+            let needles: Vec<&[u8]> = needles
+                .iter()
+                .take(8)
+                .map(|&(start, len)| {
+                    let start =
+                        core::cmp::min(start as usize, haystack.len());
+                    let end = core::cmp::min(
+                        start + (len as usize % 8),
+                        haystack.len(),
+                    );
+                    &haystack[start..end]
+                })
+                .collect();
+            let found: Vec<(usize, usize)> =
+                MultiFinder::new(&needles).find_iter(&haystack).collect();
+            found == naive_find_iter(&haystack, &needles)
+        }
+    }
+}
@@ -0,0 +1,197 @@
+/*
+This module implements an "adaptive" variant of the fallback prefilter. It
+works in exactly the same way (memchr on the rarest byte, followed by a guard
+check on the second rarest byte), except for what happens when the prefilter
+is deemed ineffective.
+
+Namely, the fallback prefilter picks its rare bytes using a background
+frequency distribution. When that distribution is a poor fit for the haystack
+(say, a byte that is usually rare is everywhere in the haystack), the
+prefilter reports lots of false positives and is eventually disabled. Instead
+of giving up right away, this prefilter counts the bytes in a sample of the
+haystack, re-picks its rare bytes based on those counts and then gives itself
+one more chance. If it's still ineffective with its new rare bytes, then it
+becomes inert like any other prefilter.
+
+The re-picked rare bytes are stored in the prefilter state, which means they
+persist across calls for the same search (e.g., in an iterator), but are not
+shared between different searches.
+*/
+
+use crate::memmem::{
+    prefilter::{PrefilterFnTy, PrefilterState},
+    rarebytes::{sample_byte_density, RareNeedleBytes},
+    NeedleInfo,
+};
+
+// Check that the functions below satisfy the Prefilter function type.
+const _: PrefilterFnTy = find;
+
+/// The maximum number of haystack bytes to sample when re-picking rare bytes.
+///
+/// The sample starts at the position where the prefilter was deemed
+/// ineffective, so it reflects the part of the haystack still left to search.
+/// The same number of bytes is sampled at the start of the haystack, to check
+/// whether the rarest byte is actually rare before relying on it.
+const SAMPLE_LEN: usize = 4096;
+
+/// The density above which the rarest byte found in the initial sample is
+/// considered common, which causes the rare bytes to be re-picked right away.
+///
+/// A byte this common would produce a candidate at least every
+/// `MIN_SKIP_BYTES` bytes on average, which is exactly the point at which
+/// the prefilter would eventually be deemed ineffective anyway. Re-picking
+/// up front avoids running the prefilter with a bad byte until then.
+const MAX_RARE_DENSITY: f32 = 1.0 / PrefilterState::MIN_SKIP_BYTES as f32;
+
+/// Look for a possible occurrence of needle. The position returned
+/// corresponds to the beginning of the occurrence, if one exists.
+///
+/// Callers may assume that this never returns false negatives (i.e., it
+/// never misses an actual occurrence), but must check that the returned
+/// position corresponds to a match. That is, it can return false
+/// positives.
+///
+/// This should only be used for forward searching.
+pub(crate) fn find(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    let mut i = 0;
+    let mut rarebytes = prestate.rarebytes(ninfo);
+    if prestate.is_sample_requested() {
+        // Sampling costs about as much as searching the sample, so it's only
+        // worth it when there's plenty of haystack left to search.
+        let common = haystack.len() >= 2 * SAMPLE_LEN && {
+            let (rare1, _) = rarebytes.as_rare_bytes(needle);
+            sample_byte_density(haystack, rare1, SAMPLE_LEN) > MAX_RARE_DENSITY
+        };
+        prestate.set_sampled(common);
+    }
+    while prestate.is_effective() {
+        if prestate.is_adapt_requested() {
+            let end = core::cmp::min(haystack.len(), i + SAMPLE_LEN);
+            rarebytes = RareNeedleBytes::observed(needle, &haystack[i..end]);
+            prestate.set_adapted(rarebytes);
+        }
+        let (rare1i, rare2i) = rarebytes.as_rare_usize();
+        let (rare1, rare2) = rarebytes.as_rare_bytes(needle);
+
+        let found = crate::memchr(rare1, &haystack[i..])?;
+        prestate.update(found);
+        i += found;
+
+        // If we can't align our first match with the haystack, then a
+        // match is impossible.
+        if i < rare1i {
+            i += 1;
+            continue;
+        }
+
+        // Align our rare2 byte with the haystack. A mismatch means that
+        // a match is impossible.
+        let aligned_rare2i = i - rare1i + rare2i;
+        if haystack.get(aligned_rare2i) != Some(&rare2) {
+            i += 1;
+            continue;
+        }
+
+        // We've done what we can. There might be a match here.
+        return Some(i - rare1i);
+    }
+    // The only way we get here is if we believe our skipping heuristic
+    // has become ineffective, even after re-picking our rare bytes. We're
+    // allowed to return false positives, so return the position at which we
+    // advanced to, aligned to the haystack.
+    let (rare1i, _) = rarebytes.as_rare_usize();
+    Some(i.saturating_sub(rare1i))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adapts_to_haystack() {
+        // 'Q' is believed to be rarer than 'x' and 'e', but in this haystack,
+        // it's everywhere. And it's the only byte in the haystack that
+        // precedes the match.
+        let needle = b"Qxxxxxxe";
+        let mut haystack = vec![b'Q'; 10_000];
+        haystack.extend_from_slice(needle);
+        let ninfo = NeedleInfo::new(needle);
+        assert_eq!((b'Q', b'x'), ninfo.rarebytes.as_rare_bytes(needle));
+
+        // A non-adaptive prefilter gives up and reports a false positive.
+        let mut prestate = PrefilterState::new();
+        let got = find(&mut prestate, &ninfo, &haystack, needle);
+        assert_ne!(Some(10_000), got);
+        assert!(!prestate.is_effective());
+
+        // But an adaptive one switches to bytes that never occur in the
+        // sample and skips straight to the match.
+        let mut prestate = PrefilterState::adaptive();
+        let got = find(&mut prestate, &ninfo, &haystack, needle);
+        assert_eq!(Some(10_000), got);
+        assert!(prestate.is_effective());
+        assert_eq!(
+            (b'x', b'e'),
+            prestate.rarebytes(&ninfo).as_rare_bytes(needle)
+        );
+    }
+
+    #[test]
+    fn samples_start_of_haystack() {
+        // Like above, but 'Q' is so common at the start of the haystack that
+        // the rare bytes are re-picked before the prefilter runs at all, so
+        // it only needs to run once.
+        let needle = b"Qxxxxxxe";
+        let mut haystack = vec![b'Q'; 2 * SAMPLE_LEN];
+        haystack.extend_from_slice(needle);
+        let ninfo = NeedleInfo::new(needle);
+
+        let mut prestate = PrefilterState::adaptive();
+        let got = find(&mut prestate, &ninfo, &haystack, needle);
+        assert_eq!(Some(2 * SAMPLE_LEN), got);
+        assert_eq!(1, prestate.skips());
+        assert_eq!(
+            (b'x', b'e'),
+            prestate.rarebytes(&ninfo).as_rare_bytes(needle)
+        );
+
+        // When the rare byte really is rare in the sample, nothing changes.
+        let haystack = vec![b'x'; 2 * SAMPLE_LEN];
+        let mut prestate = PrefilterState::adaptive();
+        assert_eq!(None, find(&mut prestate, &ninfo, &haystack, needle));
+        assert_eq!(
+            (b'Q', b'x'),
+            prestate.rarebytes(&ninfo).as_rare_bytes(needle)
+        );
+    }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn prefilter_permutations() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+
+        // SAFETY: super::find is safe to call for all inputs and on all
+        // platforms.
+        unsafe { PrefilterTest::run_all_tests(super::find) };
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod proptests {
+    use crate::memmem::{FinderBuilder, Prefilter};
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        FinderBuilder::new()
+            .prefilter(Prefilter::Adaptive)
+            .build_forward(needle)
+            .find(haystack)
+    }
+
+    define_memmem_quickcheck_tests!(find, crate::memmem::rfind);
+}
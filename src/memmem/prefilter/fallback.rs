@@ -35,6 +35,7 @@ use crate::memmem::{
 
 // Check that the functions below satisfy the Prefilter function type.
 const _: PrefilterFnTy = find;
+const _: PrefilterFnTy = rfind;
 
 /// Look for a possible occurrence of needle. The position returned
 /// corresponds to the beginning of the occurrence, if one exists.
@@ -88,6 +89,59 @@ pub(crate) fn find(
     Some(i.saturating_sub(rare1i))
 }
 
+/// Look for a possible occurrence of needle, starting from the end of the
+/// haystack. The position returned corresponds to the end of the occurrence,
+/// if one exists. That is, if a match is found at `haystack[s..e]`, then `e`
+/// is returned.
+///
+/// Callers may assume that this never returns false negatives (i.e., it
+/// never misses an actual occurrence), but must check that the returned
+/// position corresponds to a match. That is, it can return false
+/// positives.
+///
+/// This should only be used for reverse searching.
+pub(crate) fn rfind(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    let mut i = haystack.len();
+    let (rare1i, rare2i) = ninfo.rarebytes.as_rare_usize();
+    let (rare1, rare2) = ninfo.rarebytes.as_rare_bytes(needle);
+    while prestate.is_effective() {
+        // Use a fast vectorized implementation to skip to the previous
+        // occurrence of the rarest byte (heuristically chosen) in the
+        // needle.
+        let found = crate::memrchr(rare1, &haystack[..i])?;
+        prestate.update(i - found);
+        i = found;
+
+        // If we can't align our rare1 byte with the haystack such that the
+        // needle fits, then a match is impossible. Since we only ever move
+        // backwards, we'll eventually find a position that does fit (if one
+        // exists).
+        if i < rare1i || i - rare1i + needle.len() > haystack.len() {
+            continue;
+        }
+
+        // Align our rare2 byte with the haystack. A mismatch means that
+        // a match is impossible.
+        let start = i - rare1i;
+        if haystack.get(start + rare2i) != Some(&rare2) {
+            continue;
+        }
+
+        // We've done what we can. There might be a match here.
+        return Some(start + needle.len());
+    }
+    // The only way we get here is if we believe our skipping heuristic
+    // has become ineffective. We're allowed to return false positives, so
+    // return the end of the rightmost match that we haven't yet ruled out.
+    // (Every match whose rare1 byte is at or after `i` has been ruled out.)
+    Some(core::cmp::min(haystack.len(), i + needle.len() - rare1i))
+}
+
 #[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
@@ -98,6 +152,24 @@ mod tests {
         find(&mut prestate, &ninfo, haystack, needle)
     }
 
+    fn freqy_rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        let ninfo = NeedleInfo::reverse(needle);
+        let mut prestate = PrefilterState::new();
+        rfind(&mut prestate, &ninfo, haystack, needle)
+    }
+
+    #[test]
+    fn freqy_reverse() {
+        assert_eq!(Some(3), freqy_rfind(b"BARFOO", b"BAR"));
+        assert_eq!(Some(6), freqy_rfind(b"FOOBAR", b"BAR"));
+        assert_eq!(Some(4), freqy_rfind(b"zyzz", b"zyzy"));
+        assert_eq!(None, freqy_rfind(b"zzzy", b"zyzy"));
+        assert_eq!(None, freqy_rfind(b"zazb", b"zyzy"));
+        assert_eq!(Some(4), freqy_rfind(b"yzyy", b"yzyz"));
+        assert_eq!(None, freqy_rfind(b"yyyz", b"yzyz"));
+        assert_eq!(None, freqy_rfind(b"yayb", b"yzyz"));
+    }
+
     #[test]
     fn freqy_forward() {
         assert_eq!(Some(0), freqy_find(b"BARFOO", b"BAR"));
@@ -119,4 +191,58 @@ mod tests {
         // platforms.
         unsafe { PrefilterTest::run_all_tests(super::find) };
     }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn rare1_after_rare2() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+
+        // SAFETY: super::find is safe to call for all inputs and on all
+        // platforms.
+        unsafe { PrefilterTest::run_rare1_after_rare2_tests(super::find) };
+    }
+}
+
+// The reverse prefilter isn't covered by the generated prefilter tests (which
+// only support forward searching), so we test it via the reverse searcher,
+// which always uses it when possible.
+
+#[cfg(test)]
+mod simpletests {
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        crate::memmem::Finder::new(needle).find(haystack)
+    }
+
+    fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        crate::memmem::FinderRev::new(needle).rfind(haystack)
+    }
+
+    define_memmem_simple_tests!(find, rfind);
+
+    // The rarest byte in the needle occurs at every position in the
+    // haystack, so the prefilter should become inert without breaking the
+    // search.
+    #[cfg(feature = "std")]
+    #[test]
+    fn rfind_repeated_rare() {
+        let mut haystack = vec![b'z'; 10_000];
+        assert_eq!(None, rfind(&haystack, b"abczdef"));
+        haystack[..7].copy_from_slice(b"abczdef");
+        assert_eq!(Some(0), rfind(&haystack, b"abczdef"));
+        haystack[5000..5007].copy_from_slice(b"abczdef");
+        assert_eq!(Some(5000), rfind(&haystack, b"abczdef"));
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod proptests {
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        crate::memmem::Finder::new(needle).find(haystack)
+    }
+
+    fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        crate::memmem::FinderRev::new(needle).rfind(haystack)
+    }
+
+    define_memmem_quickcheck_tests!(find, rfind);
 }
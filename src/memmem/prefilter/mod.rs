@@ -1,5 +1,6 @@
 use crate::memmem::{rarebytes::RareNeedleBytes, NeedleInfo};
 
+mod adaptive;
 mod fallback;
 #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
 mod genericsimd;
@@ -47,13 +48,14 @@ impl<'a> Pre<'a> {
 /// A prefilter function.
 ///
 /// A prefilter function describes both forward and reverse searches.
-/// (Although, we don't currently implement prefilters for reverse searching.)
-/// In the case of a forward search, the position returned corresponds to
-/// the starting offset of a match (confirmed or possible). Its minimum
-/// value is `0`, and its maximum value is `haystack.len() - 1`. In the case
-/// of a reverse search, the position returned corresponds to the position
-/// immediately after a match (confirmed or possible). Its minimum value is `1`
-/// and its maximum value is `haystack.len()`.
+/// (Although, the only prefilter for reverse searching is the fallback one
+/// based on `memrchr`.) In the case of a forward search, the position
+/// returned corresponds to the starting offset of a match (confirmed or
+/// possible). Its minimum value is `0`, and its maximum value is
+/// `haystack.len() - 1`. In the case of a reverse search, the position
+/// returned corresponds to the position immediately after a match (confirmed
+/// or possible). Its minimum value is `1` and its maximum value is
+/// `haystack.len()`.
 ///
 /// In both cases, the position returned is the starting (or ending) point of a
 /// _possible_ match. That is, returning a false positive is okay. A prefilter,
@@ -148,7 +150,7 @@ impl core::fmt::Debug for PrefilterFn {
 /// disable its use. Nevertheless, this configuration option gives callers
 /// the ability to disable pefilters if you have knowledge that they won't be
 /// useful.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[non_exhaustive]
 pub enum Prefilter {
     /// Never used a prefilter in substring search.
@@ -157,6 +159,27 @@ pub enum Prefilter {
     /// it is used, then heuristics will be used to dynamically disable the
     /// prefilter if it is believed to not be carrying its weight.
     Auto,
+    /// Like `Auto`, but when the prefilter is believed to not be carrying its
+    /// weight, it gets one more chance before being disabled. Namely, the
+    /// bytes from the needle used to detect candidates are re-picked based on
+    /// how often they actually occur in a sample of the haystack being
+    /// searched, instead of relying solely on a background frequency
+    /// distribution of bytes.
+    ///
+    /// This is useful for very long haystacks whose byte distribution differs
+    /// markedly from the background distribution. e.g., A byte that is
+    /// typically rare might be quite common in a specific haystack, while a
+    /// byte that is typically common might not occur at all.
+    ///
+    /// For long haystacks, the start of the haystack is also sampled before
+    /// the prefilter first runs (see
+    /// [`sample_byte_density`](crate::memmem::sample_byte_density)). If the
+    /// rarest byte of the needle turns out to be common there, then the bytes
+    /// are re-picked right away.
+    ///
+    /// This is currently a prototype. It always uses a prefilter based on
+    /// `memchr` and is never used for reverse searches.
+    Adaptive,
 }
 
 impl Default for Prefilter {
@@ -172,19 +195,32 @@ impl Prefilter {
             _ => false,
         }
     }
+
+    pub(crate) fn is_adaptive(&self) -> bool {
+        match *self {
+            Prefilter::Adaptive => true,
+            _ => false,
+        }
+    }
 }
 
-/// PrefilterState tracks state associated with the effectiveness of a
-/// prefilter. It is used to track how many bytes, on average, are skipped by
-/// the prefilter. If this average dips below a certain threshold over time,
-/// then the state renders the prefilter inert and stops using it.
+/// The state of a prefilter's effectiveness heuristic.
+///
+/// A prefilter tracks how many bytes, on average, it skips each time it runs.
+/// If this average dips below a certain threshold over time, then the state
+/// renders the prefilter inert and it is no longer used, since running it
+/// would only slow the search down.
 ///
-/// A prefilter state should be created for each search. (Where creating an
-/// iterator is treated as a single search.) A prefilter state should only be
-/// created from a `Freqy`. e.g., An inert `Freqy` will produce an inert
-/// `PrefilterState`.
+/// Normally, a fresh state is created for each search, where an iterator
+/// counts as a single search. Some routines, such as
+/// [`FinderRev::rfind_iter_with_state`](crate::memmem::FinderRev::rfind_iter_with_state),
+/// accept a state from the caller instead, so that what was learned about the
+/// prefilter while searching one haystack carries over to the next. This is
+/// useful when searching many similar haystacks for the same needle.
+///
+/// A state only ever affects how fast a search is, never its result.
 #[derive(Clone, Debug)]
-pub(crate) struct PrefilterState {
+pub struct PrefilterState {
     /// The number of skips that has been executed. This is always 1 greater
     /// than the actual number of skips. The special sentinel value of 0
     /// indicates that the prefilter is inert. This is useful to avoid
@@ -194,6 +230,43 @@ pub(crate) struct PrefilterState {
     skips: u32,
     /// The total number of bytes that have been skipped.
     skipped: u32,
+    /// Once the prefilter has moved past this many bytes, it is judged on
+    /// its average skip even if it hasn't run `MIN_SKIPS` times yet. This is
+    /// `u32::MAX` unless a smaller warmup budget was configured.
+    max_warmup: u32,
+    /// Whether the rare bytes used by the prefilter may be re-picked based on
+    /// the haystack being searched, and if so, which stage of that process
+    /// we're in. This is always `Never` unless the prefilter is adaptive.
+    adapt: Adapt,
+}
+
+impl Default for PrefilterState {
+    fn default() -> PrefilterState {
+        PrefilterState::new()
+    }
+}
+
+/// The stages of re-picking rare bytes in an adaptive prefilter.
+#[derive(Clone, Copy, Debug)]
+enum Adapt {
+    /// Rare bytes are never re-picked. The prefilter becomes inert once it is
+    /// deemed ineffective.
+    Never,
+    /// Like `Pending`, but the start of the haystack hasn't been sampled yet.
+    /// If the rare bytes turn out to be common in the sample, then they are
+    /// re-picked right away instead of waiting for the prefilter to be
+    /// deemed ineffective.
+    Unsampled,
+    /// Rare bytes will be re-picked the first time the prefilter is deemed
+    /// ineffective.
+    Pending,
+    /// The prefilter was deemed ineffective, and it should re-pick its rare
+    /// bytes the next time it's called.
+    Requested,
+    /// The rare bytes were re-picked, and these should be used instead of the
+    /// rare bytes in the needle info. If the prefilter is deemed ineffective
+    /// again, it becomes inert.
+    Active(RareNeedleBytes),
 }
 
 impl PrefilterState {
@@ -210,13 +283,60 @@ impl PrefilterState {
     const MIN_SKIP_BYTES: u32 = 8;
 
     /// Create a fresh prefilter state.
-    pub(crate) fn new() -> PrefilterState {
-        PrefilterState { skips: 1, skipped: 0 }
+    ///
+    /// A prefilter starts out assumed to be effective, and only becomes inert
+    /// after it has been observed to skip too few bytes.
+    pub fn new() -> PrefilterState {
+        PrefilterState {
+            skips: 1,
+            skipped: 0,
+            max_warmup: core::u32::MAX,
+            adapt: Adapt::Never,
+        }
+    }
+
+    /// Create a fresh prefilter state for an adaptive prefilter. Unlike a
+    /// state created by `new`, the first time this state is deemed
+    /// ineffective, it resets itself and requests that the prefilter re-pick
+    /// its rare bytes instead of becoming inert.
+    pub(crate) fn adaptive() -> PrefilterState {
+        PrefilterState {
+            skips: 1,
+            skipped: 0,
+            max_warmup: core::u32::MAX,
+            adapt: Adapt::Unsampled,
+        }
     }
 
     /// Create a fresh prefilter state that is always inert.
-    pub(crate) fn inert() -> PrefilterState {
-        PrefilterState { skips: 0, skipped: 0 }
+    ///
+    /// Searching with an inert state never uses a prefilter.
+    pub fn inert() -> PrefilterState {
+        PrefilterState {
+            skips: 0,
+            skipped: 0,
+            max_warmup: core::u32::MAX,
+            adapt: Adapt::Never,
+        }
+    }
+
+    /// Cap the warmup of this state at the given number of bytes.
+    ///
+    /// Normally, a prefilter isn't judged until it has run `MIN_SKIPS` times.
+    /// With a budget, it is also judged once it has moved past `bytes` bytes
+    /// of the haystack, counting each candidate it reported as one byte. So
+    /// a prefilter that reports a lot of false positives early on may become
+    /// inert after fewer runs.
+    pub(crate) fn with_max_warmup_bytes(
+        mut self,
+        bytes: usize,
+    ) -> PrefilterState {
+        self.max_warmup = if bytes > core::u32::MAX as usize {
+            core::u32::MAX
+        } else {
+            bytes as u32
+        };
+        self
     }
 
     /// Update this state with the number of bytes skipped on the last
@@ -241,18 +361,80 @@ impl PrefilterState {
         if self.is_inert() {
             return false;
         }
-        if self.skips() < PrefilterState::MIN_SKIPS {
+        if self.skips() < PrefilterState::MIN_SKIPS && !self.is_warmup_spent()
+        {
             return true;
         }
         if self.skipped >= PrefilterState::MIN_SKIP_BYTES * self.skips() {
             return true;
         }
+        // Give an adaptive prefilter one more chance with a fresh slate.
+        if let Adapt::Pending | Adapt::Unsampled = self.adapt {
+            self.adapt = Adapt::Requested;
+            self.skips = 1;
+            self.skipped = 0;
+            return true;
+        }
 
         // We're inert.
         self.skips = 0;
         false
     }
 
+    /// Return the rare bytes that a prefilter should use. If the rare bytes
+    /// were re-picked by an adaptive prefilter, then those are returned.
+    /// Otherwise, the rare bytes computed for the needle are returned.
+    #[inline]
+    fn rarebytes(&self, ninfo: &NeedleInfo) -> RareNeedleBytes {
+        match self.adapt {
+            Adapt::Active(rarebytes) => rarebytes,
+            _ => ninfo.rarebytes,
+        }
+    }
+
+    /// Return true if and only if the prefilter should re-pick its rare bytes
+    /// before continuing.
+    #[inline]
+    fn is_adapt_requested(&self) -> bool {
+        match self.adapt {
+            Adapt::Requested => true,
+            _ => false,
+        }
+    }
+
+    /// Return true if and only if the prefilter should sample the start of
+    /// the haystack before it runs for the first time.
+    #[inline]
+    fn is_sample_requested(&self) -> bool {
+        match self.adapt {
+            Adapt::Unsampled => true,
+            _ => false,
+        }
+    }
+
+    /// Record the result of sampling the start of the haystack. If the rare
+    /// bytes were found to be common, then the prefilter re-picks them the
+    /// next time it checks. Otherwise, they are only re-picked once the
+    /// prefilter is deemed ineffective.
+    #[inline]
+    fn set_sampled(&mut self, common: bool) {
+        self.adapt = if common { Adapt::Requested } else { Adapt::Pending };
+    }
+
+    /// Record the rare bytes re-picked by an adaptive prefilter. All
+    /// subsequent searches with this state will use them.
+    #[inline]
+    fn set_adapted(&mut self, rarebytes: RareNeedleBytes) {
+        self.adapt = Adapt::Active(rarebytes);
+    }
+
+    /// Return true if and only if this prefilter has moved past at least as
+    /// many bytes as its warmup budget allows.
+    #[inline]
+    fn is_warmup_spent(&self) -> bool {
+        self.skipped.saturating_add(self.skips()) >= self.max_warmup
+    }
+
     #[inline]
     fn is_inert(&self) -> bool {
         self.skips == 0
@@ -279,18 +461,20 @@ pub(crate) fn forward(
     if config.is_none() || needle.len() <= 1 {
         return None;
     }
+    if config.is_adaptive() {
+        // SAFETY: adaptive::find is safe to call in all environments.
+        return unsafe { Some(PrefilterFn::new(adaptive::find)) };
+    }
 
-    #[cfg(feature = "std")]
+    #[cfg(any(feature = "std", target_feature = "avx2"))]
     {
-        if cfg!(memchr_runtime_avx) {
-            if is_x86_feature_detected!("avx2") {
-                // SAFETY: x86::avx::find only requires the avx2 feature,
-                // which we've just checked above.
-                return unsafe { Some(PrefilterFn::new(x86::avx::find)) };
-            }
+        if crate::memmem::x86::is_avx2_enabled() {
+            // SAFETY: x86::avx::find only requires the avx2 feature,
+            // which we've just checked above.
+            return unsafe { Some(PrefilterFn::new(x86::avx::find)) };
         }
     }
-    if cfg!(memchr_runtime_sse2) {
+    if crate::memmem::x86::is_sse2_enabled() {
         // SAFETY: x86::sse::find only requires the sse2 feature, which is
         // guaranteed to be available on x86_64.
         return unsafe { Some(PrefilterFn::new(x86::sse::find)) };
@@ -320,6 +504,10 @@ pub(crate) fn forward(
     if config.is_none() || needle.len() <= 1 {
         return None;
     }
+    if config.is_adaptive() {
+        // SAFETY: adaptive::find is safe to call in all environments.
+        return unsafe { Some(PrefilterFn::new(adaptive::find)) };
+    }
     let (rare1_rank, _) = rare.as_ranks(needle);
     if rare1_rank <= MAX_FALLBACK_RANK {
         // SAFETY: fallback::find is safe to call in all environments.
@@ -328,6 +516,33 @@ pub(crate) fn forward(
     None
 }
 
+/// Determine which reverse prefilter function, if any, to use.
+///
+/// Unlike forward searching, there is only one reverse prefilter: the fallback
+/// one based on `memrchr`. It is still vectorized on x86_64 (when runtime SIMD
+/// detection is enabled), since `memrchr` itself is vectorized. As with the
+/// forward fallback prefilter, we only use it when the rarest byte in the
+/// needle has a reasonably low rank.
+///
+/// Adaptive prefilters are not supported for reverse searching, so
+/// `Prefilter::Adaptive` is treated the same as `Prefilter::Auto`.
+#[inline(always)]
+pub(crate) fn reverse(
+    config: &Prefilter,
+    rare: &RareNeedleBytes,
+    needle: &[u8],
+) -> Option<PrefilterFn> {
+    if config.is_none() || needle.len() <= 1 {
+        return None;
+    }
+    let (rare1_rank, _) = rare.as_ranks(needle);
+    if rare1_rank <= MAX_FALLBACK_RANK {
+        // SAFETY: fallback::rfind is safe to call in all environments.
+        return unsafe { Some(PrefilterFn::new(fallback::rfind)) };
+    }
+    None
+}
+
 /// Return the minimum length of the haystack in which a prefilter should be
 /// used. If the haystack is below this length, then it's probably not worth
 /// the overhead of running the prefilter.
@@ -337,6 +552,14 @@ pub(crate) fn forward(
 /// the prefilter falls back to memchr for small haystacks, and, at the
 /// meta-searcher level, Rabin-Karp is employed for tiny haystacks anyway.
 ///
+/// We also tried bringing it back for the Two-Way searcher, along with a
+/// larger minimum for needles whose rarest byte is very common (rank 235 or
+/// higher), on the theory that Two-Way on its own would win there. It
+/// didn't. For needles of 33 to 100 bytes from `huge-en`, on haystack slices
+/// of 64 bytes to 4KB, skipping the prefilter below this length was up to 4x
+/// slower, and skipping it for common needles was up to 10x slower. The
+/// prefilter's own effectiveness tracking already gives up when it should.
+///
 /// We keep it around for now in case we want to bring it back.
 #[allow(dead_code)]
 pub(crate) fn minimum_len(_haystack: &[u8], needle: &[u8]) -> usize {
@@ -365,7 +588,7 @@ pub(crate) mod tests {
 
     use super::*;
     use crate::memmem::{
-        prefilter::PrefilterFnTy, rabinkarp, rarebytes::RareNeedleBytes,
+        prefilter::PrefilterFnTy, rarebytes::RareNeedleBytes, rkhash,
     };
 
     // Below is a small jig that generates prefilter tests. The main purpose
@@ -417,6 +640,79 @@ pub(crate) mod tests {
             }
         }
 
+        /// Run a handful of targeted tests on the given prefn where the rarest
+        /// byte in the needle occurs after the second rarest byte. That is,
+        /// where `rare1i > rare2i`.
+        ///
+        /// In this case, prefilters that align the haystack relative to the
+        /// rarest byte need to be careful not to misalign the second rarest
+        /// byte. Each test below is run with haystacks that are both shorter
+        /// and longer than a vector, so that both scalar and vector code paths
+        /// are exercised.
+        ///
+        /// # Safety
+        ///
+        /// Callers must ensure that the given prefilter function pointer is
+        /// safe to call for all inputs in the current environment.
+        pub(crate) unsafe fn run_rare1_after_rare2_tests(
+            prefn: PrefilterFnTy,
+        ) {
+            // (needle, rare1i, rare2i)
+            let needles: &[(&[u8], u8, u8)] = &[
+                (b"abcz", 3, 1),
+                (b"abcz", 3, 0),
+                (b"abcz", 1, 0),
+                (b"zyxwvutsrqponmlkjihgfedcba", 25, 2),
+                (b"zyxwvutsrqponmlkjihgfedcba", 20, 19),
+            ];
+            for &(needle, rare1i, rare2i) in needles {
+                let ninfo = NeedleInfo {
+                    rarebytes: RareNeedleBytes::new(rare1i, rare2i),
+                    nhash: rkhash::NeedleHash::forward(needle),
+                };
+                let rare1 = needle[rare1i as usize];
+                for pad in 0..70 {
+                    // The needle occurs after a prefix that contains its
+                    // rarest byte at a position where it can't be aligned
+                    // with the needle.
+                    let mut haystack = vec![b'@'; pad];
+                    if pad > 0 {
+                        haystack[0] = rare1;
+                    }
+                    let start = haystack.len();
+                    haystack.extend_from_slice(needle);
+                    haystack.resize(start + needle.len() + pad, b'@');
+                    let mut prestate = PrefilterState::new();
+                    assert_eq!(
+                        Some(start),
+                        prefn(&mut prestate, &ninfo, &haystack, needle),
+                        "needle: {:?}, rare1i: {}, rare2i: {}, haystack: {:?}",
+                        std::str::from_utf8(needle).unwrap(),
+                        rare1i,
+                        rare2i,
+                        std::str::from_utf8(&haystack).unwrap(),
+                    );
+
+                    // A haystack containing only the rarest byte can never
+                    // contain a match.
+                    let mut haystack = vec![b'@'; pad + needle.len()];
+                    for i in (0..haystack.len()).step_by(3) {
+                        haystack[i] = rare1;
+                    }
+                    let mut prestate = PrefilterState::new();
+                    assert_eq!(
+                        None,
+                        prefn(&mut prestate, &ninfo, &haystack, needle),
+                        "needle: {:?}, rare1i: {}, rare2i: {}, haystack: {:?}",
+                        std::str::from_utf8(needle).unwrap(),
+                        rare1i,
+                        rare2i,
+                        std::str::from_utf8(&haystack).unwrap(),
+                    );
+                }
+            }
+        }
+
         /// Create a new prefilter test from a seed and some chose offsets to
         /// rare bytes in the seed's needle.
         ///
@@ -457,7 +753,7 @@ pub(crate) mod tests {
             }
             let ninfo = NeedleInfo {
                 rarebytes: RareNeedleBytes::new(rare1i, rare2i),
-                nhash: rabinkarp::NeedleHash::forward(&needle),
+                nhash: rkhash::NeedleHash::forward(&needle),
             };
             Some(PrefilterTest { ninfo, haystack, needle, output })
         }
@@ -518,45 +814,119 @@ pub(crate) mod tests {
         /// Generate a series of prefilter tests from this seed.
         fn generate(&self) -> Vec<PrefilterTest> {
             let mut tests = vec![];
-            let mut push = |test: Option<PrefilterTest>| {
-                if let Some(test) = test {
-                    tests.push(test);
-                }
-            };
             let len_start = 2;
+            let rare_start = len_start - 1;
             // The loop below generates *a lot* of tests. The number of tests
             // was chosen somewhat empirically to be "bearable" when running
             // the test suite.
             for needle_len in len_start..=40 {
-                let rare_start = len_start - 1;
                 for rare1i in rare_start..needle_len {
                     for rare2i in rare1i..needle_len {
                         for haystack_len in needle_len..=66 {
-                            push(PrefilterTest::new(
-                                self,
+                            self.generate_all_outputs(
+                                &mut tests,
                                 rare1i,
                                 rare2i,
                                 haystack_len,
                                 needle_len,
-                                None,
-                            ));
-                            // Test all possible match scenarios for this
-                            // needle and haystack.
-                            for output in 0..=(haystack_len - needle_len) {
-                                push(PrefilterTest::new(
-                                    self,
-                                    rare1i,
-                                    rare2i,
-                                    haystack_len,
-                                    needle_len,
-                                    Some(output),
-                                ));
-                            }
+                            );
+                        }
+                    }
+                }
+            }
+            // The loop above only generates tests where rare1i <= rare2i. But
+            // the rarest byte may very well occur after the second rarest
+            // byte, which changes how prefilters need to align candidates. We
+            // generate fewer of these to keep the test suite bearable.
+            for needle_len in len_start..=24 {
+                for rare1i in rare_start..needle_len {
+                    for rare2i in rare_start..rare1i {
+                        for haystack_len in needle_len..=48 {
+                            self.generate_all_outputs(
+                                &mut tests,
+                                rare1i,
+                                rare2i,
+                                haystack_len,
+                                needle_len,
+                            );
                         }
                     }
                 }
             }
             tests
         }
+
+        /// Generate a test for every possible match position (including no
+        /// match at all) for the given rare offsets and lengths.
+        fn generate_all_outputs(
+            &self,
+            tests: &mut Vec<PrefilterTest>,
+            rare1i: usize,
+            rare2i: usize,
+            haystack_len: usize,
+            needle_len: usize,
+        ) {
+            let outputs = core::iter::once(None)
+                .chain((0..=(haystack_len - needle_len)).map(Some));
+            for output in outputs {
+                tests.extend(PrefilterTest::new(
+                    self,
+                    rare1i,
+                    rare2i,
+                    haystack_len,
+                    needle_len,
+                    output,
+                ));
+            }
+        }
+    }
+
+    /// Run the full suite against whichever prefilter `forward` selects when
+    /// limited to each family of vector routines in turn. On a CPU without
+    /// AVX2, the AVX2 run exercises SSE2 again.
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+    #[test]
+    fn forward_permutations_every_isa() {
+        use crate::memmem::x86::force;
+
+        for &isa in force::ALL {
+            let prefn = force::with_max_isa(isa, || {
+                // 'z' is rare enough that the fallback prefilter is used
+                // when no vector routines are allowed.
+                forward(&Prefilter::Auto, &RareNeedleBytes::new(0, 1), b"zq")
+            });
+            let prefn = prefn.expect("a prefilter should always be selected");
+            // SAFETY: forward only selects prefilters that are safe to call
+            // in the current environment.
+            unsafe { PrefilterTest::run_all_tests(prefn.0) };
+        }
+    }
+
+    #[test]
+    fn warmup_budget() {
+        // Without a budget, a prefilter that never skips anything is only
+        // judged after MIN_SKIPS runs.
+        let mut state = PrefilterState::new();
+        for _ in 0..PrefilterState::MIN_SKIPS {
+            assert!(state.is_effective());
+            state.update(1);
+        }
+        assert!(!state.is_effective());
+
+        // With a budget, it's judged as soon as the budget is spent.
+        let mut state = PrefilterState::new().with_max_warmup_bytes(20);
+        for _ in 0..10 {
+            assert!(state.is_effective());
+            state.update(1);
+        }
+        assert!(!state.is_effective());
+
+        // A prefilter that skips enough bytes stays effective.
+        let mut state = PrefilterState::new().with_max_warmup_bytes(20);
+        for _ in 0..PrefilterState::MIN_SKIPS {
+            assert!(state.is_effective());
+            state.update(100);
+        }
+        assert!(state.is_effective());
     }
 }
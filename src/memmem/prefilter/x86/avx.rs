@@ -30,7 +30,7 @@ pub(crate) unsafe fn find(
     )
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     #[test]
     #[cfg(not(miri))]
@@ -43,4 +43,16 @@ mod tests {
         // CPU support AVX2, which we checked above.
         unsafe { PrefilterTest::run_all_tests(super::find) };
     }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn rare1_after_rare2() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+        // SAFETY: The safety of super::find only requires that the current
+        // CPU support AVX2, which we checked above.
+        unsafe { PrefilterTest::run_rare1_after_rare2_tests(super::find) };
+    }
 }
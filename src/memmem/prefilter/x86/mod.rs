@@ -1,5 +1,5 @@
 // We only use AVX when we can detect at runtime whether it's available, which
-// requires std.
-#[cfg(feature = "std")]
+// requires std, or when it's enabled at compile time.
+#[cfg(any(feature = "std", target_feature = "avx2"))]
 pub(crate) mod avx;
 pub(crate) mod sse;
@@ -52,4 +52,12 @@ mod tests {
         // SAFETY: super::find is safe to call for all inputs on x86.
         unsafe { PrefilterTest::run_all_tests(super::find) };
     }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn rare1_after_rare2() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        // SAFETY: super::find is safe to call for all inputs on x86.
+        unsafe { PrefilterTest::run_rare1_after_rare2_tests(super::find) };
+    }
 }
@@ -31,6 +31,8 @@ Finally, aho-corasick uses Rabin-Karp for multiple pattern match in some cases:
 https://github.com/BurntSushi/aho-corasick/blob/3852632f10587db0ff72ef29e88d58bf305a0946/src/packed/rabinkarp.rs
 */
 
+use crate::memmem::rkhash::{Hash, NeedleHash};
+
 /// Whether RK is believed to be very fast for the given needle/haystack.
 pub(crate) fn is_fast(haystack: &[u8], _needle: &[u8]) -> bool {
     haystack.len() < 16
@@ -100,107 +102,6 @@ pub(crate) fn rfind_with(
     }
 }
 
-/// A hash derived from a needle.
-#[derive(Clone, Copy, Debug, Default)]
-pub(crate) struct NeedleHash {
-    /// The actual hash.
-    hash: Hash,
-    /// The factor needed to multiply a byte by in order to subtract it from
-    /// the hash. It is defined to be 2^(n-1) (using wrapping exponentiation),
-    /// where n is the length of the needle. This is how we "remove" a byte
-    /// from the hash once the hash window rolls past it.
-    hash_2pow: u32,
-}
-
-impl NeedleHash {
-    /// Create a new Rabin-Karp hash for the given needle for use in forward
-    /// searching.
-    pub(crate) fn forward(needle: &[u8]) -> NeedleHash {
-        let mut nh = NeedleHash { hash: Hash::new(), hash_2pow: 1 };
-        if needle.is_empty() {
-            return nh;
-        }
-        nh.hash.add(needle[0]);
-        for &b in needle.iter().skip(1) {
-            nh.hash.add(b);
-            nh.hash_2pow = nh.hash_2pow.wrapping_shl(1);
-        }
-        nh
-    }
-
-    /// Create a new Rabin-Karp hash for the given needle for use in reverse
-    /// searching.
-    pub(crate) fn reverse(needle: &[u8]) -> NeedleHash {
-        let mut nh = NeedleHash { hash: Hash::new(), hash_2pow: 1 };
-        if needle.is_empty() {
-            return nh;
-        }
-        nh.hash.add(needle[needle.len() - 1]);
-        for &b in needle.iter().rev().skip(1) {
-            nh.hash.add(b);
-            nh.hash_2pow = nh.hash_2pow.wrapping_shl(1);
-        }
-        nh
-    }
-
-    /// Return true if the hashes are equivalent.
-    fn eq(&self, hash: Hash) -> bool {
-        self.hash == hash
-    }
-}
-
-/// A Rabin-Karp hash. This might represent the hash of a needle, or the hash
-/// of a rolling window in the haystack.
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-pub(crate) struct Hash(u32);
-
-impl Hash {
-    /// Create a new hash that represents the empty string.
-    pub(crate) fn new() -> Hash {
-        Hash(0)
-    }
-
-    /// Create a new hash from the bytes given for use in forward searches.
-    pub(crate) fn from_bytes_fwd(bytes: &[u8]) -> Hash {
-        let mut hash = Hash::new();
-        for &b in bytes {
-            hash.add(b);
-        }
-        hash
-    }
-
-    /// Create a new hash from the bytes given for use in reverse searches.
-    fn from_bytes_rev(bytes: &[u8]) -> Hash {
-        let mut hash = Hash::new();
-        for &b in bytes.iter().rev() {
-            hash.add(b);
-        }
-        hash
-    }
-
-    /// Add 'new' and remove 'old' from this hash. The given needle hash should
-    /// correspond to the hash computed for the needle being searched for.
-    ///
-    /// This is meant to be used when the rolling window of the haystack is
-    /// advanced.
-    fn roll(&mut self, nhash: &NeedleHash, old: u8, new: u8) {
-        self.del(nhash, old);
-        self.add(new);
-    }
-
-    /// Add a byte to this hash.
-    fn add(&mut self, byte: u8) {
-        self.0 = self.0.wrapping_shl(1).wrapping_add(byte as u32);
-    }
-
-    /// Remove a byte from this hash. The given needle hash should correspond
-    /// to the hash computed for the needle being searched for.
-    fn del(&mut self, nhash: &NeedleHash, byte: u8) {
-        let factor = nhash.hash_2pow;
-        self.0 = self.0.wrapping_sub((byte as u32).wrapping_mul(factor));
-    }
-}
-
 /// Returns true if the given needle is a prefix of the given haystack.
 ///
 /// We forcefully don't inline the is_prefix call and hint at the compiler that
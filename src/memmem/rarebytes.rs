@@ -31,20 +31,90 @@ pub(crate) struct RareNeedleBytes {
     /// byte needle is handled specially by memchr itself, rare2i generally
     /// always should be different from rare1i since it would otherwise be
     /// ineffective as a guard.
+    ///
+    /// Note that rare2i may be less than rare1i. Callers that align a
+    /// candidate relative to rare1i (like the fallback prefilter) must
+    /// account for this, while callers that only care about the span covered
+    /// by the two offsets should use `as_rare_ordered_usize`.
     rare2i: u8,
 }
 
 impl RareNeedleBytes {
     /// Create a new pair of rare needle bytes with the given offsets. This is
-    /// only used in tests for generating input data.
-    #[cfg(all(test, feature = "std"))]
-    pub(crate) fn new(rare1i: u8, rare2i: u8) -> RareNeedleBytes {
+    /// used in tests for generating input data, and for reconstructing a
+    /// searcher from pre-computed parts.
+    pub(crate) const fn new(rare1i: u8, rare2i: u8) -> RareNeedleBytes {
         RareNeedleBytes { rare1i, rare2i }
     }
 
     /// Detect the leftmost offsets of the two rarest bytes in the given
     /// needle.
     pub(crate) fn forward(needle: &[u8]) -> RareNeedleBytes {
+        RareNeedleBytes::select(needle, rank)
+    }
+
+    /// Detect the leftmost offsets of the two rarest bytes in the given
+    /// needle, considering only the positions at which `mask` is non-zero.
+    ///
+    /// Unlike the other constructors, the two offsets returned are equal when
+    /// there is only one such position to choose from. This returns `None`
+    /// when there are no such positions among the first 256 bytes of the
+    /// needle, since no offset could be represented.
+    pub(crate) fn masked(
+        needle: &[u8],
+        mask: &[u8],
+    ) -> Option<RareNeedleBytes> {
+        let mut fixed = needle
+            .iter()
+            .zip(mask)
+            .enumerate()
+            .take(core::u8::MAX as usize + 1)
+            .filter(|&(_, (_, &m))| m != 0)
+            .map(|(i, (&b, _))| (i as u8, b));
+        let (mut rare1i, mut rare1) = fixed.next()?;
+        let (mut rare2i, mut rare2) = (rare1i, rare1);
+        for (i, b) in fixed {
+            if rank(b) < rank(rare1) {
+                rare2 = rare1;
+                rare2i = rare1i;
+                rare1 = b;
+                rare1i = i;
+            } else if rare2i == rare1i
+                || (b != rare1 && (rare2 == rare1 || rank(b) < rank(rare2)))
+            {
+                // Prefer a guard byte that differs from the rarest byte,
+                // since an identical one rejects fewer candidates.
+                rare2 = b;
+                rare2i = i;
+            }
+        }
+        Some(RareNeedleBytes { rare1i, rare2i })
+    }
+
+    /// Detect the leftmost offsets of the two rarest bytes in the given
+    /// needle, where rarity is determined by how often each byte occurs in
+    /// the given sample of a haystack. Ties (which includes bytes that don't
+    /// occur in the sample at all) are broken using the background frequency
+    /// distribution.
+    ///
+    /// This is used by the adaptive prefilter to re-pick its rare bytes when
+    /// the background frequency distribution turns out to be a poor fit for
+    /// the haystack being searched.
+    pub(crate) fn observed(needle: &[u8], sample: &[u8]) -> RareNeedleBytes {
+        let mut counts = [0u32; 256];
+        for &b in sample {
+            counts[b as usize] = counts[b as usize].saturating_add(1);
+        }
+        RareNeedleBytes::select(needle, |b| (counts[b as usize], rank(b)))
+    }
+
+    /// Detect the leftmost offsets of the two rarest bytes in the given
+    /// needle, according to the given ranking function. A lower rank means
+    /// the byte is believed to occur less frequently.
+    fn select<R: PartialOrd>(
+        needle: &[u8],
+        rank: impl Fn(u8) -> R,
+    ) -> RareNeedleBytes {
         if needle.len() <= 1 || needle.len() > core::u8::MAX as usize {
             // For needles bigger than u8::MAX, our offsets aren't big enough.
             // (We make our offsets small to reduce stack copying.)
@@ -113,6 +183,13 @@ impl RareNeedleBytes {
         }
     }
 
+    /// Return the rare offsets as their native u8 values in the order in which
+    /// they were constructed.
+    #[cfg(feature = "std")]
+    pub(crate) fn as_rare_u8(&self) -> (u8, u8) {
+        (self.rare1i, self.rare2i)
+    }
+
     /// Return the rare offsets as usize values in the order in which they were
     /// constructed. rare1, for example, is constructed as the "rarer" byte,
     /// and thus, callers may want to treat it differently from rare2.
@@ -132,5 +209,132 @@ impl RareNeedleBytes {
 /// Return the heuristical frequency rank of the given byte. A lower rank
 /// means the byte is believed to occur less frequently.
 fn rank(b: u8) -> usize {
-    crate::memmem::byte_frequencies::BYTE_FREQUENCIES[b as usize] as usize
+    byte_rank(b) as usize
+}
+
+/// Returns the heuristic frequency rank of the given byte.
+///
+/// A lower rank means the byte is believed to occur less frequently in
+/// typical haystacks. This is the same ranking that substring searchers in
+/// this crate use to pick which bytes of a needle to look for with their
+/// prefilters. The ranking is derived from a mix of source code, prose and
+/// binary data, so it may be a poor fit for some haystacks.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::byte_rank;
+///
+/// assert!(byte_rank(b'Z') < byte_rank(b'e'));
+/// assert!(byte_rank(b'\x7F') < byte_rank(b' '));
+/// ```
+#[inline]
+pub fn byte_rank(b: u8) -> u8 {
+    crate::memmem::byte_frequencies::BYTE_FREQUENCIES[b as usize]
+}
+
+/// Returns the offset and value of the byte in `bytes` with the lowest
+/// [`byte_rank`].
+///
+/// When several bytes share the lowest rank, the leftmost one is returned.
+/// This returns `None` only when `bytes` is empty.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::rarest_byte;
+///
+/// assert_eq!(Some((1, b'z')), rarest_byte(b"az ez"));
+/// assert_eq!(None, rarest_byte(b""));
+/// ```
+#[inline]
+pub fn rarest_byte(bytes: &[u8]) -> Option<(usize, u8)> {
+    let mut rarest: Option<(usize, u8)> = None;
+    for (i, &b) in bytes.iter().enumerate() {
+        match rarest {
+            Some((_, r)) if byte_rank(r) <= byte_rank(b) => {}
+            _ => rarest = Some((i, b)),
+        }
+    }
+    rarest
+}
+
+/// Returns the candidate needle that is likely to be the fastest to search
+/// for.
+///
+/// This is meant for when any one of several needles will do, such as
+/// different encodings of the same token. Candidates are ranked by the
+/// [`byte_rank`] of their rarest byte, since that's the byte the prefilters
+/// in this crate look for, and the rarer it is, the fewer false candidates
+/// they report. Ties are broken in favor of the longer needle, since it
+/// rules out more of the haystack with each candidate, and then in favor of
+/// the earlier one. An empty needle has no rarest byte, so it's only
+/// returned if every candidate is empty.
+///
+/// This returns `None` only when there are no candidates.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::best_needle;
+///
+/// let candidates: &[&[u8]] = &[b"caf\xc3\xa9", b"caf\xe9", b"cafe\xcc\x81"];
+/// // The continuation byte of the two byte encoding is the rarest of all.
+/// assert_eq!(Some(&b"caf\xc3\xa9"[..]), best_needle(candidates));
+/// assert_eq!(None, best_needle(&[]));
+/// ```
+pub fn best_needle<'a>(candidates: &[&'a [u8]]) -> Option<&'a [u8]> {
+    // Compare by rarest rank (empty needles after everything else), and then
+    // by length in descending order.
+    let key = |needle: &[u8]| {
+        let rank = match rarest_byte(needle) {
+            None => 256,
+            Some((_, b)) => rank(b),
+        };
+        (rank, core::usize::MAX - needle.len())
+    };
+    let mut best: Option<&'a [u8]> = None;
+    for &needle in candidates {
+        match best {
+            Some(b) if key(b) <= key(needle) => {}
+            _ => best = Some(needle),
+        }
+    }
+    best
+}
+
+/// Returns the fraction of bytes equal to `byte` in the first `sample_len`
+/// bytes of `haystack`.
+///
+/// This is a cheap estimate of how common `byte` is in the rest of the
+/// haystack, which may be quite different from what its [`byte_rank`]
+/// suggests. The adaptive prefilter uses it to check whether the rarest byte
+/// of a needle is actually rare in the haystack being searched, before
+/// relying on it.
+///
+/// If the haystack is shorter than `sample_len`, then all of it is sampled.
+/// This returns `0.0` when nothing is sampled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::sample_byte_density;
+///
+/// let haystack = b"zzzz    zzzz";
+/// assert_eq!(0.5, sample_byte_density(haystack, b'z', 8));
+/// assert_eq!(1.0 / 3.0, sample_byte_density(haystack, b' ', 12));
+/// assert_eq!(0.0, sample_byte_density(haystack, b'z', 0));
+/// ```
+#[inline]
+pub fn sample_byte_density(
+    haystack: &[u8],
+    byte: u8,
+    sample_len: usize,
+) -> f32 {
+    let sample = &haystack[..core::cmp::min(haystack.len(), sample_len)];
+    if sample.is_empty() {
+        return 0.0;
+    }
+    let count = crate::memchr_iter(byte, sample).count();
+    count as f32 / sample.len() as f32
 }
@@ -0,0 +1,150 @@
+/*!
+Oracles for testing substring search.
+
+The routines in this module search naively, by comparing the needle with the
+haystack at every position. They are far too slow for anything but testing,
+but they are simple enough to be obviously correct, which makes them useful
+for checking the results of the real searchers. In particular, [`check`]
+runs every searcher in this crate (under a few different configurations) on
+a single needle and haystack, which makes it a good fit for a fuzz target or
+for a corpus of regression tests.
+
+This module is only available when the `test-util` feature is enabled.
+*/
+
+use crate::memmem::{
+    self, util::DebugBytes, FinderBuilder, Prefilter, Strategy,
+};
+
+/// Naively search forwards for the given needle in the given haystack.
+///
+/// An empty needle matches at `0`, as it does for [`memmem::find`].
+pub fn naive_find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if haystack.len() < needle.len() {
+        return None;
+    }
+    (0..=haystack.len() - needle.len())
+        .find(|&i| &haystack[i..i + needle.len()] == needle)
+}
+
+/// Naively search in reverse for the given needle in the given haystack.
+///
+/// An empty needle matches at `haystack.len()`, as it does for
+/// [`memmem::rfind`].
+pub fn naive_rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if haystack.len() < needle.len() {
+        return None;
+    }
+    (0..=haystack.len() - needle.len())
+        .rev()
+        .find(|&i| &haystack[i..i + needle.len()] == needle)
+}
+
+/// Naively find the starting offsets of all non-overlapping occurrences of
+/// the given needle in the given haystack, in the order reported by
+/// [`memmem::find_iter`].
+pub fn naive_find_all(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+    let mut matches = vec![];
+    let mut pos = 0;
+    while pos <= haystack.len() {
+        match naive_find(&haystack[pos..], needle) {
+            None => break,
+            Some(i) => {
+                matches.push(pos + i);
+                pos += i + core::cmp::max(1, needle.len());
+            }
+        }
+    }
+    matches
+}
+
+/// Naively find the starting offsets of all non-overlapping occurrences of
+/// the given needle in the given haystack, in the order reported by
+/// [`memmem::rfind_iter`].
+pub fn naive_rfind_all(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+    let mut matches = vec![];
+    let mut end = Some(haystack.len());
+    while let Some(e) = end {
+        match naive_rfind(&haystack[..e], needle) {
+            None => break,
+            Some(i) => {
+                matches.push(i);
+                end = if i == e { e.checked_sub(1) } else { Some(i) };
+            }
+        }
+    }
+    matches
+}
+
+/// Check that every substring searcher in this crate agrees with a naive
+/// search for the given needle in the given haystack.
+///
+/// This checks the free functions (like [`memmem::find`] and
+/// [`memmem::rfind_iter`]), along with forward, reverse and bidirectional
+/// finders built with the default configuration, without a prefilter, with
+/// the vectorized routine for short needles disabled (so that Two-Way is
+/// used) and with the Boyer-Moore-Horspool strategy.
+///
+/// # Panics
+///
+/// This panics when any searcher disagrees with the naive search. The panic
+/// message describes the searcher along with the needle and haystack.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::testutil;
+///
+/// testutil::check(b"foo bar foo", b"foo");
+/// testutil::check(b"\xFF\x00\xFF", b"\x00\xFF");
+/// ```
+pub fn check(haystack: &[u8], needle: &[u8]) {
+    let fwd = naive_find(haystack, needle);
+    let rev = naive_rfind(haystack, needle);
+    let fwd_all = naive_find_all(haystack, needle);
+    let rev_all = naive_rfind_all(haystack, needle);
+    let report = |what: &str| -> String {
+        format!(
+            "{} disagrees with naive search, needle: {:?}, haystack: {:?}",
+            what,
+            DebugBytes(needle),
+            DebugBytes(haystack),
+        )
+    };
+
+    assert_eq!(fwd, memmem::find(haystack, needle), "{}", report("find"));
+    assert_eq!(rev, memmem::rfind(haystack, needle), "{}", report("rfind"));
+    let got: Vec<usize> = memmem::find_iter(haystack, needle).collect();
+    assert_eq!(fwd_all, got, "{}", report("find_iter"));
+    let got: Vec<usize> = memmem::rfind_iter(haystack, needle).collect();
+    assert_eq!(rev_all, got, "{}", report("rfind_iter"));
+
+    let mut builders = vec![("default", FinderBuilder::new())];
+    let mut builder = FinderBuilder::new();
+    builder.prefilter(Prefilter::None);
+    builders.push(("no prefilter", builder));
+    let mut builder = FinderBuilder::new();
+    builder.max_simd_needle_len(0);
+    builders.push(("no simd", builder));
+    let mut builder = FinderBuilder::new();
+    builder.strategy(Strategy::Horspool);
+    builders.push(("horspool", builder));
+    for (name, builder) in builders {
+        let finder = builder.build_forward(needle);
+        let what = format!("Finder ({})", name);
+        assert_eq!(fwd, finder.find(haystack), "{}", report(&what));
+        let got: Vec<usize> = finder.find_iter(haystack).collect();
+        assert_eq!(fwd_all, got, "{}", report(&what));
+
+        let finder = builder.build_reverse(needle);
+        let what = format!("FinderRev ({})", name);
+        assert_eq!(rev, finder.rfind(haystack), "{}", report(&what));
+        let got: Vec<usize> = finder.rfind_iter(haystack).collect();
+        assert_eq!(rev_all, got, "{}", report(&what));
+
+        let finder = builder.build_bidirectional(needle);
+        let what = format!("BiFinder ({})", name);
+        assert_eq!(fwd, finder.find(haystack), "{}", report(&what));
+        assert_eq!(rev, finder.rfind(haystack), "{}", report(&what));
+    }
+}
@@ -1,6 +1,111 @@
 use core::cmp;
 
-use crate::memmem::{prefilter::Pre, util};
+use crate::{
+    cow::CowBytes,
+    memmem::{prefilter::Pre, util, TwoWayParams, TwoWayShift},
+};
+
+/// A substring searcher that only uses the Two-Way algorithm.
+///
+/// Unlike [`Finder`](crate::memmem::Finder), this never uses a prefilter,
+/// a vectorized searcher or Rabin-Karp. It is always Two-Way, which makes it
+/// a predictable building block for callers that want to do their own
+/// prefiltering: searches run in worst case `O(needle.len() +
+/// haystack.len())` time and constant space, and nothing adapts to the
+/// haystack. For most searches, a `Finder` is faster.
+///
+/// This supports searching in both directions. Both the forward and the
+/// reverse critical factorizations of the needle are computed when the
+/// searcher is built.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::TwoWaySearcher;
+///
+/// let searcher = TwoWaySearcher::new("foo");
+/// assert_eq!(Some(4), searcher.find(b"bar foo baz foo"));
+/// assert_eq!(Some(12), searcher.rfind(b"bar foo baz foo"));
+/// assert_eq!(None, searcher.find(b"bar"));
+/// ```
+#[derive(Clone)]
+pub struct TwoWaySearcher<'n> {
+    needle: CowBytes<'n>,
+    fwd: Forward,
+    rev: Reverse,
+}
+
+/// This shows the needle, but omits the facts computed about it.
+impl<'n> core::fmt::Debug for TwoWaySearcher<'n> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("TwoWaySearcher")
+            .field("needle", &util::DebugBytes(self.needle()))
+            .finish()
+    }
+}
+
+impl<'n> TwoWaySearcher<'n> {
+    /// Create a new Two-Way searcher for the given needle.
+    pub fn new<B: ?Sized + AsRef<[u8]>>(needle: &'n B) -> TwoWaySearcher<'n> {
+        let needle = needle.as_ref();
+        TwoWaySearcher {
+            needle: CowBytes::new(needle),
+            fwd: Forward::new(needle),
+            rev: Reverse::new(needle),
+        }
+    }
+
+    /// Returns the index of the first occurrence of this needle in the given
+    /// haystack.
+    ///
+    /// An empty needle matches at position `0`.
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let needle = self.needle();
+        if needle.is_empty() {
+            Some(0)
+        } else if haystack.len() < needle.len() {
+            None
+        } else {
+            self.fwd.find(None, haystack, needle)
+        }
+    }
+
+    /// Returns the index of the last occurrence of this needle in the given
+    /// haystack.
+    ///
+    /// An empty needle matches at position `haystack.len()`.
+    pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        let needle = self.needle();
+        if needle.is_empty() {
+            Some(haystack.len())
+        } else if haystack.len() < needle.len() {
+            None
+        } else {
+            self.rev.rfind(None, haystack, needle)
+        }
+    }
+
+    /// Convert this searcher into its owned variant, such that it no longer
+    /// borrows the needle.
+    ///
+    /// If this is already an owned searcher, then this is a no-op. Otherwise,
+    /// this copies the needle.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    #[cfg(feature = "std")]
+    pub fn into_owned(self) -> TwoWaySearcher<'static> {
+        TwoWaySearcher {
+            needle: self.needle.into_owned(),
+            fwd: self.fwd,
+            rev: self.rev,
+        }
+    }
+
+    /// Returns the needle that this searcher searches for.
+    pub fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+}
 
 /// Two-Way search in the forward direction.
 #[derive(Clone, Copy, Debug)]
@@ -74,6 +179,42 @@ struct TwoWay {
     shift: Shift,
 }
 
+/// Return the smallest period of the given needle. That is, the smallest
+/// `p >= 1` such that `needle[i] == needle[i + p]` for all valid `i`. An
+/// empty needle has period `0`.
+///
+/// This uses the critical factorization computed for forward Two-Way
+/// searches. When the needle falls into the "small period" case, its period
+/// is known exactly. Otherwise, only a lower bound is known, and each
+/// candidate from there is checked in turn. In the latter case the period is
+/// at least half the length of the needle, so this takes quadratic time in
+/// the worst case.
+pub(crate) fn period(needle: &[u8]) -> usize {
+    if needle.is_empty() {
+        return 0;
+    }
+    let (period_lower_bound, critical_pos) = factorize_forward(needle);
+    match Shift::forward(needle, period_lower_bound, critical_pos) {
+        Shift::Small { period } => period,
+        Shift::Large { .. } => (period_lower_bound..needle.len())
+            .find(|&p| util::is_prefix(needle, &needle[p..]))
+            .unwrap_or(needle.len()),
+    }
+}
+
+/// Compute a critical factorization of a non-empty needle for forward
+/// searching. This returns a lower bound on the period of the needle along
+/// with the critical position.
+fn factorize_forward(needle: &[u8]) -> (usize, usize) {
+    let min_suffix = Suffix::forward(needle, SuffixKind::Minimal);
+    let max_suffix = Suffix::forward(needle, SuffixKind::Maximal);
+    if min_suffix.pos > max_suffix.pos {
+        (min_suffix.period, min_suffix.pos)
+    } else {
+        (max_suffix.period, max_suffix.pos)
+    }
+}
+
 impl Forward {
     /// Create a searcher that uses the Two-Way algorithm by searching forwards
     /// through any haystack.
@@ -83,18 +224,61 @@ impl Forward {
         }
 
         let byteset = ApproximateByteSet::new(needle);
-        let min_suffix = Suffix::forward(needle, SuffixKind::Minimal);
-        let max_suffix = Suffix::forward(needle, SuffixKind::Maximal);
-        let (period_lower_bound, critical_pos) =
-            if min_suffix.pos > max_suffix.pos {
-                (min_suffix.period, min_suffix.pos)
-            } else {
-                (max_suffix.period, max_suffix.pos)
-            };
+        let (period_lower_bound, critical_pos) = factorize_forward(needle);
         let shift = Shift::forward(needle, period_lower_bound, critical_pos);
         Forward(TwoWay { byteset, critical_pos, shift })
     }
 
+    /// Create a searcher from the pre-computed parts of a critical
+    /// factorization. No factorization is computed.
+    ///
+    /// This panics if the parts given are out of bounds for the needle given.
+    /// Otherwise, if the parts weren't computed for the needle given, then
+    /// search results are unspecified.
+    pub(crate) fn from_params(
+        needle: &[u8],
+        params: &TwoWayParams,
+    ) -> Forward {
+        let len = needle.len();
+        assert!(
+            params.critical_pos <= len,
+            "critical position {} is out of bounds for needle of length {}",
+            params.critical_pos,
+            len,
+        );
+        let shift = match params.shift {
+            TwoWayShift::Small { period } => Shift::Small { period },
+            TwoWayShift::Large { shift } => Shift::Large { shift },
+        };
+        let amount = match shift {
+            Shift::Small { period } => period,
+            Shift::Large { shift } => shift,
+        };
+        assert!(
+            1 <= amount && amount <= len,
+            "shift {} is out of bounds for needle of length {}",
+            amount,
+            len,
+        );
+        Forward(TwoWay {
+            byteset: ApproximateByteSet(params.byteset),
+            critical_pos: params.critical_pos,
+            shift,
+        })
+    }
+
+    /// Write the parts of this searcher's critical factorization to the
+    /// given params.
+    #[cfg(feature = "std")]
+    pub(crate) fn write_params(&self, params: &mut TwoWayParams) {
+        params.critical_pos = self.0.critical_pos;
+        params.byteset = self.0.byteset.0;
+        params.shift = match self.0.shift {
+            Shift::Small { period } => TwoWayShift::Small { period },
+            Shift::Large { shift } => TwoWayShift::Large { shift },
+        };
+    }
+
     /// Find the position of the first occurrence of this searcher's needle in
     /// the given haystack. If one does not exist, then return None.
     ///
@@ -266,29 +450,27 @@ impl Reverse {
     /// Find the position of the last occurrence of this searcher's needle
     /// in the given haystack. If one does not exist, then return None.
     ///
-    /// This will automatically initialize prefilter state. This should only
-    /// be used for one-off searches.
+    /// This accepts prefilter state that is useful when using the same
+    /// searcher multiple times, such as in an iterator. The prefilter, if
+    /// given, must be a reverse prefilter.
     ///
     /// Callers must guarantee that the needle is non-empty and its length is
     /// <= the haystack's length.
     #[inline(always)]
     pub(crate) fn rfind(
         &self,
+        pre: Option<&mut Pre<'_>>,
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
         debug_assert!(!needle.is_empty(), "needle should not be empty");
         debug_assert!(needle.len() <= haystack.len(), "haystack too short");
-        // For the reverse case, we don't use a prefilter. It's plausible that
-        // perhaps we should, but it's a lot of additional code to do it, and
-        // it's not clear that it's actually worth it. If you have a really
-        // compelling use case for this, please file an issue.
         match self.0.shift {
             Shift::Small { period } => {
-                self.rfind_small_imp(haystack, needle, period)
+                self.rfind_small_imp(pre, haystack, needle, period)
             }
             Shift::Large { shift } => {
-                self.rfind_large_imp(haystack, needle, shift)
+                self.rfind_large_imp(pre, haystack, needle, shift)
             }
         }
     }
@@ -297,19 +479,25 @@ impl Reverse {
     /// only useful for conveniently testing this substring implementation in
     /// isolation.
     #[cfg(test)]
-    fn rfind_general(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    fn rfind_general(
+        &self,
+        pre: Option<&mut Pre<'_>>,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
         if needle.is_empty() {
             Some(haystack.len())
         } else if haystack.len() < needle.len() {
             None
         } else {
-            self.rfind(haystack, needle)
+            self.rfind(pre, haystack, needle)
         }
     }
 
     #[inline(always)]
     fn rfind_small_imp(
         &self,
+        mut pre: Option<&mut Pre<'_>>,
         haystack: &[u8],
         needle: &[u8],
         period: usize,
@@ -318,6 +506,15 @@ impl Reverse {
         let mut pos = haystack.len();
         let mut shift = nlen;
         while pos >= nlen {
+            if let Some(pre) = pre.as_mut() {
+                if pre.should_call() {
+                    pos = pre.call(&haystack[..pos], needle)?;
+                    shift = nlen;
+                    if pos < nlen {
+                        return None;
+                    }
+                }
+            }
             if !self.0.byteset.contains(haystack[pos - nlen]) {
                 pos -= nlen;
                 shift = nlen;
@@ -348,6 +545,7 @@ impl Reverse {
     #[inline(always)]
     fn rfind_large_imp(
         &self,
+        mut pre: Option<&mut Pre<'_>>,
         haystack: &[u8],
         needle: &[u8],
         shift: usize,
@@ -355,6 +553,14 @@ impl Reverse {
         let nlen = needle.len();
         let mut pos = haystack.len();
         while pos >= nlen {
+            if let Some(pre) = pre.as_mut() {
+                if pre.should_call() {
+                    pos = pre.call(&haystack[..pos], needle)?;
+                    if pos < nlen {
+                        return None;
+                    }
+                }
+            }
             if !self.0.byteset.contains(haystack[pos - nlen]) {
                 pos -= nlen;
                 continue;
@@ -821,7 +1027,35 @@ mod tests {
         assert_suffix_max!("aaa", "aaa", 1);
     }
 
+    /// A naive but obviously correct implementation of `period`.
+    fn naive_period(needle: &[u8]) -> usize {
+        if needle.is_empty() {
+            return 0;
+        }
+        (1..=needle.len())
+            .find(|&p| needle[p..] == needle[..needle.len() - p])
+            .unwrap()
+    }
+
+    #[test]
+    fn period_simple() {
+        assert_eq!(0, period(b""));
+        assert_eq!(1, period(b"a"));
+        assert_eq!(1, period(b"aaaa"));
+        assert_eq!(2, period(b"ab"));
+        assert_eq!(2, period(b"abab"));
+        assert_eq!(3, period(b"abcabca"));
+        assert_eq!(3, period(b"aaba"));
+        assert_eq!(5, period(b"abczz"));
+    }
+
     quickcheck! {
+        fn qc_period(bytes: Vec<u8>) -> bool {
+            // Use a tiny alphabet so that periodic needles are common.
+            let bytes: Vec<u8> = bytes.iter().map(|&b| b % 3).collect();
+            period(&bytes) == naive_period(&bytes)
+        }
+
         fn qc_suffix_forward_maximal(bytes: Vec<u8>) -> bool {
             if bytes.is_empty() {
                 return true;
@@ -859,7 +1093,7 @@ mod simpletests {
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
-        Reverse::new(needle).rfind_general(haystack, needle)
+        Reverse::new(needle).rfind_general(None, haystack, needle)
     }
 
     define_memmem_simple_tests!(twoway_find, twoway_rfind);
@@ -876,3 +1110,20 @@ mod simpletests {
         assert_eq!(Some(0), rfind(haystack.as_bytes(), needle.as_bytes()));
     }
 }
+
+#[cfg(test)]
+mod searchertests {
+    use super::TwoWaySearcher;
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        TwoWaySearcher::new(needle).find(haystack)
+    }
+
+    fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        TwoWaySearcher::new(needle).rfind(haystack)
+    }
+
+    define_memmem_simple_tests!(find, rfind);
+    #[cfg(all(feature = "std", not(miri)))]
+    define_memmem_quickcheck_tests!(find, rfind);
+}
@@ -3,6 +3,30 @@
 // dynamic linker and call out to libc, which introduces a bit more latency
 // than we'd like.)
 
+/// A wrapper for formatting a byte string in a `Debug` impl.
+///
+/// The bytes are shown like a string literal, where any byte that isn't
+/// printable ASCII is escaped.
+pub(crate) struct DebugBytes<'a>(pub(crate) &'a [u8]);
+
+impl<'a> core::fmt::Debug for DebugBytes<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "\"")?;
+        for &b in self.0 {
+            // escape_default also escapes single quotes, which isn't
+            // necessary inside a double quoted string.
+            if b == b'\'' {
+                write!(f, "'")?;
+                continue;
+            }
+            for e in core::ascii::escape_default(b) {
+                write!(f, "{}", e as char)?;
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
 /// Returns true if and only if needle is a prefix of haystack.
 #[inline(always)]
 pub(crate) fn is_prefix(haystack: &[u8], needle: &[u8]) -> bool {
@@ -64,7 +64,10 @@ mod x86sse {
     }
 }
 
-#[cfg(all(feature = "std", target_arch = "x86_64"))]
+#[cfg(all(
+    any(feature = "std", target_feature = "avx2"),
+    target_arch = "x86_64"
+))]
 mod x86avx {
     use super::Vector;
     use core::arch::x86_64::*;
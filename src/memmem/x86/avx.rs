@@ -1,10 +1,10 @@
-#[cfg(not(feature = "std"))]
-pub(crate) use self::nostd::Forward;
-#[cfg(feature = "std")]
-pub(crate) use self::std::Forward;
+#[cfg(any(feature = "std", target_feature = "avx2"))]
+pub(crate) use self::imp::{Forward, Reverse};
+#[cfg(not(any(feature = "std", target_feature = "avx2")))]
+pub(crate) use self::stub::{Forward, Reverse};
 
-#[cfg(feature = "std")]
-mod std {
+#[cfg(any(feature = "std", target_feature = "avx2"))]
+mod imp {
     use core::arch::x86_64::{__m128i, __m256i};
 
     use crate::memmem::{genericsimd, NeedleInfo};
@@ -20,11 +20,19 @@ mod std {
         pub(crate) fn new(
             ninfo: &NeedleInfo,
             needle: &[u8],
+            max_needle_len: usize,
+            prefetch_distance: usize,
         ) -> Option<Forward> {
-            if !cfg!(memchr_runtime_avx) || !is_x86_feature_detected!("avx2") {
+            if !crate::memmem::x86::is_avx2_enabled() {
                 return None;
             }
-            genericsimd::Forward::new(ninfo, needle).map(Forward)
+            genericsimd::Forward::new(
+                ninfo,
+                needle,
+                max_needle_len,
+                prefetch_distance,
+            )
+            .map(Forward)
         }
 
         /// Returns the minimum length of haystack that is needed for this
@@ -47,6 +55,48 @@ mod std {
             unsafe { self.find_impl(haystack, needle) }
         }
 
+        /// Returns the number of bytes of padding that `find_padded` needs
+        /// after the end of the haystack.
+        #[inline(always)]
+        pub(crate) fn padding_len(&self) -> usize {
+            self.0.padding_len::<__m256i>()
+        }
+
+        /// Search `haystack[..len]`, reading the padding after it as needed.
+        /// This panics if there are fewer than `padding_len` bytes of
+        /// padding.
+        #[inline(always)]
+        pub(crate) fn find_padded(
+            &self,
+            haystack: &[u8],
+            len: usize,
+            needle: &[u8],
+        ) -> Option<usize> {
+            // SAFETY: The only way a Forward value can exist is if the avx2
+            // target feature is enabled. This is the only safety requirement
+            // for calling the genericsimd searcher.
+            unsafe { self.find_padded_impl(haystack, len, needle) }
+        }
+
+        /// The implementation of find_padded marked with the appropriate
+        /// target feature.
+        ///
+        /// # Safety
+        ///
+        /// Callers must ensure that the avx2 CPU feature is enabled in the
+        /// current environment.
+        #[target_feature(enable = "avx2")]
+        unsafe fn find_padded_impl(
+            &self,
+            haystack: &[u8],
+            len: usize,
+            needle: &[u8],
+        ) -> Option<usize> {
+            genericsimd::fwd_find_padded::<__m256i>(
+                &self.0, haystack, len, needle,
+            )
+        }
+
         /// The implementation of find marked with the appropriate target
         /// feature.
         ///
@@ -67,12 +117,74 @@ mod std {
             }
         }
     }
+
+    /// An AVX accelerated vectorized reverse substring search routine that
+    /// only works on small needles.
+    #[derive(Clone, Copy, Debug)]
+    pub(crate) struct Reverse(genericsimd::Reverse);
+
+    impl Reverse {
+        /// Create a new "generic simd" reverse searcher. If one could not be
+        /// created from the given inputs, then None is returned.
+        pub(crate) fn new(
+            ninfo: &NeedleInfo,
+            needle: &[u8],
+            max_needle_len: usize,
+        ) -> Option<Reverse> {
+            if !crate::memmem::x86::is_avx2_enabled() {
+                return None;
+            }
+            genericsimd::Reverse::new(ninfo, needle, max_needle_len)
+                .map(Reverse)
+        }
+
+        /// Returns the minimum length of haystack that is needed for this
+        /// searcher to work. Passing a haystack with a length smaller than
+        /// this will cause `rfind` to panic.
+        #[inline(always)]
+        pub(crate) fn min_haystack_len(&self) -> usize {
+            self.0.min_haystack_len::<__m128i>()
+        }
+
+        #[inline(always)]
+        pub(crate) fn rfind(
+            &self,
+            haystack: &[u8],
+            needle: &[u8],
+        ) -> Option<usize> {
+            // SAFETY: The only way a Reverse value can exist is if the avx2
+            // target feature is enabled. This is the only safety requirement
+            // for calling the genericsimd searcher.
+            unsafe { self.rfind_impl(haystack, needle) }
+        }
+
+        /// The implementation of rfind marked with the appropriate target
+        /// feature.
+        ///
+        /// # Safety
+        ///
+        /// Callers must ensure that the avx2 CPU feature is enabled in the
+        /// current environment.
+        #[target_feature(enable = "avx2")]
+        unsafe fn rfind_impl(
+            &self,
+            haystack: &[u8],
+            needle: &[u8],
+        ) -> Option<usize> {
+            if haystack.len() < self.0.min_haystack_len::<__m256i>() {
+                genericsimd::rev_find::<__m128i>(&self.0, haystack, needle)
+            } else {
+                genericsimd::rev_find::<__m256i>(&self.0, haystack, needle)
+            }
+        }
+    }
 }
 
-// We still define the avx "forward" type on nostd to make caller code a bit
-// simpler. This avoids needing a lot more conditional compilation.
-#[cfg(not(feature = "std"))]
-mod nostd {
+// We still define the avx "forward" and "reverse" types on nostd (unless AVX2
+// is enabled at compile time) to make caller code a bit simpler. This avoids
+// needing a lot more conditional compilation.
+#[cfg(not(any(feature = "std", target_feature = "avx2")))]
+mod stub {
     use crate::memmem::NeedleInfo;
 
     #[derive(Clone, Copy, Debug)]
@@ -80,8 +192,10 @@ mod nostd {
 
     impl Forward {
         pub(crate) fn new(
-            ninfo: &NeedleInfo,
-            needle: &[u8],
+            _ninfo: &NeedleInfo,
+            _needle: &[u8],
+            _max_needle_len: usize,
+            _prefetch_distance: usize,
         ) -> Option<Forward> {
             None
         }
@@ -92,8 +206,46 @@ mod nostd {
 
         pub(crate) fn find(
             &self,
-            haystack: &[u8],
-            needle: &[u8],
+            _haystack: &[u8],
+            _needle: &[u8],
+        ) -> Option<usize> {
+            unreachable!()
+        }
+
+        pub(crate) fn padding_len(&self) -> usize {
+            unreachable!()
+        }
+
+        pub(crate) fn find_padded(
+            &self,
+            _haystack: &[u8],
+            _len: usize,
+            _needle: &[u8],
+        ) -> Option<usize> {
+            unreachable!()
+        }
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub(crate) struct Reverse(());
+
+    impl Reverse {
+        pub(crate) fn new(
+            _ninfo: &NeedleInfo,
+            _needle: &[u8],
+            _max_needle_len: usize,
+        ) -> Option<Reverse> {
+            None
+        }
+
+        pub(crate) fn min_haystack_len(&self) -> usize {
+            unreachable!()
+        }
+
+        pub(crate) fn rfind(
+            &self,
+            _haystack: &[u8],
+            _needle: &[u8],
         ) -> Option<usize> {
             unreachable!()
         }
@@ -102,7 +254,9 @@ mod nostd {
 
 #[cfg(all(test, feature = "std", not(miri)))]
 mod tests {
-    use crate::memmem::{prefilter::PrefilterState, NeedleInfo};
+    use crate::memmem::{
+        genericsimd::MAX_NEEDLE_LEN, prefilter::PrefilterState, NeedleInfo,
+    };
 
     fn find(
         _: &mut PrefilterState,
@@ -110,7 +264,9 @@ mod tests {
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
-        super::Forward::new(ninfo, needle).unwrap().find(haystack, needle)
+        super::Forward::new(ninfo, needle, MAX_NEEDLE_LEN, 0)
+            .unwrap()
+            .find(haystack, needle)
     }
 
     #[test]
@@ -128,7 +284,12 @@ mod tests {
                 // filter our tests such that Forward::new will be guaranteed
                 // to succeed. (And also remove tests with a haystack that is
                 // too small.)
-                let fwd = match super::Forward::new(&t.ninfo, &t.needle) {
+                let fwd = match super::Forward::new(
+                    &t.ninfo,
+                    &t.needle,
+                    MAX_NEEDLE_LEN,
+                    0,
+                ) {
                     None => return false,
                     Some(fwd) => fwd,
                 };
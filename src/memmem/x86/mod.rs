@@ -1,2 +1,107 @@
 pub(crate) mod avx;
 pub(crate) mod sse;
+
+/// Returns true if the AVX2 routines may be used in the current environment.
+///
+/// With std, support for AVX2 is detected at runtime. Without it, this is
+/// only defined when AVX2 is enabled at compile time, e.g., with
+/// `-C target-feature=+avx2`. With std, this also returns false once AVX2
+/// has been avoided with `prefer_avoid_avx`.
+///
+/// In tests, this also respects any limit set by `force::with_max_isa`.
+#[cfg(any(feature = "std", target_feature = "avx2"))]
+pub(crate) fn is_avx2_enabled() -> bool {
+    #[cfg(all(test, feature = "std"))]
+    {
+        if !force::allows(force::Isa::Avx2) {
+            return false;
+        }
+    }
+    #[cfg(feature = "std")]
+    let available =
+        is_x86_feature_detected!("avx2") && !crate::memchr::is_avx_avoided();
+    #[cfg(not(feature = "std"))]
+    let available = true;
+    cfg!(memchr_runtime_avx) && available
+}
+
+/// Returns true if the SSE2 routines may be used in the current environment.
+///
+/// In tests, this also respects any limit set by `force::with_max_isa`.
+pub(crate) fn is_sse2_enabled() -> bool {
+    #[cfg(all(test, feature = "std"))]
+    {
+        if !force::allows(force::Isa::Sse2) {
+            return false;
+        }
+    }
+    cfg!(memchr_runtime_sse2)
+}
+
+/// A test-only hook for limiting which vector routines are selected when a
+/// searcher is built, regardless of what the CPU supports.
+///
+/// This can only ever *disable* routines. Forcing the AVX2 routines on a CPU
+/// that doesn't support them would be undefined behavior, so asking for AVX2
+/// on such a CPU still gets you SSE2.
+///
+/// The limit is per-thread, so that tests running in parallel don't affect
+/// one another. It is consulted only when a searcher is constructed, since
+/// that's when its routines are picked.
+#[cfg(all(test, feature = "std"))]
+pub(crate) mod force {
+    use std::cell::Cell;
+
+    /// A family of routines, ordered from least to most capable.
+    #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+    pub(crate) enum Isa {
+        Fallback,
+        Sse2,
+        Avx2,
+    }
+
+    /// All of the families, for tests that want to run against each one.
+    pub(crate) const ALL: &[Isa] = &[Isa::Fallback, Isa::Sse2, Isa::Avx2];
+
+    std::thread_local! {
+        static MAX_ISA: Cell<Isa> = Cell::new(Isa::Avx2);
+    }
+
+    /// Run `f` such that no searcher built by it on this thread uses
+    /// routines more capable than `isa`.
+    pub(crate) fn with_max_isa<T, F: FnOnce() -> T>(isa: Isa, f: F) -> T {
+        struct Restore(Isa);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                MAX_ISA.with(|max| max.set(self.0));
+            }
+        }
+
+        let _restore = Restore(MAX_ISA.with(|max| max.replace(isa)));
+        f()
+    }
+
+    /// Returns true if routines from the given family may be selected.
+    pub(super) fn allows(isa: Isa) -> bool {
+        MAX_ISA.with(|max| isa <= max.get())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::is_avx2_enabled;
+
+    #[test]
+    fn prefer_avoid_avx() {
+        // This flips a global setting, so any searcher built concurrently by
+        // another test might pick SSE2 instead of AVX2. That's still correct,
+        // so it's only a (brief) loss of coverage.
+        let detected = is_avx2_enabled();
+        crate::prefer_avoid_avx(true);
+        let avoided = is_avx2_enabled();
+        crate::prefer_avoid_avx(false);
+        assert!(!avoided);
+        assert_eq!(detected, is_avx2_enabled());
+    }
+}
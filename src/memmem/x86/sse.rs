@@ -10,11 +10,22 @@ pub(crate) struct Forward(genericsimd::Forward);
 impl Forward {
     /// Create a new "generic simd" forward searcher. If one could not be
     /// created from the given inputs, then None is returned.
-    pub(crate) fn new(ninfo: &NeedleInfo, needle: &[u8]) -> Option<Forward> {
-        if !cfg!(memchr_runtime_sse2) {
+    pub(crate) fn new(
+        ninfo: &NeedleInfo,
+        needle: &[u8],
+        max_needle_len: usize,
+        prefetch_distance: usize,
+    ) -> Option<Forward> {
+        if !crate::memmem::x86::is_sse2_enabled() {
             return None;
         }
-        genericsimd::Forward::new(ninfo, needle).map(Forward)
+        genericsimd::Forward::new(
+            ninfo,
+            needle,
+            max_needle_len,
+            prefetch_distance,
+        )
+        .map(Forward)
     }
 
     /// Returns the minimum length of haystack that is needed for this searcher
@@ -36,6 +47,45 @@ impl Forward {
         unsafe { self.find_impl(haystack, needle) }
     }
 
+    /// Returns the number of bytes of padding that `find_padded` needs after
+    /// the end of the haystack.
+    #[inline(always)]
+    pub(crate) fn padding_len(&self) -> usize {
+        self.0.padding_len::<__m128i>()
+    }
+
+    /// Search `haystack[..len]`, reading the padding after it as needed.
+    /// This panics if there are fewer than `padding_len` bytes of padding.
+    #[inline(always)]
+    pub(crate) fn find_padded(
+        &self,
+        haystack: &[u8],
+        len: usize,
+        needle: &[u8],
+    ) -> Option<usize> {
+        // SAFETY: sse2 is enabled on all x86_64 targets, so this is always
+        // safe to call.
+        unsafe { self.find_padded_impl(haystack, len, needle) }
+    }
+
+    /// The implementation of find_padded marked with the appropriate target
+    /// feature.
+    ///
+    /// # Safety
+    ///
+    /// This is safe to call in all cases since sse2 is guaranteed to be part
+    /// of x86_64. It is marked as unsafe because of the target feature
+    /// attribute.
+    #[target_feature(enable = "sse2")]
+    unsafe fn find_padded_impl(
+        &self,
+        haystack: &[u8],
+        len: usize,
+        needle: &[u8],
+    ) -> Option<usize> {
+        genericsimd::fwd_find_padded::<__m128i>(&self.0, haystack, len, needle)
+    }
+
     /// The implementation of find marked with the appropriate target feature.
     ///
     /// # Safety
@@ -53,9 +103,67 @@ impl Forward {
     }
 }
 
+/// An SSE accelerated vectorized reverse substring search routine that only
+/// works on small needles.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Reverse(genericsimd::Reverse);
+
+impl Reverse {
+    /// Create a new "generic simd" reverse searcher. If one could not be
+    /// created from the given inputs, then None is returned.
+    pub(crate) fn new(
+        ninfo: &NeedleInfo,
+        needle: &[u8],
+        max_needle_len: usize,
+    ) -> Option<Reverse> {
+        if !crate::memmem::x86::is_sse2_enabled() {
+            return None;
+        }
+        genericsimd::Reverse::new(ninfo, needle, max_needle_len).map(Reverse)
+    }
+
+    /// Returns the minimum length of haystack that is needed for this searcher
+    /// to work. Passing a haystack with a length smaller than this will cause
+    /// `rfind` to panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len(&self) -> usize {
+        self.0.min_haystack_len::<__m128i>()
+    }
+
+    #[inline(always)]
+    pub(crate) fn rfind(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        // SAFETY: sse2 is enabled on all x86_64 targets, so this is always
+        // safe to call.
+        unsafe { self.rfind_impl(haystack, needle) }
+    }
+
+    /// The implementation of rfind marked with the appropriate target
+    /// feature.
+    ///
+    /// # Safety
+    ///
+    /// This is safe to call in all cases since sse2 is guaranteed to be part
+    /// of x86_64. It is marked as unsafe because of the target feature
+    /// attribute.
+    #[target_feature(enable = "sse2")]
+    unsafe fn rfind_impl(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        genericsimd::rev_find::<__m128i>(&self.0, haystack, needle)
+    }
+}
+
 #[cfg(all(test, feature = "std", not(miri)))]
 mod tests {
-    use crate::memmem::{prefilter::PrefilterState, NeedleInfo};
+    use crate::memmem::{
+        genericsimd::MAX_NEEDLE_LEN, prefilter::PrefilterState, NeedleInfo,
+    };
 
     fn find(
         _: &mut PrefilterState,
@@ -63,7 +171,9 @@ mod tests {
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
-        super::Forward::new(ninfo, needle).unwrap().find(haystack, needle)
+        super::Forward::new(ninfo, needle, MAX_NEEDLE_LEN, 0)
+            .unwrap()
+            .find(haystack, needle)
     }
 
     #[test]
@@ -78,7 +188,12 @@ mod tests {
                 // filter our tests such that Forward::new will be guaranteed
                 // to succeed. (And also remove tests with a haystack that is
                 // too small.)
-                let fwd = match super::Forward::new(&t.ninfo, &t.needle) {
+                let fwd = match super::Forward::new(
+                    &t.ninfo,
+                    &t.needle,
+                    MAX_NEEDLE_LEN,
+                    0,
+                ) {
                     None => return false,
                     Some(fwd) => fwd,
                 };
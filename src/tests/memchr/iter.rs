@@ -1,6 +1,9 @@
 use quickcheck::quickcheck;
 
-use crate::{tests::memchr::testdata::memchr_tests, Memchr, Memchr2, Memchr3};
+use crate::{
+    memrchr_batch, memrchr_iter, tests::memchr::testdata::memchr_tests,
+    Memchr, Memchr2, Memchr3, MemchrRuns, MemchrSplit, MemchrSplitIndices,
+};
 
 #[test]
 fn memchr1_iter() {
@@ -48,7 +51,120 @@ fn memrchr3_iter() {
     }
 }
 
+#[test]
+fn memchr_iters_are_fused() {
+    fn assert_fused<I: std::iter::FusedIterator<Item = usize>>(mut it: I) {
+        for _ in it.by_ref() {}
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next());
+    }
+
+    let haystack = b"abcabcabc";
+    assert_fused(Memchr::new(b'a', haystack));
+    assert_fused(Memchr2::new(b'a', b'b', haystack));
+    assert_fused(Memchr3::new(b'a', b'b', b'c', haystack));
+    assert_fused(Memchr::new(b'a', haystack).rev());
+    assert_fused(Memchr2::new(b'a', b'b', haystack).rev());
+    assert_fused(Memchr3::new(b'a', b'b', b'c', haystack).rev());
+
+    let mut runs = MemchrRuns::new(b'a', b"aabaa");
+    for _ in runs.by_ref() {}
+    assert_eq!(None, runs.next());
+    assert_eq!(None, runs.next());
+
+    let mut split = MemchrSplit::new(b'a', b"aabaa");
+    for _ in split.by_ref() {}
+    assert_eq!(None, split.next());
+    assert_eq!(None, split.next_back());
+}
+
+#[test]
+fn memchr_split_edges() {
+    let split: Vec<&[u8]> = MemchrSplit::new(b',', b"").collect();
+    assert_eq!(vec![&b""[..]], split);
+    let split: Vec<&[u8]> = MemchrSplit::new(b',', b",").collect();
+    assert_eq!(vec![&b""[..], b""], split);
+    let split: Vec<&[u8]> = MemchrSplit::new(b',', b"abc").rev().collect();
+    assert_eq!(vec![&b"abc"[..]], split);
+}
+
+#[test]
+fn memchr_split_indices_edges() {
+    let split: Vec<(usize, &[u8])> =
+        MemchrSplitIndices::new(b',', b"").collect();
+    assert_eq!(vec![(0, &b""[..])], split);
+    let split: Vec<(usize, &[u8])> =
+        MemchrSplitIndices::new(b',', b",a,").collect();
+    assert_eq!(vec![(0, &b""[..]), (1, b"a"), (3, b"")], split);
+    let split: Vec<(usize, &[u8])> =
+        MemchrSplitIndices::new(b',', b",a,").rev().collect();
+    assert_eq!(vec![(3, &b""[..]), (1, b"a"), (0, b"")], split);
+}
+
+#[test]
+fn memchr_runs_long() {
+    // Runs spanning several vectors, and ending at every possible offset.
+    for len in 0..150 {
+        let mut haystack = vec![b'x'; 5];
+        haystack.resize(5 + len, b'\t');
+        haystack.push(b'y');
+        let runs: Vec<(usize, usize)> =
+            MemchrRuns::new(b'\t', &haystack).collect();
+        let expected = if len == 0 { vec![] } else { vec![(5, len)] };
+        assert_eq!(expected, runs);
+
+        haystack.pop();
+        let runs: Vec<(usize, usize)> =
+            MemchrRuns::new(b'\t', &haystack).collect();
+        assert_eq!(expected, runs);
+    }
+}
+
+#[test]
+fn memrchr_batch_dense() {
+    // Every other byte matches, so each window's mask has many bits, and
+    // batches of every size stop partway through a window.
+    let haystack: Vec<u8> = (0..300).map(|i| (i % 2) as u8).collect();
+    let expected: Vec<usize> = memrchr_iter(1, &haystack).collect();
+    for size in 1..100 {
+        let mut it = Memchr::new(1, &haystack);
+        let mut out = vec![0; size];
+        let mut got = vec![];
+        loop {
+            let n = it.next_back_batch(&mut out);
+            got.extend_from_slice(&out[..n]);
+            if n < size {
+                break;
+            }
+        }
+        assert_eq!(expected, got, "batch size: {}", size);
+    }
+    assert_eq!(0, memrchr_batch(1, &haystack, &mut []));
+}
+
 quickcheck! {
+    fn qc_memrchr_batch_matches_iter(
+        needle: u8, data: Vec<u8>, size: u8, front: u8
+    ) -> bool {
+        // Take some positions from the front first, to check that batches
+        // and plain iteration mix.
+        let size = size as usize % 16 + 1;
+        let mut it = Memchr::new(needle, &data);
+        let mut got: Vec<usize> =
+            it.by_ref().take(front as usize % 4).collect();
+        let mut out = vec![0; size];
+        loop {
+            let n = it.next_back_batch(&mut out);
+            got.extend_from_slice(&out[..n]);
+            if n < size {
+                break;
+            }
+        }
+        let front = core::cmp::min(front as usize % 4, got.len());
+        got[front..].reverse();
+        got.iter().cloned().eq(positions1(needle, &data))
+    }
+
     fn qc_memchr_double_ended_iter(
         needle: u8, data: Vec<u8>, take_side: Vec<bool>
     ) -> bool {
@@ -137,6 +253,53 @@ quickcheck! {
         answer.rev().eq(Memchr3::new(needle1, needle2, needle3, &data).rev())
     }
 
+    fn qc_memchr_runs(data: Vec<u8>) -> bool {
+        // Use a tiny alphabet so that runs are common.
+        let data: Vec<u8> = data.iter().map(|&b| b % 2).collect();
+        let mut expected = vec![];
+        for i in positions1(0, &data) {
+            match expected.last_mut() {
+                Some(&mut (start, ref mut len)) if start + *len == i => {
+                    *len += 1;
+                }
+                _ => expected.push((i, 1)),
+            }
+        }
+        MemchrRuns::new(0, &data).eq(expected)
+    }
+
+    fn qc_memchr_split(data: Vec<u8>, take_side: Vec<bool>) -> bool {
+        // Use a tiny alphabet so that empty subslices are common.
+        let data: Vec<u8> = data.iter().map(|&b| b % 3).collect();
+        let mut take_side = take_side;
+        if take_side.is_empty() { take_side.push(true) };
+
+        let expected: Vec<&[u8]> = data.split(|&b| b == 0).collect();
+        let found = double_ended_take(
+            MemchrSplit::new(0, &data), take_side.iter().cycle().cloned());
+        MemchrSplit::new(0, &data).eq(expected.iter().cloned())
+            && found == expected
+    }
+
+    fn qc_memchr_split_indices(data: Vec<u8>, take_side: Vec<bool>) -> bool {
+        // Use a tiny alphabet so that empty subslices are common.
+        let data: Vec<u8> = data.iter().map(|&b| b % 3).collect();
+        let mut take_side = take_side;
+        if take_side.is_empty() { take_side.push(true) };
+
+        let start = data.as_ptr() as usize;
+        let expected: Vec<(usize, &[u8])> = data
+            .split(|&b| b == 0)
+            .map(|s| (s.as_ptr() as usize - start, s))
+            .collect();
+        let found = double_ended_take(
+            MemchrSplitIndices::new(0, &data),
+            take_side.iter().cycle().cloned(),
+        );
+        MemchrSplitIndices::new(0, &data).eq(expected.iter().cloned())
+            && found == expected
+    }
+
     fn qc_memchr1_iter_size_hint(data: Vec<u8>) -> bool {
         // test that the size hint is within reasonable bounds
         let needle = 0;
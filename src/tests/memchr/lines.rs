@@ -0,0 +1,99 @@
+use quickcheck::quickcheck;
+
+use crate::LineStreamer;
+
+/// Run the given chunks through a line splitter and collect every line.
+fn lines(strip_cr: bool, chunks: &[&[u8]]) -> Vec<Vec<u8>> {
+    let mut lines = vec![];
+    let mut streamer = LineStreamer::new();
+    streamer.strip_cr(strip_cr);
+    for chunk in chunks {
+        streamer.push(chunk, |line| lines.push(line.to_vec()));
+    }
+    streamer.finish(|line| lines.push(line.to_vec()));
+    assert!(streamer.partial().is_empty());
+    lines
+}
+
+#[test]
+fn line_streamer_simple() {
+    assert!(lines(false, &[]).is_empty());
+    assert!(lines(false, &[b""]).is_empty());
+    assert_eq!(vec![b"".to_vec()], lines(false, &[b"\n"]));
+    assert_eq!(vec![b"a".to_vec()], lines(false, &[b"a"]));
+    assert_eq!(vec![b"a".to_vec()], lines(false, &[b"a\n"]));
+    assert_eq!(
+        vec![b"ab".to_vec(), b"c".to_vec()],
+        lines(false, &[b"a", b"", b"b\nc"]),
+    );
+}
+
+#[test]
+fn line_streamer_strip_cr() {
+    assert_eq!(
+        vec![b"a\r".to_vec(), b"b".to_vec(), b"c\r".to_vec()],
+        lines(false, &[b"a\r\nb\nc\r"]),
+    );
+    assert_eq!(
+        vec![b"a".to_vec(), b"b".to_vec(), b"c\r".to_vec()],
+        lines(true, &[b"a\r\nb\nc\r"]),
+    );
+    // The \r and \n are in different chunks.
+    assert_eq!(
+        vec![b"a".to_vec(), b"\rb".to_vec()],
+        lines(true, &[b"a\r", b"\n\rb\r", b"\n"]),
+    );
+}
+
+#[test]
+fn line_streamer_partial() {
+    let mut streamer = LineStreamer::new();
+    streamer.push(b"abc\nde", |_| {});
+    assert_eq!(b"de", streamer.partial());
+    streamer.push(b"f", |_| {});
+    assert_eq!(b"def", streamer.partial());
+    streamer.push(b"\n", |line| assert_eq!(b"def", line));
+    assert_eq!(b"", streamer.partial());
+}
+
+quickcheck! {
+    fn qc_line_streamer_matches_split(
+        data: Vec<u8>,
+        splits: Vec<usize>,
+        strip_cr: bool
+    ) -> bool {
+        // Use a tiny alphabet so that terminators are common.
+        let data: Vec<u8> = data
+            .iter()
+            .map(|&b| [b'a', b'\r', b'\n'][b as usize % 3])
+            .collect();
+        let mut splits: Vec<usize> =
+            splits.iter().map(|&i| i % (data.len() + 1)).collect();
+        splits.sort();
+        let mut chunks = vec![];
+        let mut start = 0;
+        for &end in &splits {
+            chunks.push(&data[start..end]);
+            start = end;
+        }
+        chunks.push(&data[start..]);
+
+        let mut expected: Vec<Vec<u8>> =
+            data.split(|&b| b == b'\n').map(|line| line.to_vec()).collect();
+        // The final element from split is the unterminated final line, which
+        // is only reported if it's non-empty.
+        if expected.last() == Some(&vec![]) {
+            expected.pop();
+        }
+        if strip_cr {
+            let n = expected.len();
+            let terminated = n - (!data.ends_with(b"\n") && n > 0) as usize;
+            for line in &mut expected[..terminated] {
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+            }
+        }
+        lines(strip_cr, &chunks) == expected
+    }
+}
@@ -1,10 +1,18 @@
 use quickcheck::quickcheck;
 
 use crate::{
+    find_byte_in, find_u16_be, find_u16_le, find_u32_be, find_u32_le, is_all,
     memchr,
     memchr::{fallback, naive},
-    memchr2, memchr3, memrchr, memrchr2, memrchr3,
+    memchr16, memchr2, memchr2_count, memchr2_which, memchr3, memchr3_count,
+    memchr3_which, memchr_byteset, memchr_cancellable, memchr_classify,
+    memchr_dual, memchr_grid, memchr_high_bit, memchr_iter, memchr_mask,
+    memchr_masks, memchr_not_range, memchr_nth, memchr_nz, memchr_range,
+    memchr_ring, memchr_summary, memchr_unescaped, memcmp_idx, memcmp_ridx,
+    memrchr, memrchr2, memrchr3, memrchr_byteset, memrchr_nz, rfind_byte_in,
+    simd_available,
     tests::memchr::testdata::memchr_tests,
+    ByteSet, Side,
 };
 
 #[test]
@@ -132,3 +140,802 @@ quickcheck! {
         memrchr3(n1, n2, n3, &corpus) == naive::memrchr3(n1, n2, n3, &corpus)
     }
 }
+
+// The tests below pin down the semantics of searching for more than one
+// byte. Namely, the position reported is always the leftmost (or rightmost,
+// for reverse searches) position of any of the needles, even when needles
+// are repeated. We check both the public routines (which may dispatch to
+// vectorized implementations) and the fallback routines.
+
+fn min_opt(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(core::cmp::min(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn max_opt(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(core::cmp::max(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[test]
+fn memchr2_memchr3_duplicate_needles() {
+    for test in memchr_tests() {
+        test.one(false, |n1, h| memchr2(n1, n1, h));
+        test.one(false, |n1, h| memchr3(n1, n1, n1, h));
+        test.one(false, |n1, h| fallback::memchr2(n1, n1, h));
+        test.one(false, |n1, h| fallback::memchr3(n1, n1, n1, h));
+        test.one(true, |n1, h| memrchr2(n1, n1, h));
+        test.one(true, |n1, h| memrchr3(n1, n1, n1, h));
+        test.one(true, |n1, h| fallback::memrchr2(n1, n1, h));
+        test.one(true, |n1, h| fallback::memrchr3(n1, n1, n1, h));
+        test.two(false, |n1, n2, h| memchr3(n1, n2, n1, h));
+        test.two(false, |n1, n2, h| fallback::memchr3(n1, n2, n2, h));
+        test.two(true, |n1, n2, h| memrchr3(n1, n2, n1, h));
+        test.two(true, |n1, n2, h| fallback::memrchr3(n1, n2, n2, h));
+    }
+}
+
+quickcheck! {
+    fn qc_memchr2_is_leftmost(n1: u8, n2: u8, corpus: Vec<u8>) -> bool {
+        let expected = min_opt(memchr(n1, &corpus), memchr(n2, &corpus));
+        memchr2(n1, n2, &corpus) == expected
+            && fallback::memchr2(n1, n2, &corpus) == expected
+    }
+
+    fn qc_memchr3_is_leftmost(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        let expected = min_opt(
+            min_opt(memchr(n1, &corpus), memchr(n2, &corpus)),
+            memchr(n3, &corpus),
+        );
+        memchr3(n1, n2, n3, &corpus) == expected
+            && fallback::memchr3(n1, n2, n3, &corpus) == expected
+    }
+
+    fn qc_memrchr2_is_rightmost(n1: u8, n2: u8, corpus: Vec<u8>) -> bool {
+        let expected = max_opt(memrchr(n1, &corpus), memrchr(n2, &corpus));
+        memrchr2(n1, n2, &corpus) == expected
+            && fallback::memrchr2(n1, n2, &corpus) == expected
+    }
+
+    fn qc_memrchr3_is_rightmost(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        let expected = max_opt(
+            max_opt(memrchr(n1, &corpus), memrchr(n2, &corpus)),
+            memrchr(n3, &corpus),
+        );
+        memrchr3(n1, n2, n3, &corpus) == expected
+            && fallback::memrchr3(n1, n2, n3, &corpus) == expected
+    }
+
+    fn qc_memchr2_duplicate_needle(n1: u8, corpus: Vec<u8>) -> bool {
+        memchr2(n1, n1, &corpus) == memchr(n1, &corpus)
+            && memrchr2(n1, n1, &corpus) == memrchr(n1, &corpus)
+    }
+
+    fn qc_memchr3_duplicate_needle(n1: u8, n2: u8, corpus: Vec<u8>) -> bool {
+        memchr3(n1, n1, n1, &corpus) == memchr(n1, &corpus)
+            && memchr3(n1, n2, n2, &corpus) == memchr2(n1, n2, &corpus)
+            && memrchr3(n1, n1, n1, &corpus) == memrchr(n1, &corpus)
+            && memrchr3(n1, n2, n2, &corpus) == memrchr2(n1, n2, &corpus)
+    }
+
+    fn qc_memchr2_which(n1: u8, n2: u8, corpus: Vec<u8>) -> bool {
+        let expected = memchr2(n1, n2, &corpus).map(|i| (i, corpus[i]));
+        let got = memchr2_which(n1, n2, &corpus);
+        got == expected
+            && fallback::memchr2_which(n1, n2, &corpus) == expected
+            && naive::memchr2_which(n1, n2, &corpus) == expected
+            && match got {
+                None => true,
+                Some((_, b)) => b == n1 || b == n2,
+            }
+    }
+
+    fn qc_memchr3_which(n1: u8, n2: u8, n3: u8, corpus: Vec<u8>) -> bool {
+        let expected = memchr3(n1, n2, n3, &corpus).map(|i| (i, corpus[i]));
+        let got = memchr3_which(n1, n2, n3, &corpus);
+        got == expected
+            && fallback::memchr3_which(n1, n2, n3, &corpus) == expected
+            && naive::memchr3_which(n1, n2, n3, &corpus) == expected
+            && match got {
+                None => true,
+                Some((_, b)) => b == n1 || b == n2 || b == n3,
+            }
+    }
+}
+
+#[test]
+fn memchr_which_long() {
+    // Put each needle at every position in haystacks spanning several
+    // vectors, followed by a later match of a different needle, so that
+    // every path through the vectorized routines has to pick the right one.
+    for len in 1..100 {
+        for pos in 0..len {
+            for &needle in b"xyz".iter() {
+                let mut haystack = vec![b'a'; len];
+                haystack[pos] = needle;
+                if pos + 1 < len {
+                    haystack[len - 1] =
+                        if needle == b'x' { b'y' } else { b'x' };
+                }
+                let expected = Some((pos, needle));
+                let got = memchr3_which(b'x', b'y', b'z', &haystack);
+                assert_eq!(expected, got);
+                let got = fallback::memchr3_which(b'x', b'y', b'z', &haystack);
+                assert_eq!(expected, got);
+                if needle != b'z' {
+                    let got = memchr2_which(b'x', b'y', &haystack);
+                    assert_eq!(expected, got);
+                    let got = fallback::memchr2_which(b'x', b'y', &haystack);
+                    assert_eq!(expected, got);
+                }
+            }
+        }
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_nth(n1: u8, corpus: Vec<u8>, n: u8) -> bool {
+        let n = n as usize % 8;
+        memchr_nth(n1, &corpus, n) == memchr_iter(n1, &corpus).nth(n)
+    }
+}
+
+#[test]
+fn memchr_range_long() {
+    // Exercise every position in haystacks spanning several vectors.
+    for len in 1..100 {
+        for pos in 0..len {
+            let mut haystack = vec![b'a'; len];
+            haystack[pos] = b'\n';
+            assert_eq!(Some(pos), memchr_range(0x00, 0x1F, &haystack));
+            assert_eq!(Some(pos), memchr_not_range(0x20, 0x7E, &haystack));
+            assert_eq!(Some(pos), fallback::memchr_range(0, 0x1F, &haystack));
+            assert_eq!(
+                Some(pos),
+                fallback::memchr_not_range(0x20, 0x7E, &haystack)
+            );
+            assert_eq!(None, memchr_range(0x80, 0xFF, &haystack));
+            assert_eq!(None, memchr_not_range(0x00, 0xFF, &haystack));
+        }
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_range_matches_naive(
+        lo: u8, hi: u8, corpus: Vec<u8>
+    ) -> bool {
+        let expected = naive::memchr_range(lo, hi, &corpus);
+        let fallback_ok = lo > hi
+            || fallback::memchr_range(lo, hi, &corpus) == expected;
+        memchr_range(lo, hi, &corpus) == expected && fallback_ok
+    }
+
+    fn qc_memchr_not_range_matches_naive(
+        lo: u8, hi: u8, corpus: Vec<u8>
+    ) -> bool {
+        let expected = naive::memchr_not_range(lo, hi, &corpus);
+        let fallback_ok = lo > hi
+            || fallback::memchr_not_range(lo, hi, &corpus) == expected;
+        memchr_not_range(lo, hi, &corpus) == expected && fallback_ok
+    }
+}
+
+#[test]
+fn is_all_long() {
+    // Exercise every position in haystacks spanning several unrolled loops.
+    for len in 0..150 {
+        let mut haystack = vec![b'a'; len];
+        assert!(is_all(b'a', &haystack));
+        assert!(fallback::is_all(b'a', &haystack));
+        assert_eq!(len == 0, is_all(b'b', &haystack));
+        for pos in 0..len {
+            haystack[pos] = b'b';
+            assert!(!is_all(b'a', &haystack));
+            assert!(!fallback::is_all(b'a', &haystack));
+            haystack[pos] = b'a';
+        }
+    }
+}
+
+quickcheck! {
+    fn qc_is_all_matches_naive(n1: u8, corpus: Vec<u8>) -> bool {
+        let expected = naive::is_all(n1, &corpus);
+        is_all(n1, &corpus) == expected
+            && fallback::is_all(n1, &corpus) == expected
+    }
+}
+
+#[test]
+fn memchr_high_bit_long() {
+    // Exercise every position in haystacks spanning several unrolled loops,
+    // with a second non-ASCII byte after the first one.
+    for len in 0..150 {
+        let mut haystack = vec![0x7F; len];
+        assert_eq!(None, memchr_high_bit(&haystack));
+        assert_eq!(None, fallback::memchr_high_bit(&haystack));
+        for pos in 0..len {
+            haystack[pos] = 0x80;
+            haystack[len - 1] |= 0xC0;
+            assert_eq!(Some(pos), memchr_high_bit(&haystack));
+            assert_eq!(Some(pos), fallback::memchr_high_bit(&haystack));
+            haystack[len - 1] = 0x7F;
+            haystack[pos] = 0x7F;
+        }
+    }
+}
+
+#[test]
+fn memchr_unescaped_runs() {
+    // A quote after a run of n backslashes is escaped when n is odd. Put
+    // the run at each position in a haystack spanning a few vectors.
+    for len in 1..40 {
+        for n in 0..len {
+            for start in 0..len - n {
+                let mut haystack = vec![b'a'; len];
+                for b in &mut haystack[start..start + n] {
+                    *b = b'\\';
+                }
+                haystack[start + n] = b'"';
+                let expected = if n % 2 == 0 { Some(start + n) } else { None };
+                assert_eq!(
+                    expected,
+                    memchr_unescaped(b'"', b'\\', &haystack),
+                    "haystack: {:?}",
+                    haystack,
+                );
+            }
+        }
+    }
+    assert_eq!(Some(1), memchr_unescaped(b'x', b'x', b"axx"));
+    assert_eq!(None, memchr_unescaped(b'"', b'\\', b""));
+    assert_eq!(None, memchr_unescaped(b'"', b'\\', b"\\"));
+}
+
+quickcheck! {
+    fn qc_memchr_unescaped_matches_naive(corpus: Vec<u8>) -> bool {
+        // Use a tiny alphabet so that runs of escapes are common.
+        let corpus: Vec<u8> =
+            corpus.iter().map(|&b| b"ab\\\""[b as usize % 4]).collect();
+        let mut escapes = 0;
+        let mut expected = None;
+        for (i, &b) in corpus.iter().enumerate() {
+            if b == b'"' && escapes % 2 == 0 {
+                expected = Some(i);
+                break;
+            }
+            escapes = if b == b'\\' { escapes + 1 } else { 0 };
+        }
+        memchr_unescaped(b'"', b'\\', &corpus) == expected
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_high_bit_matches_naive(corpus: Vec<u8>) -> bool {
+        let expected = naive::memchr_high_bit(&corpus);
+        memchr_high_bit(&corpus) == expected
+            && fallback::memchr_high_bit(&corpus) == expected
+    }
+}
+
+#[test]
+fn find_byte_in_long() {
+    // Exercise every position in haystacks spanning several vectors, with
+    // sets of every size so that each strategy is used.
+    let set: Vec<u8> = (b'0'..=b'9').collect();
+    for len in 1..70 {
+        for pos in 0..len {
+            for setlen in 0..=set.len() {
+                let set = &set[..setlen];
+                let expected = if setlen == 0 { None } else { Some(pos) };
+                let mut haystack = vec![b'a'; len];
+                haystack[pos] = *set.last().unwrap_or(&b'0');
+                assert_eq!(expected, find_byte_in(&haystack, set));
+                assert_eq!(expected, rfind_byte_in(&haystack, set));
+                assert_eq!(expected, fallback::find_byte_in(&haystack, set));
+                assert_eq!(expected, fallback::rfind_byte_in(&haystack, set));
+            }
+        }
+    }
+}
+
+quickcheck! {
+    fn qc_find_byte_in_matches_naive(set: Vec<u8>, corpus: Vec<u8>) -> bool {
+        find_byte_in(&corpus, &set) == naive::find_byte_in(&corpus, &set)
+            && rfind_byte_in(&corpus, &set)
+                == naive::rfind_byte_in(&corpus, &set)
+    }
+}
+
+#[test]
+fn byteset_members() {
+    let mut set = ByteSet::default();
+    assert!(set.is_empty());
+    for &b in [0u8, 63, 64, 127, 128, 255, 64].iter() {
+        set.add(b);
+    }
+    assert_eq!(6, set.len());
+    assert!(!set.is_empty());
+    assert!(set.contains(255) && !set.contains(254));
+
+    let mut out = [0u8; 6];
+    assert_eq!(Some(6), set.write_members(&mut out));
+    assert_eq!([0, 63, 64, 127, 128, 255], out);
+    assert_eq!(None, set.write_members(&mut out[..5]));
+}
+
+quickcheck! {
+    fn qc_memchr_byteset_matches_naive(set: Vec<u8>, corpus: Vec<u8>) -> bool {
+        // Sets with more than 8 bytes use a different strategy, so make sure
+        // both kinds are common.
+        let set: Vec<u8> = set.iter().take(16).cloned().collect();
+        let byteset = ByteSet::new(&set);
+        memchr_byteset(&byteset, &corpus) == naive::find_byte_in(&corpus, &set)
+            && memrchr_byteset(&byteset, &corpus)
+                == naive::rfind_byte_in(&corpus, &set)
+    }
+}
+
+#[test]
+fn memchr_mask_lengths() {
+    for len in 0..300 {
+        let haystack: Vec<u8> = (0..len).map(|i| (i % 7) as u8).collect();
+        let mut expected = vec![0; (len + 63) / 64];
+        naive::memchr_mask(3, &haystack, &mut expected);
+
+        // Bits for positions that don't match must be cleared, and words
+        // beyond the bitset needed must not be touched.
+        let mut got = vec![!0; expected.len() + 1];
+        memchr_mask(3, &haystack, &mut got);
+        assert_eq!(expected[..], got[..expected.len()], "len: {}", len);
+        assert_eq!(!0, got[expected.len()]);
+
+        let mut got = vec![!0; expected.len()];
+        fallback::memchr_mask(3, &haystack, &mut got);
+        assert_eq!(expected, got, "len: {}", len);
+    }
+}
+
+#[test]
+fn memchr_masks_lengths() {
+    for len in 0..300 {
+        let haystack: Vec<u8> = (0..len).map(|i| (i % 7) as u8).collect();
+        let mut expected = vec![0; (len + 63) / 64];
+        naive::memchr_mask(3, &haystack, &mut expected);
+        let expected: Vec<(usize, u64)> = expected
+            .into_iter()
+            .enumerate()
+            .map(|(i, m)| (i * 64, m))
+            .collect();
+
+        let mut got = vec![];
+        memchr_masks(3, &haystack, |at, mask| got.push((at, mask)));
+        assert_eq!(expected, got, "len: {}", len);
+
+        let mut got = vec![];
+        fallback::memchr_masks(3, &haystack, |at, mask| got.push((at, mask)));
+        assert_eq!(expected, got, "len: {}", len);
+
+        let mut got = vec![];
+        naive::memchr_masks(3, &haystack, |at, mask| got.push((at, mask)));
+        assert_eq!(expected, got, "len: {}", len);
+    }
+}
+
+#[test]
+#[should_panic]
+fn memchr_mask_too_small() {
+    memchr_mask(b'a', &[b'a'; 65], &mut [0; 1]);
+}
+
+quickcheck! {
+    fn qc_memchr_mask_matches_iter(n1: u8, corpus: Vec<u8>) -> bool {
+        let mut got = vec![0; (corpus.len() + 63) / 64];
+        memchr_mask(n1, &corpus, &mut got);
+        let mut expected = vec![0u64; got.len()];
+        for i in memchr_iter(n1, &corpus) {
+            expected[i / 64] |= 1 << (i % 64);
+        }
+        got == expected
+    }
+}
+
+#[test]
+fn memchr_summary_lengths() {
+    for len in 0..300 {
+        let haystack: Vec<u8> = (0..len).map(|i| (i % 7) as u8).collect();
+        let expected = naive::memchr_summary(3, &haystack);
+        assert_eq!(expected, memchr_summary(3, &haystack), "len: {}", len);
+        assert_eq!(
+            expected,
+            fallback::memchr_summary(3, &haystack),
+            "len: {}",
+            len,
+        );
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_summary_matches_naive(n1: u8, corpus: Vec<u8>) -> bool {
+        let summary = memchr_summary(n1, &corpus);
+        summary.count() == memchr_iter(n1, &corpus).count()
+            && summary.first() == naive::memchr(n1, &corpus)
+            && summary.last() == naive::memrchr(n1, &corpus)
+    }
+}
+
+#[test]
+fn memchr_classify_lengths() {
+    let mut table = [0u8; 256];
+    table[b'x' as usize] = 0b01;
+    table[b'y' as usize] = 0b11;
+    for len in 0..40 {
+        for pos in 0..len {
+            let mut haystack = vec![b'a'; len];
+            haystack[pos] = b'y';
+            let got = memchr_classify(&table, &haystack, 0b10);
+            assert_eq!(Some((pos, 0b11)), got, "len: {}", len);
+            assert_eq!(None, memchr_classify(&table, &haystack, 0b100));
+        }
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_classify_matches_naive(
+        table: Vec<u8>,
+        corpus: Vec<u8>,
+        classes: u8
+    ) -> bool {
+        let mut t = [0u8; 256];
+        for (dst, &src) in t.iter_mut().zip(&table) {
+            *dst = src;
+        }
+        let expected = corpus
+            .iter()
+            .position(|&b| t[b as usize] & classes != 0)
+            .map(|i| (i, t[corpus[i] as usize]));
+        memchr_classify(&t, &corpus, classes) == expected
+    }
+}
+
+#[test]
+fn simd_available_matches_build() {
+    let expected = cfg!(all(
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        memchr_runtime_sse2,
+        not(miri),
+    ));
+    assert_eq!(expected, simd_available());
+}
+
+#[test]
+fn memchr_grid_partial_row() {
+    // The last row is only two bytes wide.
+    let grid = b"abcdefghij";
+    assert_eq!(Some((0, 0)), memchr_grid(b'a', grid, 4));
+    assert_eq!(Some((1, 3)), memchr_grid(b'h', grid, 4));
+    assert_eq!(Some((2, 1)), memchr_grid(b'j', grid, 4));
+    assert_eq!(None, memchr_grid(b'z', grid, 4));
+    assert_eq!(Some((9, 0)), memchr_grid(b'j', grid, 1));
+    assert_eq!(Some((0, 9)), memchr_grid(b'j', grid, 100));
+}
+
+#[test]
+#[should_panic]
+fn memchr_grid_zero_width() {
+    memchr_grid(b'a', b"abc", 0);
+}
+
+quickcheck! {
+    fn qc_memchr_nz_is_index_plus_one(n1: u8, corpus: Vec<u8>) -> bool {
+        let plus_one = |i: usize| i + 1;
+        memchr_nz(n1, &corpus).map(|i| i.get())
+            == memchr(n1, &corpus).map(plus_one)
+            && memrchr_nz(n1, &corpus).map(|i| i.get())
+                == memrchr(n1, &corpus).map(plus_one)
+    }
+}
+
+#[test]
+fn memchr_nz_uses_niche() {
+    use core::{mem::size_of, num::NonZeroUsize};
+
+    assert_eq!(size_of::<usize>(), size_of::<Option<NonZeroUsize>>());
+}
+
+#[test]
+fn memcmp_idx_long() {
+    // A single difference at every position, across lengths that exercise
+    // the word, vector and overlapping tail code paths.
+    for len in 0..70 {
+        let a = vec![b'x'; len];
+        assert_eq!(None, memcmp_idx(&a, &a));
+        for pos in 0..len {
+            let mut b = a.clone();
+            b[pos] = b'y';
+            assert_eq!(Some(pos), memcmp_idx(&a, &b));
+            assert_eq!(Some(pos), fallback::memcmp_idx(&a, &b));
+            // Only the first difference counts.
+            b[len - 1] = b'z';
+            assert_eq!(Some(pos), memcmp_idx(&a, &b));
+        }
+    }
+}
+
+quickcheck! {
+    fn qc_memcmp_idx_matches_naive(a: Vec<u8>, b: Vec<u8>) -> bool {
+        // Share a prefix so that the first difference isn't always at 0.
+        let mut b2 = a.clone();
+        b2.truncate(a.len() / 2);
+        b2.extend_from_slice(&b);
+        let len = core::cmp::min(a.len(), b2.len());
+        let expected = match naive::memcmp_idx(&a, &b2) {
+            None if a.len() != b2.len() => Some(len),
+            expected => expected,
+        };
+        memcmp_idx(&a, &b2) == expected
+            && fallback::memcmp_idx(&a[..len], &b2[..len])
+                == naive::memcmp_idx(&a, &b2)
+    }
+}
+
+#[test]
+fn memcmp_ridx_long() {
+    // A single difference at every position, across lengths that exercise
+    // the word, vector and overlapping head code paths.
+    for len in 0..70 {
+        let a = vec![b'x'; len];
+        assert_eq!(None, memcmp_ridx(&a, &a));
+        for pos in 0..len {
+            let mut b = a.clone();
+            b[pos] = b'y';
+            assert_eq!(Some(pos), memcmp_ridx(&a, &b));
+            assert_eq!(Some(pos), fallback::memcmp_ridx(&a, &b));
+            // Only the last difference counts.
+            b[0] = b'z';
+            assert_eq!(Some(pos), memcmp_ridx(&a, &b));
+        }
+    }
+}
+
+quickcheck! {
+    fn qc_memcmp_ridx_matches_naive(a: Vec<u8>, b: Vec<u8>) -> bool {
+        // Share a suffix so that the last difference isn't always at the
+        // end.
+        let len = core::cmp::min(a.len(), b.len());
+        let mut b2 = b[..len].to_vec();
+        b2[len / 2..].copy_from_slice(&a[len / 2..len]);
+        let a = &a[..len];
+        let expected = naive::memcmp_ridx(a, &b2);
+        memcmp_ridx(a, &b2) == expected
+            && fallback::memcmp_ridx(a, &b2) == expected
+    }
+}
+
+#[test]
+fn memchr16_long() {
+    // A single match at every position, across lengths that exercise the
+    // word, vector and overlapping tail code paths.
+    for len in 0..40 {
+        let haystack = vec![0x0101u16; len];
+        assert_eq!(None, memchr16(0x0001, &haystack));
+        assert_eq!(None, memchr16(0x0100, &haystack));
+        for pos in 0..len {
+            let mut haystack = haystack.clone();
+            haystack[pos] = 0x0001;
+            assert_eq!(Some(pos), memchr16(0x0001, &haystack));
+            assert_eq!(Some(pos), fallback::memchr16(0x0001, &haystack));
+            // Only the first match counts.
+            haystack[len - 1] = 0x0001;
+            assert_eq!(Some(pos), memchr16(0x0001, &haystack));
+        }
+    }
+}
+
+quickcheck! {
+    fn qc_memchr16_matches_naive(n1: u16, haystack: Vec<u16>) -> bool {
+        // Use a tiny alphabet in each byte so that matches are likely, and
+        // so that a match of only one byte of a unit is too.
+        let haystack: Vec<u16> =
+            haystack.iter().map(|&u| u & 0x0303).collect();
+        let n1 = n1 & 0x0303;
+        let expected = naive::memchr16(n1, &haystack);
+        memchr16(n1, &haystack) == expected
+            && fallback::memchr16(n1, &haystack) == expected
+    }
+}
+
+quickcheck! {
+    fn qc_find_uint_matches_naive(value: u32, haystack: Vec<u8>) -> bool {
+        // Use a tiny alphabet so that matches, and partial matches in the
+        // wrong byte order, are likely.
+        let haystack: Vec<u8> = haystack.iter().map(|&b| b & 0x01).collect();
+        let value = value & 0x01010101;
+        let find = |needle: &[u8]| {
+            haystack.windows(needle.len()).position(|w| w == needle)
+        };
+        let short = value as u16;
+        find_u16_le(short, &haystack) == find(&short.to_le_bytes())
+            && find_u16_be(short, &haystack) == find(&short.to_be_bytes())
+            && find_u32_le(value, &haystack) == find(&value.to_le_bytes())
+            && find_u32_be(value, &haystack) == find(&value.to_be_bytes())
+    }
+}
+
+#[test]
+fn find_uint_overlapping() {
+    let haystack = b"\x01\x01\x01\x02";
+    assert_eq!(Some(2), find_u16_le(0x0201, haystack));
+    assert_eq!(Some(0), find_u16_be(0x0101, haystack));
+    assert_eq!(Some(0), find_u32_be(0x01010102, haystack));
+    assert_eq!(None, find_u32_le(0x01010102, haystack));
+    assert_eq!(None, find_u32_le(0x01010102, &haystack[..3]));
+}
+
+#[test]
+fn memchr_count_long() {
+    // CRLF and bare LF line endings, at every offset into haystacks that
+    // span a few vectors, so that the scalar tail is exercised too.
+    for len in 0..80 {
+        let mut haystack = vec![b'a'; len];
+        let mut expected = 0;
+        for i in (0..len).step_by(3) {
+            haystack[i] = if i % 2 == 0 { b'\r' } else { b'\n' };
+            expected += 1;
+        }
+        assert_eq!(expected, memchr2_count(b'\r', b'\n', &haystack));
+        assert_eq!(expected, memchr3_count(b'\r', b'\n', b'z', &haystack));
+        assert_eq!(
+            len - expected,
+            memchr3_count(b'a', b'a', b'a', &haystack),
+            "len: {}",
+            len,
+        );
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_count_matches_naive(
+        n1: u8,
+        n2: u8,
+        n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        // Use a tiny alphabet so that the needles occur, and sometimes
+        // coincide.
+        let corpus: Vec<u8> = corpus.iter().map(|&b| b % 4).collect();
+        let (n1, n2, n3) = (n1 % 4, n2 % 4, n3 % 4);
+        memchr2_count(n1, n2, &corpus)
+            == naive::memchr2_count(n1, n2, &corpus)
+            && memchr3_count(n1, n2, n3, &corpus)
+                == naive::memchr3_count(n1, n2, n3, &corpus)
+            && fallback::memchr2_count(n1, n2, &corpus)
+                == naive::memchr2_count(n1, n2, &corpus)
+            && fallback::memchr3_count(n1, n2, n3, &corpus)
+                == naive::memchr3_count(n1, n2, n3, &corpus)
+    }
+}
+
+#[test]
+#[should_panic]
+fn memchr_cancellable_zero_chunk() {
+    let _ = memchr_cancellable(b'a', b"abc", 0, || true);
+}
+
+quickcheck! {
+    fn qc_memchr_cancellable(
+        n1: u8, corpus: Vec<u8>, chunk: u8, stop: u8
+    ) -> bool {
+        // Use a tiny alphabet so that matches are likely.
+        let corpus: Vec<u8> = corpus.iter().map(|&b| b % 4).collect();
+        let n1 = n1 % 4;
+        let chunk = chunk as usize % 8 + 1;
+        let stop = stop as usize % 8;
+        // The search is cancelled at the stop'th call, after this many bytes
+        // have been searched.
+        let limit = (stop + 1) * chunk;
+
+        let mut calls = 0;
+        let result = memchr_cancellable(n1, &corpus, chunk, || {
+            calls += 1;
+            calls <= stop
+        });
+        let completed = match memchr(n1, &corpus) {
+            Some(i) => i < limit,
+            None => corpus.len() <= limit,
+        };
+        if completed {
+            result == Ok(memchr(n1, &corpus))
+        } else {
+            result.map_err(|c| c.searched()) == Err(limit)
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn memchr_ring_head_out_of_bounds() {
+    memchr_ring(b'a', b"abc", 4);
+}
+
+quickcheck! {
+    fn qc_memchr_ring_matches_rotated(
+        n1: u8, corpus: Vec<u8>, head: usize
+    ) -> bool {
+        let head = head % (corpus.len() + 1);
+        let mut rotated = corpus.clone();
+        rotated.rotate_left(head);
+        memchr_ring(n1, &corpus, head) == memchr(n1, &rotated)
+    }
+}
+
+fn naive_memchr_dual(n1: u8, h1: &[u8], h2: &[u8]) -> Option<(usize, Side)> {
+    let len = core::cmp::max(h1.len(), h2.len());
+    (0..len).find_map(|i| {
+        if h1.get(i) == Some(&n1) {
+            Some((i, Side::First))
+        } else if h2.get(i) == Some(&n1) {
+            Some((i, Side::Second))
+        } else {
+            None
+        }
+    })
+}
+
+#[test]
+fn memchr_dual_simple() {
+    assert_eq!(None, memchr_dual(b'a', b"", b""));
+    assert_eq!(Some((0, Side::First)), memchr_dual(b'a', b"a", b"a"));
+    assert_eq!(Some((0, Side::Second)), memchr_dual(b'a', b"", b"a"));
+    assert_eq!(Some((1, Side::Second)), memchr_dual(b'a', b"zza", b"za"));
+
+    // Matches beyond the first chunk, and a match in one haystack that is
+    // preceded by a match in the other in an earlier chunk.
+    let mut h1 = vec![b'z'; 5000];
+    let mut h2 = vec![b'z'; 3000];
+    h1[4000] = b'a';
+    assert_eq!(Some((4000, Side::First)), memchr_dual(b'a', &h1, &h2));
+    h2[2999] = b'a';
+    assert_eq!(Some((2999, Side::Second)), memchr_dual(b'a', &h1, &h2));
+    h1[2999] = b'a';
+    assert_eq!(Some((2999, Side::First)), memchr_dual(b'a', &h1, &h2));
+}
+
+quickcheck! {
+    fn qc_memchr_dual_matches_naive(
+        n1: u8,
+        h1: Vec<u8>,
+        h2: Vec<u8>
+    ) -> bool {
+        memchr_dual(n1, &h1, &h2) == naive_memchr_dual(n1, &h1, &h2)
+    }
+
+    fn qc_memchr_dual_long_matches_naive(
+        n1: u8,
+        pos1: usize,
+        pos2: usize,
+        len1: usize,
+        len2: usize
+    ) -> bool {
+        let (len1, len2) = (len1 % 2048, len2 % 2048);
+        let mut h1 = vec![n1.wrapping_add(1); len1];
+        let mut h2 = vec![n1.wrapping_add(1); len2];
+        if len1 > 0 {
+            h1[pos1 % len1] = n1;
+        }
+        if len2 > 0 {
+            h2[pos2 % len2] = n1;
+        }
+        memchr_dual(n1, &h1, &h2) == naive_memchr_dual(n1, &h1, &h2)
+    }
+}
@@ -0,0 +1,59 @@
+// Regression tests for substring search, using inputs that were found to
+// trigger bugs (by quickcheck or by the fuzz targets in `fuzz/`).
+//
+// Every searcher is checked against a naive search by
+// `memmem::testutil::check`, so each entry only needs a needle and a
+// haystack. To add a failure found by the `memmem` or `memrmem` fuzz target,
+// split the crashing input the same way the target does (the first
+// `max(data[0], 1) % data.len()` bytes are the needle) and append it to
+// `CORPUS`.
+//
+// This requires the `test-util` feature, so run it with:
+//
+//     cargo test --features test-util --test regressions
+
+use memchr::memmem::testutil;
+
+/// Each entry is a (needle, haystack) pair.
+const CORPUS: &[(&[u8], &[u8])] = &[
+    // Failures caught by quickcheck.
+    (b"\x00\x15", b"\x00\x15\x15\x00"),
+    (b"\x00\x1e", b"\x1e\x00"),
+    // A needle whose rarest bytes are also its most repetitive ones.
+    (b"\xFF\xFF\xFE", b"\xFF\xFF\xFF\xFF\xFE\xFF\xFF\xFE\xFF"),
+    (b"\x80\x00\x80\x00", b"\x80\x00\x80\x00\x80\x00\x80\x00\x80"),
+    // Matches straddling 16 and 32 byte vector boundaries.
+    (b"zq", b"...............zq..............zq"),
+    (b"\xC0zq", b"..............\xC0zq.............\xC0zq.."),
+    // A match that ends exactly at the end of a haystack too short for
+    // the vectorized searchers.
+    (b"abcabd", b"abcabcabd"),
+    // Periodic needles, which take Two-Way's "small period" shift.
+    (b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab", &[b'a'; 100]),
+    (
+        b"abababababababababababababababababababababac",
+        b"ababababababababababababababababababababababababababac",
+    ),
+    // The empty needle and the empty haystack.
+    (b"", b""),
+    (b"", b"\xFF\xFE"),
+    (b"\xFF", b""),
+];
+
+#[test]
+fn corpus() {
+    for &(needle, haystack) in CORPUS {
+        testutil::check(haystack, needle);
+    }
+}
+
+#[test]
+fn corpus_cross() {
+    // Searching for each needle in every other haystack is cheap, and
+    // exercises near misses that the entries above don't.
+    for &(needle, _) in CORPUS {
+        for &(_, haystack) in CORPUS {
+            testutil::check(haystack, needle);
+        }
+    }
+}